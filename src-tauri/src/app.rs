@@ -1,25 +1,44 @@
 // Application lifecycle management for CCCS
 use crate::{
-    AppError, AppResult, 
+    AppError, AppResult, ClaudeDirInfo, ErrorLogEntry, DEFAULT_SWITCHING_GLYPH, ClaudeDirSource, FileStatusChanged, MonitoringStats, ProfileStatusKind,
+    ProfileSummary, StatusGlyphs, UserSettings, WindowGeometry,
     claude_detector::ClaudeDetector,
-    config_service::ConfigService,
+    error::ErrorLog,
+    config_service::{ConfigService, LIVE_PROFILE_NAME},
     tray_service::TrayService,
     monitor_service::MonitorService,
     settings_service::SettingsService,
     i18n_service::I18nService,
 };
-use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Listener, Manager};
 
+/// Hovers closer together than this only trigger one rescan
+const HOVER_RESCAN_DEBOUNCE: Duration = Duration::from_secs(5);
+/// Settings windows opened closer together than this only trigger one rescan
+const SETTINGS_OPEN_RESCAN_DEBOUNCE: Duration = Duration::from_secs(2);
+/// How long exit waits for a running command to release the app state before giving up on shutdown
+const SHUTDOWN_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set once shutdown has started, so the tray's exit and the ExitRequested run event don't both run it
+static SHUTDOWN_STARTED: AtomicBool = AtomicBool::new(false);
+
 pub struct App {
     config_service: Arc<Mutex<ConfigService>>,
     tray_service: Arc<Mutex<TrayService>>,
     monitor_service: Arc<Mutex<MonitorService>>,
     settings_service: Arc<Mutex<SettingsService>>,
     i18n_service: Arc<Mutex<I18nService>>,
+    error_log: Arc<Mutex<ErrorLog>>,
     app_handle: AppHandle,
     is_initialized: bool,
+    needs_claude_dir_selection: bool,
+    claude_dir_source: ClaudeDirSource,
+    // Profile that was active last session but no longer matches settings.json, found at startup
+    external_change: Option<String>,
 }
 
 impl App {
@@ -30,39 +49,87 @@ impl App {
         let settings_service = Arc::new(Mutex::new(SettingsService::new()?));
         let i18n_service = Arc::new(Mutex::new(I18nService::new()));
         
-        // Get settings for monitor interval
-        let monitor_interval = {
-            let settings = settings_service.lock().unwrap();
-            settings.get_current_settings().monitor_interval_minutes
-        };
+        // Get settings for monitoring and profile comparison
+        let current_settings = settings_service.lock().unwrap().get_current_settings().clone();
         
-        // Detect Claude directory immediately during construction
-        let claude_dir = Self::detect_claude_directory_with_fallback(&app_handle)?;
+        // Detect Claude directory immediately during construction. If nothing is found the
+        // user is asked to pick one once the event loop runs, until then the default path is used
+        let (claude_dir, claude_dir_source, needs_claude_dir_selection) =
+            match Self::detect_claude_directory_with_fallback(current_settings.claude_dir_override.as_deref()) {
+                Ok((dir, source)) => (dir, source, false),
+                Err(e @ (AppError::ClaudeNotFound | AppError::WrongClaudeProduct(_))) => {
+                    log::warn!("{}", e);
+                    let home_dir = dirs::home_dir().ok_or(AppError::ClaudeNotFound)?;
+                    (home_dir.join(".claude"), ClaudeDirSource::Stub, true)
+                }
+                Err(e) => return Err(e),
+            };
         
         // Initialize services with proper configuration
-        let config_service = Arc::new(Mutex::new(ConfigService::new(claude_dir)));
-        let tray_service = Arc::new(Mutex::new(TrayService::new(app_handle.clone())));
-        let monitor_service = Arc::new(Mutex::new(MonitorService::new(monitor_interval)));
+        let mut config = ConfigService::new(claude_dir);
+        config.set_compare_keys(current_settings.compare_keys.clone());
+        config.set_switch_mode(current_settings.switch_mode);
+        config.set_env_merge(current_settings.env_merge);
+        config.set_follow_symlinks(current_settings.follow_symlinks);
+        config.set_allow_empty_profiles(current_settings.allow_empty_profiles);
+        config.set_preserve_partial_match_fields(current_settings.preserve_model_on_switch);
+        config.set_backup_max_age_days(current_settings.backup_max_age_days);
+        config.set_backup_dir(current_settings.backup_dir.clone());
+        config.set_max_profile_size(current_settings.max_profile_size_bytes);
+        config.set_status_glyphs(current_settings.status_glyphs);
+        let config_service = Arc::new(Mutex::new(config));
+        let mut tray = TrayService::new(app_handle.clone());
+        tray.set_status_glyphs(current_settings.status_glyphs);
+        let tray_service = Arc::new(Mutex::new(tray));
+        let mut monitor = MonitorService::with_limits(
+            current_settings.monitor_interval_minutes,
+            current_settings.max_monitored_files,
+            current_settings.max_metadata_cache,
+        )?;
+        monitor.set_scan_error_policy(current_settings.max_scan_errors, current_settings.max_backoff_seconds)?;
+        monitor.set_cache_optimize_interval(current_settings.cache_optimize_interval_scans)?;
+        monitor.set_power_aware(current_settings.power_aware_monitoring);
+        monitor.set_mtime_only(current_settings.detect_changes_by_mtime_only);
+        let monitor_service = Arc::new(Mutex::new(monitor));
         
-        Ok(Self {
+        let app = Self {
             config_service,
             tray_service,
             monitor_service,
             settings_service,
             i18n_service,
+            error_log: Arc::new(Mutex::new(ErrorLog::default())),
             app_handle,
             is_initialized: false,
-        })
+            needs_claude_dir_selection,
+            claude_dir_source,
+            external_change: None,
+        };
+        app.log_claude_dir_resolution();
+        Ok(app)
     }
     
     /// Detect Claude directory with fallback strategies
-    fn detect_claude_directory_with_fallback(_app_handle: &AppHandle) -> AppResult<PathBuf> {
-        // Try automatic detection first
+    fn detect_claude_directory_with_fallback(
+        claude_dir_override: Option<&Path>,
+    ) -> AppResult<(PathBuf, ClaudeDirSource)> {
+        // A directory the user picked before wins over auto-detection
+        if let Some(dir) = claude_dir_override {
+            match ClaudeDetector::validate_default_config(dir) {
+                Ok(()) => {
+                    log::info!("Using user-selected Claude directory: {:?}", dir);
+                    return Ok((dir.to_path_buf(), ClaudeDirSource::Override));
+                }
+                Err(e) => log::warn!("Ignoring user-selected Claude directory {:?}: {}", dir, e),
+            }
+        }
+        
+        // Try automatic detection next
         match ClaudeDetector::detect_claude_installation() {
             Ok(dir) => {
                 log::info!("Claude directory detected automatically: {:?}", dir);
                 ClaudeDetector::validate_default_config(&dir)?;
-                return Ok(dir);
+                return Ok((dir, ClaudeDirSource::Auto));
             }
             Err(AppError::ClaudeNotFound) => {
                 log::warn!("Claude directory not found automatically");
@@ -71,41 +138,73 @@ impl App {
         }
         
         // Try common fallback locations
-        let fallback_locations = vec![
-            dirs::home_dir().map(|d| d.join(".claude")),
-            dirs::config_dir().map(|d| d.join("claude")),
-            std::env::var("CLAUDE_CONFIG_DIR").ok().map(PathBuf::from),
-        ];
-        
-        for location in fallback_locations.into_iter().flatten() {
+        for (location, source) in ClaudeDetector::auto_detect_candidates() {
             if location.exists() && ClaudeDetector::validate_default_config(&location).is_ok() {
                 log::info!("Found Claude directory at fallback location: {:?}", location);
-                return Ok(location);
+                return Ok((location, source));
             }
         }
         
-        // Create a default directory as last resort
-        if let Some(home_dir) = dirs::home_dir() {
-            let claude_dir = home_dir.join(".claude");
-            log::warn!("Creating default Claude directory: {:?}", claude_dir);
-            std::fs::create_dir_all(&claude_dir)
-                .map_err(|e| AppError::FileSystemError(format!("Failed to create Claude directory: {}", e)))?;
-            
-            // Create a minimal settings.json
-            let settings_file = claude_dir.join("settings.json");
-            if !settings_file.exists() {
-                let default_settings = r#"{"model": "claude-sonnet-4"}"#;
-                std::fs::write(&settings_file, default_settings)
-                    .map_err(|e| AppError::FileSystemError(format!("Failed to create default settings: {}", e)))?;
-                log::info!("Created default settings.json");
+        // A running Claude Code process knows where its config is, even in unusual setups
+        #[cfg(feature = "process-detection")]
+        if let Some(dir) = ClaudeDetector::detect_from_process() {
+            if ClaudeDetector::validate_default_config(&dir).is_ok() {
+                return Ok((dir, ClaudeDirSource::Process));
             }
-            
-            return Ok(claude_dir);
+        }
+        
+        // Point users who only have Claude Desktop at the right product instead of "not found"
+        if let Some(desktop_dir) = ClaudeDetector::detect_claude_desktop() {
+            return Err(ClaudeDetector::wrong_product_error(&desktop_dir));
         }
         
         Err(AppError::ClaudeNotFound)
     }
     
+    /// Create `~/.claude` with an empty settings.json, returning the file if it was newly created
+    fn create_default_claude_directory() -> AppResult<Option<PathBuf>> {
+        let home_dir = dirs::home_dir().ok_or(AppError::ClaudeNotFound)?;
+        let claude_dir = home_dir.join(".claude");
+        log::warn!("Creating default Claude directory: {:?}", claude_dir);
+        std::fs::create_dir_all(&claude_dir)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to create Claude directory: {}", e)))?;
+        
+        // Empty settings leave every choice (model included) to Claude Code's own defaults
+        let settings_file = claude_dir.join("settings.json");
+        if settings_file.exists() {
+            return Ok(None);
+        }
+        
+        std::fs::write(&settings_file, "{}")
+            .map_err(|e| AppError::FileSystemError(format!("Failed to create default settings: {}", e)))?;
+        log::info!("Created default settings.json");
+        Ok(Some(settings_file))
+    }
+    
+    /// The Claude directory in use and how it was chosen
+    pub fn claude_dir_info(&self) -> ClaudeDirInfo {
+        let path = self.config_service.lock().unwrap().get_claude_dir().to_path_buf();
+        ClaudeDirInfo {
+            path: path.to_string_lossy().to_string(),
+            source: self.claude_dir_source,
+        }
+    }
+    
+    /// Log the Claude directory in use and its source on one parseable line, matching `claude_dir_info`
+    fn log_claude_dir_resolution(&self) {
+        log::info!("Claude directory resolved: {}", self.claude_dir_info().log_fields());
+    }
+    
+    /// Whether no Claude directory was found and the user should be asked to pick one
+    pub fn needs_claude_dir_selection(&self) -> bool {
+        self.needs_claude_dir_selection
+    }
+    
+    /// Profile active at the end of the last session if settings.json was changed since, so it no longer matches
+    pub fn external_change(&self) -> Option<&str> {
+        self.external_change.as_deref()
+    }
+    
     /// Initialize the application
     pub async fn initialize(&mut self) -> AppResult<()> {
         log::info!("Initializing CCCS application");
@@ -125,10 +224,14 @@ impl App {
                 Err(e) => {
                     log::error!("Failed to scan profiles: {}", e);
                     // Don't fail initialization, just log the error
+                    self.record_error("scan_profiles", &e);
                 }
             }
         }
         
+        // Tell the user when settings.json was changed while CCCS wasn't running
+        self.detect_external_change();
+        
         // Step 2: Setup file monitoring
         self.setup_monitoring().await?;
         
@@ -147,6 +250,55 @@ impl App {
         Ok(())
     }
     
+    /// Notify the user when the profile active at the end of the last session no longer matches
+    /// settings.json, and none other does
+    ///
+    /// Runs before any window exists, so the result is also kept for `external_change`.
+    fn detect_external_change(&mut self) {
+        let Some(state) = self.app_handle.try_state::<Mutex<SettingsService>>() else {
+            return;
+        };
+        let Ok(mut settings) = state.lock() else {
+            return;
+        };
+        
+        let summaries = self.config_service.lock().unwrap().profile_summaries();
+        let active = active_profile_name(&summaries);
+        let last_active = settings.get_current_settings().last_active_profile.clone();
+        
+        // Compare against this session from now on, even if nothing switches
+        if let Err(e) = settings.update_last_active_profile(active) {
+            log::warn!("Failed to save last active profile: {}", e);
+        }
+        // show_notification locks the settings itself
+        drop(settings);
+        
+        if changed_externally(last_active.as_deref(), active) {
+            log::info!("settings.json no longer matches last active profile {:?}", last_active);
+            let last_active = last_active.unwrap_or_default();
+            show_notification(&self.app_handle, &localized_text(&self.app_handle, "external_change_detected", &[&last_active]));
+            self.external_change = Some(last_active);
+        }
+    }
+    
+    /// Bring up only the tray with Settings and Exit, skipping profile scanning and monitoring
+    ///
+    /// For `--safe-mode` launches, so a Claude directory that hangs or crashes normal startup
+    /// can still be changed, or auto-start monitoring turned off, from the settings window.
+    pub async fn initialize_safe_mode(&mut self) -> AppResult<()> {
+        log::warn!("SAFE MODE: profiles are not scanned and file monitoring is off");
+        
+        {
+            let mut tray_service = self.tray_service.lock().unwrap();
+            tray_service.create_tray()?;
+            tray_service.show_safe_mode_menu()?;
+        }
+        self.setup_window_listeners(false);
+        
+        self.is_initialized = true;
+        Ok(())
+    }
+    
     /// Refresh all profile status and update UI
     async fn refresh_all_status(&self) -> AppResult<()> {
         log::debug!("Refreshing all profile status");
@@ -158,10 +310,11 @@ impl App {
         }
         
         // Then get the profiles and statuses
-        let profiles = {
+        let (profiles, summaries) = {
             let config_service = self.config_service.lock().unwrap();
-            config_service.get_profiles().to_vec()
+            (config_service.get_profiles().to_vec(), config_service.profile_summaries())
         };
+        emit_active_profile_changed(&self.app_handle, &summaries);
         
         let statuses = {
             let config_service = self.config_service.lock().unwrap();
@@ -179,9 +332,9 @@ impl App {
             if let Ok(i18n_service) = self.i18n_service.lock() {
                 let active_profile = profiles.iter()
                     .enumerate()
-                    .find(|(i, _)| matches!(statuses[*i], crate::ProfileStatus::FullMatch))
-                    .map(|(_, p)| p.name.as_str());
-                let tooltip = i18n_service.get_tray_tooltip(profiles.len(), active_profile);
+                    .find(|(i, p)| !p.is_live && matches!(statuses[*i], crate::ProfileStatus::FullMatch))
+                    .map(|(_, p)| p.label());
+                let tooltip = i18n_service.get_tray_tooltip(profiles.iter().filter(|p| !p.is_live).count(), active_profile);
                 let _ = tray_service.set_tooltip(&tooltip);
             }
         }
@@ -193,58 +346,177 @@ impl App {
     async fn setup_monitoring(&self) -> AppResult<()> {
         log::info!("Setting up file monitoring");
         
-        let config_service = Arc::clone(&self.config_service);
-        let tray_service = Arc::clone(&self.tray_service);
-        let app_handle = self.app_handle.clone();
-        
-        let mut monitor_service = self.monitor_service.lock().unwrap();
-        
         // Add files to monitor
         let monitored_files = {
-            let config = config_service.lock().unwrap();
+            let config = self.config_service.lock().unwrap();
             config.get_monitored_files()
         };
         
-        for file in monitored_files {
-            monitor_service.add_file_to_monitor(file);
-        }
+        let current = self.current_settings();
+        add_monitored_files(&mut self.monitor_service.lock().unwrap(), monitored_files, &current.extra_monitored_files);
         
         // Start monitoring if auto-start is enabled
-        let should_auto_start = {
-            let settings = self.settings_service.lock().unwrap();
-            settings.get_current_settings().auto_start_monitoring
-        };
+        if current.auto_start_monitoring {
+            self.start_monitoring()?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Start file monitoring with the standard change callback
+    pub fn start_monitoring(&self) -> AppResult<()> {
+        let callback = self.make_monitor_callback();
+        self.monitor_service.lock().unwrap().start_monitoring(callback)
+    }
+    
+    /// Change the live monitor's interval, restarting it when running, returns the interval now in effect
+    pub fn apply_monitor_interval(&self, minutes: u64) -> AppResult<u64> {
+        let mut monitor_service = self.monitor_service.lock().unwrap();
+        monitor_service.set_monitor_interval(minutes)?;
+        Ok(monitor_service.get_monitoring_stats().effective_interval_minutes)
+    }
+    
+    /// Stop file monitoring, individual file subscriptions keep running
+    pub fn stop_monitoring(&self) {
+        self.monitor_service.lock().unwrap().stop_monitoring();
+    }
+    
+    /// Whether the monitor task is running right now, which can differ from `auto_start_monitoring`
+    pub fn is_monitoring(&self) -> bool {
+        self.monitor_service.lock().unwrap().is_monitoring()
+    }
+    
+    /// Current monitoring statistics
+    pub fn monitoring_stats(&self) -> MonitoringStats {
+        self.monitor_service.lock().unwrap().get_monitoring_stats()
+    }
+    
+    /// Recent errors, oldest first
+    pub fn error_log(&self) -> Vec<ErrorLogEntry> {
+        self.error_log.lock().unwrap().entries()
+    }
+    
+    /// Add an error to the error log
+    fn record_error(&self, operation: &str, error: &AppError) {
+        record_error(&self.error_log, operation, error);
+    }
+    
+    /// Build the callback run on file changes: refresh status, update the tray and notify the frontend
+    fn make_monitor_callback(&self) -> impl Fn(Vec<crate::ConfigFileChange>) + Send + Sync + 'static {
+        let config_service = Arc::clone(&self.config_service);
+        let tray_service = Arc::clone(&self.tray_service);
+        let error_log = Arc::clone(&self.error_log);
+        let app_handle = self.app_handle.clone();
         
-        if should_auto_start {
-            let callback = move |changes: Vec<crate::ConfigFileChange>| {
-                log::info!("File changes detected: {} files changed", changes.len());
+        move |changes: Vec<crate::ConfigFileChange>| {
+            log::info!("File changes detected: {} files changed", changes.len());
+            
+            // Anything that isn't settings.json or a profile is a user-specified extra file
+            let (changes, watched): (Vec<_>, Vec<_>) = match config_service.lock() {
+                Ok(config) => {
+                    let config_files = config.get_monitored_files();
+                    changes.into_iter().partition(|change| config_files.contains(&change.file_path))
+                }
+                Err(_) => (changes, Vec::new()),
+            };
+            for change in watched {
+                let payload = FileStatusChanged {
+                    path: change.file_path.to_string_lossy().to_string(),
+                    change_type: change.change_type,
+                };
+                let _ = app_handle.emit("watched_file_changed", payload);
+            }
+            if changes.is_empty() {
+                return;
+            }
+            
+            // Update configuration service
+            let summaries = if let Ok(mut config) = config_service.lock() {
+                if let Err(e) = config.refresh_after_changes(&changes) {
+                    log::error!("Failed to refresh profiles after file changes: {}", e);
+                    record_error(&error_log, "refresh_after_changes", &e);
+                }
                 
-                // Update configuration service
-                if let Ok(mut config) = config_service.lock() {
-                    if let Err(e) = config.refresh_profile_status() {
-                        log::error!("Failed to refresh profile status: {}", e);
-                    }
-                    
-                    // Update tray menu with detailed status
-                    if let Ok(mut tray) = tray_service.lock() {
-                        let profiles = config.get_profiles();
-                        let statuses = config.compare_profiles();
-                        if let Err(e) = tray.update_menu_with_detailed_status(profiles, &statuses) {
-                            log::error!("Failed to update tray menu: {}", e);
-                        }
+                // Update tray menu with detailed status
+                if let Ok(mut tray) = tray_service.lock() {
+                    let profiles = config.get_profiles();
+                    let statuses = config.compare_profiles();
+                    if let Err(e) = tray.update_menu_with_detailed_status(profiles, &statuses) {
+                        log::error!("Failed to update tray menu: {}", e);
                     }
                 }
                 
-                // Emit event to notify frontend
-                let _ = app_handle.emit("profiles_changed", ());
+                config.profile_summaries()
+            } else {
+                Vec::new()
             };
             
-            monitor_service.start_monitoring(callback)?;
+            // Emit event to notify frontend
+            emit_profiles_changed(&app_handle, summaries);
+        }
+    }
+    
+    /// Start watching a user-specified file, its changes are emitted as `watched_file_changed`
+    pub fn watch_extra_file(&self, path: &Path) -> AppResult<()> {
+        let mut monitor_service = self.monitor_service.lock().unwrap();
+        if !monitor_service.add_file_to_monitor(path.to_path_buf()) {
+            return Err(AppError::MonitorError(format!(
+                "Can't monitor {:?}, it may be too large or max_monitored_files is reached", path
+            )));
+        }
+        
+        // The running task works on a copy of the file list
+        if monitor_service.is_monitoring() {
+            monitor_service.restart_monitoring()?;
+        }
+        Ok(())
+    }
+    
+    /// Stop watching a user-specified file, settings and profile files stay monitored
+    pub fn unwatch_extra_file(&self, path: &Path) -> AppResult<()> {
+        let is_config_file = self.config_service.lock().unwrap()
+            .get_monitored_files()
+            .iter()
+            .any(|file| file == path);
+        if is_config_file {
+            return Ok(());
         }
         
+        let mut monitor_service = self.monitor_service.lock().unwrap();
+        if monitor_service.remove_file_from_monitor(path) && monitor_service.is_monitoring() {
+            monitor_service.restart_monitoring()?;
+        }
+        Ok(())
+    }
+    
+    /// Emit `file_status_changed` events for changes to one settings or profile file
+    pub fn subscribe_file(&self, path: &Path) -> AppResult<()> {
+        let known = self.config_service.lock().unwrap()
+            .get_monitored_files()
+            .iter()
+            .any(|file| file == path);
+        if !known {
+            return Err(AppError::PermissionError(
+                format!("Only settings and profile files can be subscribed to: {:?}", path)
+            ));
+        }
+        
+        let app_handle = self.app_handle.clone();
+        self.monitor_service.lock().unwrap().subscribe_file(path.to_path_buf(), move |change| {
+            let payload = FileStatusChanged {
+                path: change.file_path.to_string_lossy().to_string(),
+                change_type: change.change_type,
+            };
+            let _ = app_handle.emit("file_status_changed", payload);
+        });
         Ok(())
     }
     
+    /// Stop the events started by `subscribe_file`, returns false if the file wasn't subscribed
+    pub fn unsubscribe_file(&self, path: &Path) -> bool {
+        self.monitor_service.lock().unwrap().unsubscribe_file(path)
+    }
+    
     /// Setup system tray
     async fn setup_tray(&self) -> AppResult<()> {
         log::info!("Setting up system tray");
@@ -267,9 +539,9 @@ impl App {
             let i18n = self.i18n_service.lock().unwrap();
             let active_profile = profiles.iter()
                 .enumerate()
-                .find(|(i, _)| matches!(statuses[*i], crate::ProfileStatus::FullMatch))
-                .map(|(_, p)| p.name.as_str());
-            i18n.get_tray_tooltip(profiles.len(), active_profile)
+                .find(|(i, p)| !p.is_live && matches!(statuses[*i], crate::ProfileStatus::FullMatch))
+                .map(|(_, p)| p.label());
+            i18n.get_tray_tooltip(profiles.iter().filter(|p| !p.is_live).count(), active_profile)
         };
         tray_service.set_tooltip(&tooltip)?;
         
@@ -288,57 +560,95 @@ impl App {
         let config_service_clone = Arc::clone(&config_service);
         let tray_service_clone = Arc::clone(&tray_service);
         let i18n_service_clone = Arc::clone(&i18n_service);
-        let _app_handle_for_switch = self.app_handle.clone();
+        let error_log_for_switch = Arc::clone(&self.error_log);
+        let app_handle_for_switch = self.app_handle.clone();
         
         self.app_handle.listen("profile_switch_requested", move |event| {
             // Parse payload manually since as_str() is unstable
             if let Ok(profile_name) = serde_json::from_str::<String>(event.payload()) {
                 log::info!("Profile switch requested: {}", profile_name);
                 
-                // Show switching status
-                if let Ok(mut tray) = tray_service_clone.lock() {
-                    let _ = tray.update_profile_status(&profile_name, "❕");
-                }
-                
-                // Perform switch
-                let result = {
-                    let mut config = config_service_clone.lock().unwrap();
-                    config.switch_profile(&profile_name)
-                };
+                // Errors are logged inside, the tray is the only feedback here.
+                // Tray clicks are never forced, so a locked profile stays active
+                let _ = switch_profile_and_update_tray(
+                    &app_handle_for_switch,
+                    &config_service_clone,
+                    &tray_service_clone,
+                    &i18n_service_clone,
+                    &error_log_for_switch,
+                    &profile_name,
+                    false,
+                );
+            }
+        });
+        
+        // Listen for new profile from template requests from tray
+        let config_service_for_template = Arc::clone(&config_service);
+        let tray_service_for_template = Arc::clone(&tray_service);
+        let error_log_for_template = Arc::clone(&self.error_log);
+        let app_handle_for_template = self.app_handle.clone();
+        self.app_handle.listen("profile_template_requested", move |event| {
+            if let Ok(template) = serde_json::from_str::<String>(event.payload()) {
+                log::info!("Profile from template requested: {}", template);
                 
-                match result {
-                    Ok(()) => {
-                        log::info!("Profile switched successfully: {}", profile_name);
-                        
-                        // Update tray menu with detailed status
-                        if let (Ok(config), Ok(mut tray)) = (config_service_clone.lock(), tray_service_clone.lock()) {
-                            let profiles = config.get_profiles();
-                            let statuses = config.compare_profiles();
-                            let _ = tray.update_menu_with_detailed_status(profiles, &statuses);
-                            
-                            // Update tooltip
-                            if let Ok(i18n) = i18n_service_clone.lock() {
-                                let active_profile = profiles.iter()
-                                    .enumerate()
-                                    .find(|(i, _)| matches!(statuses[*i], crate::ProfileStatus::FullMatch))
-                                    .map(|(_, p)| p.name.as_str());
-                                let tooltip = i18n.get_tray_tooltip(profiles.len(), active_profile);
-                                let _ = tray.set_tooltip(&tooltip);
-                            }
-                        }
+                let summaries = if let Ok(mut config) = config_service_for_template.lock() {
+                    let name = config.unique_profile_name(&template);
+                    if let Err(e) = config.create_from_template(&template, &name) {
+                        log::error!("Failed to create profile from template {}: {}", template, e);
+                        record_error(&error_log_for_template, "create_from_template", &e);
+                        return;
                     }
-                    Err(e) => {
-                        log::error!("Failed to switch profile {}: {}", profile_name, e);
-                        
-                        // Reset status on error
-                        if let Ok(mut tray) = tray_service_clone.lock() {
-                            let _ = tray.update_profile_status(&profile_name, "");
-                        }
+                    
+                    if let Ok(mut tray) = tray_service_for_template.lock() {
+                        let profiles = config.get_profiles();
+                        let statuses = config.compare_profiles();
+                        let _ = tray.update_menu_with_detailed_status(profiles, &statuses);
                     }
-                }
+                    
+                    config.profile_summaries()
+                } else {
+                    Vec::new()
+                };
+                
+                emit_profiles_changed(&app_handle_for_template, summaries);
+            }
+        });
+        
+        // Listen for tray icon hover events
+        let config_service = Arc::clone(&self.config_service);
+        let tray_service = Arc::clone(&self.tray_service);
+        let error_log = Arc::clone(&self.error_log);
+        let app_handle_for_hover = self.app_handle.clone();
+        let last_hover_rescan = Mutex::new(None);
+        self.app_handle.listen("tray_icon_hover", move |_| {
+            // Some desktops send Enter events constantly, so this can be turned off
+            let enabled = app_handle_for_hover.try_state::<Mutex<SettingsService>>()
+                .and_then(|state| state.lock().ok().map(|s| s.get_current_settings().rescan_on_hover))
+                .unwrap_or(true);
+            if !enabled {
+                return;
+            }
+            
+            if !rescan_due(&mut last_hover_rescan.lock().unwrap(), Instant::now(), HOVER_RESCAN_DEBOUNCE) {
+                log::debug!("Tray icon hover ignored, profiles were rescanned recently");
+                return;
             }
+            log::info!("Tray icon hover detected, refreshing profiles");
+            
+            // Refresh profiles synchronously
+            rescan_and_update_tray(&config_service, &tray_service, &error_log, "on hover");
         });
         
+        self.setup_window_listeners(true);
+        
+        Ok(())
+    }
+    
+    /// Listeners for the settings window, the only ones safe mode needs
+    ///
+    /// With `rescan_on_open` a newly created settings window triggers a rescan, so it
+    /// starts from fresh profiles. Safe mode doesn't scan profiles at all.
+    fn setup_window_listeners(&self, rescan_on_open: bool) {
         // Listen for settings menu clicks
         let app_handle_clone = self.app_handle.clone();
         self.app_handle.listen("menu_settings_clicked", move |_| {
@@ -350,6 +660,11 @@ impl App {
         
         // Listen for open settings window requests
         let app_handle_clone2 = self.app_handle.clone();
+        let monitor_for_window = Arc::clone(&self.monitor_service);
+        let config_for_window = Arc::clone(&self.config_service);
+        let tray_for_window = Arc::clone(&self.tray_service);
+        let error_log_for_window = Arc::clone(&self.error_log);
+        let last_open_rescan = Mutex::new(None);
         self.app_handle.listen("open_settings_window", move |_| {
             log::info!("Opening settings window");
             
@@ -362,17 +677,31 @@ impl App {
                 return;
             }
             
-            // Create settings window
-            match tauri::WebviewWindowBuilder::new(
+            // Restore the last geometry, moved onto a visible work area in case
+            // the monitor it was on has been disconnected since
+            let saved_geometry = app_handle_clone2
+                .try_state::<Mutex<SettingsService>>()
+                .and_then(|state| state.lock().ok().and_then(|s| s.get_current_settings().settings_window))
+                .map(|geometry| clamp_window_geometry(geometry, &logical_work_areas(&app_handle_clone2)));
+            
+            let builder = tauri::WebviewWindowBuilder::new(
                 &app_handle_clone2,
                 "settings",
                 tauri::WebviewUrl::App("settings.html".into())
             )
             .title("CCCS Settings")
-            .inner_size(600.0, 750.0)
             .min_inner_size(500.0, 650.0)
-            .center()
-            .resizable(true)
+            .resizable(true);
+            
+            let builder = match saved_geometry {
+                Some(geometry) => builder
+                    .inner_size(geometry.width, geometry.height)
+                    .position(geometry.x, geometry.y),
+                None => builder.inner_size(600.0, 750.0).center(),
+            };
+            
+            // Create settings window
+            match builder
             .on_page_load(|window, _payload| {
                 // Inject initialization script after page loads
                 log::info!("Settings page loaded, injecting init script");
@@ -392,52 +721,91 @@ impl App {
             {
                 Ok(window) => {
                     log::info!("Settings window created successfully");
+                    
+                    // Save geometry on close so the window reopens where it was left
+                    // and drop the file subscriptions only the settings window uses
+                    let window_for_events = window.clone();
+                    let monitor_for_events = Arc::clone(&monitor_for_window);
+                    window.on_window_event(move |event| {
+                        if let tauri::WindowEvent::CloseRequested { .. } = event {
+                            save_settings_window_geometry(&window_for_events);
+                            if let Ok(mut monitor) = monitor_for_events.lock() {
+                                monitor.unsubscribe_all();
+                            }
+                        }
+                    });
+                    
                     let _ = window.show();
+                    
+                    // Reopening the window in quick succession reuses the last scan
+                    if rescan_on_open
+                        && rescan_due(&mut last_open_rescan.lock().unwrap(), Instant::now(), SETTINGS_OPEN_RESCAN_DEBOUNCE)
+                    {
+                        let summaries = rescan_and_update_tray(
+                            &config_for_window, &tray_for_window, &error_log_for_window, "for the settings window"
+                        );
+                        if let Some(summaries) = summaries {
+                            emit_profiles_changed(&app_handle_clone2, summaries);
+                        }
+                    }
                 }
                 Err(e) => {
                     log::error!("Failed to create settings window: {}", e);
                 }
             }
         });
+    }
+    
+    /// Re-run Claude directory detection and rebuild profile state for the result
+    pub async fn reload_claude_directory(&mut self) -> AppResult<PathBuf> {
+        log::info!("Reloading Claude directory");
         
-        // Listen for tray icon hover events
-        let config_service = Arc::clone(&self.config_service);
-        let tray_service = Arc::clone(&self.tray_service);
-        let _app_handle_for_hover = self.app_handle.clone();
-        self.app_handle.listen("tray_icon_hover", move |_| {
-            log::info!("Tray icon hover detected, refreshing profiles");
-            
-            // Refresh profiles synchronously
-            if let Ok(mut config) = config_service.lock() {
-                if let Err(e) = config.scan_profiles() {
-                    log::error!("Failed to scan profiles on hover: {}", e);
-                    return;
+        let claude_dir_override = self.current_settings().claude_dir_override;
+        let (claude_dir, source) = Self::detect_claude_directory_with_fallback(claude_dir_override.as_deref())?;
+        self.needs_claude_dir_selection = false;
+        self.claude_dir_source = source;
+        
+        // Point config service at the (possibly new) directory and rescan
+        {
+            let mut config_service = self.config_service.lock().unwrap();
+            config_service.set_claude_dir(claude_dir.clone());
+            match config_service.scan_profiles() {
+                Ok(profiles) => {
+                    log::info!("Rescanned {} profiles after reload", profiles.len());
                 }
-                
-                // Update tray menu with fresh profiles and detailed status
-                if let Ok(mut tray) = tray_service.lock() {
-                    let profiles = config.get_profiles();
-                    let statuses = config.compare_profiles();
-                    let _ = tray.update_menu_with_detailed_status(profiles, &statuses);
+                Err(e) => {
+                    log::error!("Failed to scan profiles after reload: {}", e);
+                    self.record_error("scan_profiles", &e);
                 }
             }
-        });
+        }
         
-        // Listen for app exit requests
-        let monitor_service = Arc::clone(&self.monitor_service);
-        self.app_handle.listen("app_exit_requested", move |_| {
-            log::info!("Application exit requested");
-            
-            // Stop monitoring
-            if let Ok(mut monitor) = monitor_service.lock() {
-                monitor.stop_monitoring();
-            }
-            
-            log::info!("Application cleanup completed");
-        });
+        self.log_claude_dir_resolution();
+        
+        // Drop files from the old directory before re-adding the new ones
+        {
+            let mut monitor_service = self.monitor_service.lock().unwrap();
+            monitor_service.stop_monitoring();
+            monitor_service.clear_monitored_files();
+        }
+        self.setup_monitoring().await?;
         
+        self.refresh_all_status().await?;
+        let summaries = self.config_service.lock().unwrap().profile_summaries();
+        emit_profiles_changed(&self.app_handle, summaries);
         
-        Ok(())
+        log::info!("Claude directory reloaded: {:?}", claude_dir);
+        Ok(claude_dir)
+    }
+    
+    /// Latest user settings, read from the managed settings that commands update
+    ///
+    /// App's own SettingsService is only loaded at launch, so it's just a fallback for when
+    /// the managed state isn't registered.
+    fn current_settings(&self) -> UserSettings {
+        let managed = self.app_handle.try_state::<Mutex<SettingsService>>()
+            .and_then(|state| state.lock().ok().map(|s| s.get_current_settings().clone()));
+        managed.unwrap_or_else(|| self.settings_service.lock().unwrap().get_current_settings().clone())
     }
     
     /// Update monitor interval
@@ -453,16 +821,25 @@ impl App {
     
     
     /// Shutdown the application gracefully
-    #[allow(dead_code)]
-    pub async fn shutdown(&self) -> AppResult<()> {
+    pub fn shutdown(&self) -> AppResult<()> {
         log::info!("Shutting down CCCS application");
         
         // Stop monitoring
+        log::info!("Stopping file monitoring");
         {
             let mut monitor_service = self.monitor_service.lock().unwrap();
             monitor_service.stop_monitoring();
+            monitor_service.unsubscribe_all();
+        }
+        
+        // The settings window won't see CloseRequested when the app exits, save its geometry here
+        if let Some(window) = self.app_handle.get_webview_window("settings") {
+            log::info!("Saving settings window geometry");
+            save_settings_window_geometry(&window);
         }
         
+        // Switch history isn't kept, so there are no pending history writes to flush
+        
         // Clean up resources
         log::info!("Application shutdown completed");
         
@@ -475,6 +852,45 @@ impl App {
         self.is_initialized
     }
     
+    /// Switch profiles and refresh the tray, the same way a tray click does
+    pub fn switch_profile(&self, profile_name: &str, force: bool) -> AppResult<()> {
+        switch_profile_and_update_tray(
+            &self.app_handle,
+            &self.config_service,
+            &self.tray_service,
+            &self.i18n_service,
+            &self.error_log,
+            profile_name,
+            force,
+        )
+    }
+    
+    /// Set or remove a profile's alias and show it in the tray
+    pub fn set_profile_alias(&self, profile_name: &str, alias: Option<&str>) -> AppResult<()> {
+        self.config_service.lock().unwrap().set_profile_alias(profile_name, alias)?;
+        update_tray_from_config(&self.config_service, &self.tray_service, &self.i18n_service);
+        Ok(())
+    }
+    
+    /// Use a different glyph set for profile statuses and redraw the tray and profile list with it
+    pub fn set_status_glyphs(&self, glyphs: StatusGlyphs) {
+        let summaries = {
+            let mut config = self.config_service.lock().unwrap();
+            config.set_status_glyphs(glyphs);
+            config.profile_summaries()
+        };
+        self.tray_service.lock().unwrap().set_status_glyphs(glyphs);
+        update_tray_from_config(&self.config_service, &self.tray_service, &self.i18n_service);
+        emit_profiles_changed(&self.app_handle, summaries);
+    }
+    
+    /// Lock or unlock a profile and show the change in the tray
+    pub fn set_profile_locked(&self, profile_name: &str, locked: bool) -> AppResult<()> {
+        self.config_service.lock().unwrap().set_profile_locked(profile_name, locked)?;
+        update_tray_from_config(&self.config_service, &self.tray_service, &self.i18n_service);
+        Ok(())
+    }
+    
     /// Get reference to config service
     pub fn get_config_service(&self) -> Arc<Mutex<ConfigService>> {
         Arc::clone(&self.config_service)
@@ -487,6 +903,470 @@ impl App {
     }
 }
 
+/// Add settings and profile files, then the user's extra files, to the monitor
+fn add_monitored_files(monitor_service: &mut MonitorService, config_files: Vec<PathBuf>, extra_files: &[PathBuf]) {
+    for file in config_files {
+        monitor_service.add_file_to_monitor(file);
+    }
+    
+    // User-specified files may have been moved or deleted since they were added
+    for file in extra_files {
+        match SettingsService::validate_extra_monitored_file(file) {
+            Ok(()) => {
+                monitor_service.add_file_to_monitor(file.clone());
+            }
+            Err(e) => log::warn!("Skipping extra monitored file: {}", e),
+        }
+    }
+}
+
+/// Run `App::shutdown` on the app stored in Tauri state, if it's available
+///
+/// Only the first call shuts down, later ones return right away.
+pub(crate) fn shutdown_app(app_handle: &AppHandle) {
+    if SHUTDOWN_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    
+    let Some(app_state) = app_handle.try_state::<Arc<Mutex<App>>>() else {
+        log::warn!("Application state not available, skipping shutdown");
+        return;
+    };
+    
+    // Wait a bounded time for a running command, so a stuck one can't block exit forever
+    let deadline = Instant::now() + SHUTDOWN_LOCK_TIMEOUT;
+    let app = loop {
+        match app_state.try_lock() {
+            Ok(app) => break app,
+            Err(TryLockError::Poisoned(e)) => {
+                log::warn!("App state was poisoned, shutting down anyway");
+                break e.into_inner();
+            }
+            Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => {
+                log::error!(
+                    "App state still busy after {:?}, exiting without shutdown: monitoring isn't stopped and window geometry isn't saved",
+                    SHUTDOWN_LOCK_TIMEOUT
+                );
+                return;
+            }
+        }
+    };
+    
+    if let Err(e) = app.shutdown() {
+        log::error!("Application shutdown failed: {}", e);
+    }
+}
+
+/// Notify listeners that profiles changed: a bare refresh signal plus the new profile list
+pub(crate) fn emit_profiles_changed(app_handle: &AppHandle, summaries: Vec<ProfileSummary>) {
+    emit_active_profile_changed(app_handle, &summaries);
+    let _ = app_handle.emit("profiles_changed", ());
+    let _ = app_handle.emit("profiles_updated", summaries);
+}
+
+/// Whether a trigger at `now` should rescan, recording it as the last rescan when it does
+fn rescan_due(last_rescan: &mut Option<Instant>, now: Instant, debounce: Duration) -> bool {
+    if last_rescan.is_some_and(|last| now.duration_since(last) < debounce) {
+        return false;
+    }
+    *last_rescan = Some(now);
+    true
+}
+
+/// Rescan profiles and rebuild the tray menu, returning the new summaries unless the scan failed
+fn rescan_and_update_tray(
+    config_service: &Mutex<ConfigService>,
+    tray_service: &Mutex<TrayService>,
+    error_log: &Mutex<ErrorLog>,
+    context: &str,
+) -> Option<Vec<ProfileSummary>> {
+    let mut config = config_service.lock().ok()?;
+    if let Err(e) = config.scan_profiles() {
+        log::error!("Failed to scan profiles {}: {}", context, e);
+        record_error(error_log, "scan_profiles", &e);
+        return None;
+    }
+    
+    // Update tray menu with fresh profiles and detailed status
+    if let Ok(mut tray) = tray_service.lock() {
+        let profiles = config.get_profiles();
+        let statuses = config.compare_profiles();
+        let _ = tray.update_menu_with_detailed_status(profiles, &statuses);
+    }
+    Some(config.profile_summaries())
+}
+
+/// Add an error to a shared error log, skipped when the lock is poisoned
+fn record_error(error_log: &Mutex<ErrorLog>, operation: &str, error: &AppError) {
+    if let Ok(mut error_log) = error_log.lock() {
+        error_log.record(operation, error);
+    }
+}
+
+/// The last active profile announced with `active_profile_changed`
+#[derive(Default)]
+pub(crate) struct ActiveProfileTracker(Mutex<Option<String>>);
+
+impl ActiveProfileTracker {
+    /// Remember the active profile, returns whether it differs from the previous one
+    fn update(&self, active: Option<&str>) -> bool {
+        let mut last = self.0.lock().unwrap();
+        if last.as_deref() == active {
+            return false;
+        }
+        *last = active.map(str::to_string);
+        true
+    }
+}
+
+/// The active profile as the tray shows it, the first file profile fully matching settings.json
+fn active_profile_name(summaries: &[ProfileSummary]) -> Option<&str> {
+    summaries.iter()
+        .find(|s| s.name != LIVE_PROFILE_NAME && s.status == ProfileStatusKind::FullMatch)
+        .map(|s| s.name.as_str())
+}
+
+/// Emit `active_profile_changed` with the new name, or null, when the active profile differs
+/// from the last one announced
+///
+/// Called from every refresh path, so switches from the tray or a command and external edits
+/// picked up by the monitor all end up here.
+fn emit_active_profile_changed(app_handle: &AppHandle, summaries: &[ProfileSummary]) {
+    let Some(tracker) = app_handle.try_state::<ActiveProfileTracker>() else {
+        return;
+    };
+    
+    let active = active_profile_name(summaries);
+    if tracker.update(active) {
+        log::info!("Active profile changed to {:?}", active);
+        let _ = app_handle.emit("active_profile_changed", active);
+        
+        // Kept for the next launch, see `App::detect_external_change`
+        if let Some(state) = app_handle.try_state::<Mutex<SettingsService>>() {
+            if let Ok(mut settings) = state.lock() {
+                if let Err(e) = settings.update_last_active_profile(active) {
+                    log::warn!("Failed to save last active profile: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Whether settings.json went from matching a profile last session to matching none
+fn changed_externally(last_active: Option<&str>, active: Option<&str>) -> bool {
+    last_active.is_some() && active.is_none()
+}
+
+/// Store the settings window's current logical geometry in the user settings
+fn save_settings_window_geometry(window: &tauri::WebviewWindow) {
+    let geometry = (|| -> tauri::Result<WindowGeometry> {
+        let scale_factor = window.scale_factor()?;
+        let position = window.outer_position()?.to_logical::<f64>(scale_factor);
+        let size = window.inner_size()?.to_logical::<f64>(scale_factor);
+        Ok(WindowGeometry { x: position.x, y: position.y, width: size.width, height: size.height })
+    })();
+    
+    match geometry {
+        Ok(geometry) => {
+            if let Some(state) = window.try_state::<Mutex<SettingsService>>() {
+                if let Ok(mut settings) = state.lock() {
+                    if let Err(e) = settings.update_settings_window_geometry(geometry) {
+                        log::warn!("Failed to save settings window geometry: {}", e);
+                    }
+                }
+            }
+        }
+        Err(e) => log::warn!("Failed to read settings window geometry: {}", e),
+    }
+}
+
+/// Switch to a profile, showing progress in the tray and refreshing menu and tooltip afterwards
+fn switch_profile_and_update_tray(
+    app_handle: &AppHandle,
+    config_service: &Arc<Mutex<ConfigService>>,
+    tray_service: &Arc<Mutex<TrayService>>,
+    i18n_service: &Arc<Mutex<I18nService>>,
+    error_log: &Arc<Mutex<ErrorLog>>,
+    profile_name: &str,
+    force: bool,
+) -> AppResult<()> {
+    // Show switching status
+    let switching_glyph = app_handle.try_state::<Mutex<SettingsService>>()
+        .and_then(|state| state.lock().ok().map(|s| s.get_current_settings().switching_glyph.clone()))
+        .unwrap_or_else(|| DEFAULT_SWITCHING_GLYPH.to_string());
+    if let Ok(mut tray) = tray_service.lock() {
+        let _ = tray.update_profile_status(profile_name, &switching_glyph);
+    }
+    
+    // Perform switch
+    let result = {
+        let mut config = config_service.lock().unwrap();
+        config.switch_profile_with_force(profile_name, force)
+    };
+    
+    match &result {
+        Ok(()) => {
+            log::info!("Profile switched successfully: {}", profile_name);
+            update_tray_from_config(config_service, tray_service, i18n_service);
+            
+            let summaries = config_service.lock().unwrap().profile_summaries();
+            emit_active_profile_changed(app_handle, &summaries);
+        }
+        Err(e) => {
+            log::error!("Failed to switch profile {}: {}", profile_name, e);
+            record_error(error_log, "switch_profile", e);
+            
+            // Reset status on error
+            if let Ok(mut tray) = tray_service.lock() {
+                let _ = tray.update_profile_status(profile_name, "");
+            }
+        }
+    }
+    
+    result
+}
+
+/// Rebuild the tray menu and tooltip from the current profiles and their status
+fn update_tray_from_config(
+    config_service: &Arc<Mutex<ConfigService>>,
+    tray_service: &Arc<Mutex<TrayService>>,
+    i18n_service: &Arc<Mutex<I18nService>>,
+) {
+    if let (Ok(config), Ok(mut tray)) = (config_service.lock(), tray_service.lock()) {
+        let profiles = config.get_profiles();
+        let statuses = config.compare_profiles();
+        let _ = tray.update_menu_with_detailed_status(profiles, &statuses);
+        
+        // Update tooltip
+        if let Ok(i18n) = i18n_service.lock() {
+            let active_profile = profiles.iter()
+                .enumerate()
+                .find(|(i, p)| !p.is_live && matches!(statuses[*i], crate::ProfileStatus::FullMatch))
+                .map(|(_, p)| p.label());
+            let tooltip = i18n.get_tray_tooltip(profiles.iter().filter(|p| !p.is_live).count(), active_profile);
+            let _ = tray.set_tooltip(&tooltip);
+        }
+    }
+}
+
+/// Ask the user for their Claude directory, then reload from it
+///
+/// Runs on its own thread because the dialogs block and must not run on the main thread.
+/// Declining the picker falls back to creating a stub `~/.claude`, but only with consent.
+pub(crate) fn prompt_for_claude_directory(app_handle: AppHandle) {
+    std::thread::spawn(move || {
+        let declined = match tauri::async_runtime::block_on(ClaudeDetector::show_directory_picker(&app_handle)) {
+            Ok(Some(claude_dir)) => {
+                if let Err(e) = save_claude_dir_override(&app_handle, claude_dir) {
+                    log::error!("Failed to save selected Claude directory: {}", e);
+                    return;
+                }
+                false
+            }
+            Ok(None) => {
+                if !consent_to_default_settings(&app_handle) {
+                    log::info!("User declined creating a default settings.json");
+                    return;
+                }
+                
+                match App::create_default_claude_directory() {
+                    Ok(Some(settings_file)) => {
+                        let _ = app_handle.emit("default_settings_created", settings_file.to_string_lossy().to_string());
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        log::error!("Failed to create default Claude directory: {}", e);
+                        return;
+                    }
+                }
+                true
+            }
+            Err(e) => {
+                log::error!("Selected Claude directory is not usable: {}", e);
+                return;
+            }
+        };
+        
+        if let Some(state) = app_handle.try_state::<Arc<Mutex<App>>>() {
+            if let Ok(mut app) = state.lock() {
+                if let Err(e) = tauri::async_runtime::block_on(app.reload_claude_directory()) {
+                    log::error!("Failed to reload Claude directory: {}", e);
+                } else if declined {
+                    // Detection now finds the stub, but it isn't a real installation
+                    app.claude_dir_source = ClaudeDirSource::Stub;
+                    app.log_claude_dir_resolution();
+                }
+            }
+        }
+    });
+}
+
+/// Show a system notification titled with the app name, unless `show_notifications` is off
+pub(crate) fn show_notification(app_handle: &AppHandle, body: &str) {
+    use tauri_plugin_notification::NotificationExt;
+    
+    let enabled = app_handle.try_state::<Mutex<SettingsService>>()
+        .and_then(|state| state.lock().ok().map(|s| s.get_current_settings().show_notifications))
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+    
+    let title = localized_text(app_handle, "app_name", &[]);
+    if let Err(e) = app_handle.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show notification: {}", e);
+    }
+}
+
+/// Text in the user's language, from the managed I18nService that locale changes update
+fn localized_text(app_handle: &AppHandle, key: &str, args: &[&str]) -> String {
+    app_handle.try_state::<Mutex<I18nService>>()
+        .and_then(|state| state.lock().ok().map(|i18n| i18n.get_text_with_args(key, args)))
+        .unwrap_or_else(|| I18nService::new().get_text_with_args(key, args))
+}
+
+/// Tell the user once at startup when some profiles couldn't be loaded
+///
+/// Only when both `show_notifications` and `startup_load_check` are on. Desktop notifications
+/// can't react to clicks, so this is a dialog whose Settings button opens the settings window.
+/// The dialog blocks, so it's shown from its own thread.
+pub(crate) fn report_profile_load_errors(app_handle: AppHandle) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+    
+    let enabled = app_handle.try_state::<Mutex<SettingsService>>()
+        .and_then(|state| state.lock().ok().map(|s| {
+            let settings = s.get_current_settings();
+            settings.show_notifications && settings.startup_load_check
+        }))
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+    
+    let failed_count = app_handle.try_state::<Arc<Mutex<App>>>()
+        .and_then(|state| state.lock().ok().map(|app| {
+            app.config_service.lock().unwrap().get_profile_load_errors().len()
+        }))
+        .unwrap_or(0);
+    if failed_count == 0 {
+        return;
+    }
+    
+    log::warn!("{} profiles failed to load at startup", failed_count);
+    let message = app_handle.try_state::<Mutex<I18nService>>()
+        .and_then(|state| state.lock().ok().map(|i18n| load_errors_message(&i18n, failed_count)))
+        .unwrap_or_else(|| load_errors_message(&I18nService::new(), failed_count));
+    std::thread::spawn(move || {
+        let open_settings = app_handle.dialog()
+            .message(message)
+            .title(localized_text(&app_handle, "app_name", &[]))
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                localized_text(&app_handle, "settings", &[]),
+                localized_text(&app_handle, "close", &[]),
+            ))
+            .blocking_show();
+        if open_settings {
+            let _ = app_handle.emit("open_settings_window", ());
+        }
+    });
+}
+
+/// Summary shown when profiles fail to load, e.g. "2 profiles couldn't be loaded — open Settings to view"
+fn load_errors_message(i18n: &I18nService, failed_count: usize) -> String {
+    let key = if failed_count == 1 { "profile_load_error" } else { "profile_load_errors" };
+    i18n.get_text_with_args(key, &[&failed_count.to_string()])
+}
+
+/// Let the user pick a Claude directory, then save it as the override and reload from it
+///
+/// Blocks on the folder dialog, so it must not run on the main thread. Returns `None` on cancel.
+pub(crate) fn pick_and_use_claude_directory(app_handle: &AppHandle) -> AppResult<Option<PathBuf>> {
+    let Some(claude_dir) = ClaudeDetector::pick_directory(app_handle)? else {
+        return Ok(None);
+    };
+    save_claude_dir_override(app_handle, claude_dir)?;
+    
+    let state = app_handle.try_state::<Arc<Mutex<App>>>()
+        .ok_or_else(|| AppError::ConfigError("Application state is not available".to_string()))?;
+    let mut app = state.lock()
+        .map_err(|_| AppError::ConfigError("Failed to access application state".to_string()))?;
+    tauri::async_runtime::block_on(app.reload_claude_directory()).map(Some)
+}
+
+/// Persist a chosen Claude directory in the managed settings
+fn save_claude_dir_override(app_handle: &AppHandle, claude_dir: PathBuf) -> AppResult<()> {
+    let state = app_handle.try_state::<Mutex<SettingsService>>()
+        .ok_or_else(|| AppError::SettingsError("Settings are not available".to_string()))?;
+    let mut settings = state.lock()
+        .map_err(|_| AppError::SettingsError("Failed to access settings".to_string()))?;
+    settings.update_claude_dir_override(Some(claude_dir))
+}
+
+/// Whether an empty `~/.claude/settings.json` may be created, from the setting or by asking
+fn consent_to_default_settings(app_handle: &AppHandle) -> bool {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+    
+    let auto_create = app_handle.try_state::<Mutex<SettingsService>>()
+        .and_then(|state| state.lock().ok().map(|s| s.get_current_settings().auto_create_settings))
+        .unwrap_or(false);
+    if auto_create {
+        return true;
+    }
+    
+    app_handle.dialog()
+        .message(localized_text(app_handle, "create_default_settings", &[]))
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            localized_text(app_handle, "ok", &[]),
+            localized_text(app_handle, "cancel", &[]),
+        ))
+        .blocking_show()
+}
+
+/// Work areas of all monitors in logical pixels, primary monitor first
+fn logical_work_areas(app_handle: &AppHandle) -> Vec<WindowGeometry> {
+    let primary = app_handle.primary_monitor().ok().flatten();
+    let mut monitors: Vec<tauri::Monitor> = primary.iter().cloned().collect();
+    if let Ok(available) = app_handle.available_monitors() {
+        monitors.extend(available.into_iter().filter(|m| {
+            primary.as_ref().map(|p| p.name() != m.name() || p.position() != m.position()).unwrap_or(true)
+        }));
+    }
+    
+    monitors.iter().map(|monitor| {
+        let scale_factor = monitor.scale_factor();
+        let area = monitor.work_area();
+        let position = area.position.to_logical::<f64>(scale_factor);
+        let size = area.size.to_logical::<f64>(scale_factor);
+        WindowGeometry { x: position.x, y: position.y, width: size.width, height: size.height }
+    }).collect()
+}
+
+/// Move a window onto the work area containing its top-left corner (or the first one),
+/// shrinking it if it doesn't fit
+fn clamp_window_geometry(geometry: WindowGeometry, work_areas: &[WindowGeometry]) -> WindowGeometry {
+    let contains_origin = |area: &&WindowGeometry| {
+        geometry.x >= area.x && geometry.x < area.x + area.width
+            && geometry.y >= area.y && geometry.y < area.y + area.height
+    };
+    
+    let Some(area) = work_areas.iter().find(contains_origin).or(work_areas.first()) else {
+        return geometry;
+    };
+    
+    let width = geometry.width.min(area.width);
+    let height = geometry.height.min(area.height);
+    WindowGeometry {
+        x: geometry.x.clamp(area.x, area.x + area.width - width),
+        y: geometry.y.clamp(area.y, area.y + area.height - height),
+        width,
+        height,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -501,4 +1381,112 @@ mod tests {
         // For now, we just test that the types compile correctly
         assert!(true);
     }
+    
+    #[test]
+    fn test_clamp_window_geometry() {
+        let primary = WindowGeometry { x: 0.0, y: 25.0, width: 1440.0, height: 875.0 };
+        let secondary = WindowGeometry { x: 1440.0, y: 0.0, width: 1920.0, height: 1080.0 };
+        let areas = [primary, secondary];
+        
+        // Fully visible geometry is kept as-is
+        let visible = WindowGeometry { x: 1600.0, y: 100.0, width: 600.0, height: 750.0 };
+        assert_eq!(clamp_window_geometry(visible, &areas), visible);
+        
+        // Geometry on a disconnected monitor moves onto the primary work area
+        let off_screen = WindowGeometry { x: -2000.0, y: 300.0, width: 600.0, height: 750.0 };
+        let clamped = clamp_window_geometry(off_screen, &areas);
+        assert_eq!(clamped, WindowGeometry { x: 0.0, y: 150.0, width: 600.0, height: 750.0 });
+        
+        // Windows larger than the work area are shrunk to fit
+        let oversized = WindowGeometry { x: 10.0, y: 30.0, width: 2000.0, height: 2000.0 };
+        assert_eq!(clamp_window_geometry(oversized, &areas), primary);
+        
+        // Without monitor information the geometry is left untouched
+        assert_eq!(clamp_window_geometry(off_screen, &[]), off_screen);
+    }
+    
+    #[test]
+    fn test_active_profile_tracking() {
+        let summary = |name: &str, status| ProfileSummary { name: name.to_string(), status, glyph: String::new() };
+        let summaries = vec![
+            summary(LIVE_PROFILE_NAME, ProfileStatusKind::FullMatch),
+            summary("home", ProfileStatusKind::NoMatch),
+            summary("work", ProfileStatusKind::FullMatch),
+        ];
+        assert_eq!(active_profile_name(&summaries), Some("work"));
+        assert_eq!(active_profile_name(&summaries[..2]), None);
+        
+        // Only actual changes are reported
+        let tracker = ActiveProfileTracker::default();
+        assert!(!tracker.update(None));
+        assert!(tracker.update(Some("work")));
+        assert!(!tracker.update(Some("work")));
+        assert!(tracker.update(None));
+    }
+    
+    #[test]
+    fn test_changed_externally() {
+        assert!(changed_externally(Some("work"), None));
+        // Switching profiles outside CCCS still leaves a known profile active
+        assert!(!changed_externally(Some("work"), Some("home")));
+        assert!(!changed_externally(None, None));
+        assert!(!changed_externally(None, Some("work")));
+    }
+    
+    #[test]
+    fn test_hover_rescan_debounce() {
+        let start = Instant::now();
+        let mut last_rescan = None;
+        let debounce = HOVER_RESCAN_DEBOUNCE;
+        assert!(rescan_due(&mut last_rescan, start, debounce));
+        assert!(!rescan_due(&mut last_rescan, start + Duration::from_secs(1), debounce));
+        assert!(!rescan_due(&mut last_rescan, start + debounce - Duration::from_millis(1), debounce));
+        assert!(rescan_due(&mut last_rescan, start + debounce, debounce));
+        // The window restarts from the last rescan, not the first hover
+        assert!(!rescan_due(&mut last_rescan, start + debounce + Duration::from_secs(1), debounce));
+    }
+    
+    #[test]
+    fn test_extra_files_survive_reload() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let settings_file = temp_dir.path().join("settings.json");
+        let extra_file = temp_dir.path().join("CLAUDE.md");
+        std::fs::write(&settings_file, "{}").unwrap();
+        std::fs::write(&extra_file, "notes").unwrap();
+        
+        // The extra file is only known to the managed settings, added after launch
+        let settings = UserSettings { extra_monitored_files: vec![extra_file.clone()], ..UserSettings::default() };
+        let mut monitor = MonitorService::new(5);
+        add_monitored_files(&mut monitor, vec![settings_file.clone()], &settings.extra_monitored_files);
+        
+        // A reload clears the monitor and adds everything again from the current settings
+        monitor.clear_monitored_files();
+        let missing = temp_dir.path().join("missing.md");
+        let mut extra_files = settings.extra_monitored_files.clone();
+        extra_files.push(missing);
+        add_monitored_files(&mut monitor, vec![settings_file.clone()], &extra_files);
+        assert_eq!(monitor.get_monitored_files(), &[settings_file, extra_file]);
+    }
+    
+    #[test]
+    fn test_claude_dir_log_fields() {
+        let info = ClaudeDirInfo { path: "/home/me/.claude".to_string(), source: ClaudeDirSource::Env };
+        assert_eq!(info.log_fields(), r#"claude_dir_source=env path="/home/me/.claude""#);
+        
+        // Log names match what get_claude_dir_info serializes
+        for source in [ClaudeDirSource::Auto, ClaudeDirSource::Fallback, ClaudeDirSource::Process, ClaudeDirSource::Stub] {
+            assert_eq!(serde_json::to_value(source).unwrap(), source.as_str());
+        }
+    }
+    
+    #[test]
+    fn test_load_errors_message() {
+        let mut i18n = I18nService::new();
+        i18n.set_locale("en").unwrap();
+        assert_eq!(load_errors_message(&i18n, 1), "1 profile couldn't be loaded — open Settings to view");
+        assert_eq!(load_errors_message(&i18n, 2), "2 profiles couldn't be loaded — open Settings to view");
+        
+        i18n.set_locale("zh").unwrap();
+        assert_eq!(load_errors_message(&i18n, 2), "2 个配置无法加载 — 打开设置查看");
+    }
 }
\ No newline at end of file
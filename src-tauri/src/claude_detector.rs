@@ -1,8 +1,12 @@
 // Claude Code installation detection
-use crate::{AppError, AppResult};
+use crate::{AppError, AppResult, ClaudeDirSource, ClaudeProduct, DirectoryValidation};
+use crate::config_service::ConfigService;
 use std::path::{Path, PathBuf};
 use tauri::AppHandle;
 
+// Claude Desktop keeps its (MCP) config here, Claude Code never writes this file
+const CLAUDE_DESKTOP_CONFIG_FILE: &str = "claude_desktop_config.json";
+
 pub struct ClaudeDetector;
 
 impl ClaudeDetector {
@@ -22,6 +26,35 @@ impl ClaudeDetector {
         }
     }
     
+    /// Claude Desktop's config directory, if it exists on this machine
+    ///
+    /// `~/Library/Application Support/Claude` on macOS, `%APPDATA%\Claude` on Windows,
+    /// `~/.config/Claude` on Linux.
+    pub fn detect_claude_desktop() -> Option<PathBuf> {
+        let desktop_dir = dirs::config_dir()?.join("Claude");
+        (Self::identify_product(&desktop_dir) == ClaudeProduct::Desktop).then_some(desktop_dir)
+    }
+    
+    /// Tell a Claude Code directory from a Claude Desktop one by the files in it
+    pub fn identify_product(dir: &Path) -> ClaudeProduct {
+        if dir.join("settings.json").is_file() {
+            ClaudeProduct::Code
+        } else if dir.join(CLAUDE_DESKTOP_CONFIG_FILE).is_file() {
+            ClaudeProduct::Desktop
+        } else {
+            ClaudeProduct::Unknown
+        }
+    }
+    
+    /// Error for when only Claude Desktop's config was found where Claude Code's was expected
+    pub fn wrong_product_error(desktop_dir: &Path) -> AppError {
+        AppError::WrongClaudeProduct(format!(
+            "{:?} belongs to Claude Desktop. CCCS switches Claude Code settings, which live in \
+             ~/.claude/settings.json and are created the first time Claude Code runs",
+            desktop_dir
+        ))
+    }
+    
     /// Validate that the default settings.json file exists
     pub fn validate_default_config(claude_dir: &Path) -> AppResult<()> {
         let settings_file = claude_dir.join("settings.json");
@@ -29,6 +62,9 @@ impl ClaudeDetector {
         if settings_file.exists() && settings_file.is_file() {
             log::info!("Found default settings.json at: {:?}", settings_file);
             Ok(())
+        } else if Self::identify_product(claude_dir) == ClaudeProduct::Desktop {
+            log::error!("Claude Desktop directory given instead of Claude Code: {:?}", claude_dir);
+            Err(Self::wrong_product_error(claude_dir))
         } else {
             log::error!("Default settings.json not found at: {:?}", settings_file);
             Err(AppError::ConfigError(
@@ -37,6 +73,146 @@ impl ClaudeDetector {
         }
     }
     
+    /// Find the config directory of a running Claude Code process, the last detection strategy
+    ///
+    /// Privacy: this reads the command line and environment of the user's own processes, only
+    /// to spot Claude Code and take its `CLAUDE_CONFIG_DIR` or home directory. Other users'
+    /// processes are skipped and nothing but the directory found is logged. The working directory
+    /// isn't used, it's the project Claude Code runs in and only holds project settings. Only built
+    /// with the opt-in `process-detection` feature, otherwise processes aren't inspected at all.
+    #[cfg(feature = "process-detection")]
+    pub fn detect_from_process() -> Option<PathBuf> {
+        use sysinfo::{ProcessRefreshKind, System, UpdateKind};
+        
+        let mut system = System::new();
+        system.refresh_processes_specifics(
+            ProcessRefreshKind::new()
+                .with_user(UpdateKind::Always)
+                .with_cmd(UpdateKind::Always)
+                .with_environ(UpdateKind::Always),
+        );
+        
+        let own_user = sysinfo::get_current_pid().ok()
+            .and_then(|pid| system.process(pid))
+            .and_then(|process| process.user_id().cloned());
+        let dir = system.processes().values()
+            .filter(|process| own_user.is_some() && process.user_id() == own_user.as_ref())
+            .filter(|process| Self::is_claude_code_process(process.name(), process.cmd()))
+            .find_map(|process| Self::config_dir_from_environ(process.environ()));
+        
+        match &dir {
+            Some(dir) => log::info!("Found Claude directory from a running Claude Code process: {:?}", dir),
+            None => log::debug!("No running Claude Code process with a usable config directory"),
+        }
+        dir
+    }
+    
+    /// Whether a process is Claude Code, either the native binary or the npm package run by node
+    #[cfg(feature = "process-detection")]
+    fn is_claude_code_process(name: &str, cmd: &[String]) -> bool {
+        match name.trim_end_matches(".exe") {
+            "claude" => true,
+            // The script is the first argument after node's own flags
+            "node" => cmd.iter()
+                .skip(1)
+                .find(|arg| !arg.starts_with('-'))
+                .is_some_and(|script| Self::is_claude_code_script(Path::new(script))),
+            _ => false,
+        }
+    }
+    
+    /// Whether a script is the npm package's entry point, directly or through the `claude` bin link
+    #[cfg(feature = "process-detection")]
+    fn is_claude_code_script(script: &Path) -> bool {
+        let script = script.canonicalize().unwrap_or_else(|_| script.to_path_buf());
+        let components: Vec<_> = script.components().map(|c| c.as_os_str()).collect();
+        let in_package = components.windows(2)
+            .any(|pair| pair[0] == "@anthropic-ai" && pair[1] == "claude-code");
+        in_package && script.file_name().is_some_and(|file| file == "cli.js" || file == "cli.mjs")
+    }
+    
+    /// Config directory from a process environment: `CLAUDE_CONFIG_DIR`, then `.claude` in its home
+    #[cfg(feature = "process-detection")]
+    fn config_dir_from_environ(environ: &[String]) -> Option<PathBuf> {
+        let var = |key: &str| environ.iter()
+            .find_map(|entry| entry.strip_prefix(key)?.strip_prefix('='))
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+        
+        let candidates = [
+            var("CLAUDE_CONFIG_DIR"),
+            var("HOME").map(|home| home.join(".claude")),
+            var("USERPROFILE").map(|home| home.join(".claude")),
+        ];
+        candidates.into_iter()
+            .flatten()
+            .find(|dir| dir.join("settings.json").is_file())
+    }
+    
+    /// Fallback directories startup looks in, in order: `~/.claude`, `<config dir>/claude` and `CLAUDE_CONFIG_DIR`
+    pub fn auto_detect_candidates() -> Vec<(PathBuf, ClaudeDirSource)> {
+        [
+            (dirs::home_dir().map(|d| d.join(".claude")), ClaudeDirSource::Fallback),
+            (dirs::config_dir().map(|d| d.join("claude")), ClaudeDirSource::Fallback),
+            (std::env::var("CLAUDE_CONFIG_DIR").ok().map(PathBuf::from), ClaudeDirSource::Env),
+        ]
+        .into_iter()
+        .filter_map(|(dir, source)| dir.map(|dir| (dir, source)))
+        .collect()
+    }
+    
+    /// Whether startup would find a config placed in `dir` without the user picking it
+    pub fn would_auto_detect(dir: &Path) -> bool {
+        let candidates: Vec<PathBuf> = Self::auto_detect_candidates().into_iter().map(|(dir, _)| dir).collect();
+        Self::matches_candidate(dir, &candidates)
+    }
+    
+    fn matches_candidate(dir: &Path, candidates: &[PathBuf]) -> bool {
+        // Resolve symlinks and `..` where the paths exist, trailing separators otherwise
+        let normalize = |path: &Path| path.canonicalize()
+            .unwrap_or_else(|_| path.components().collect());
+        let dir = normalize(dir);
+        candidates.iter().any(|candidate| normalize(candidate) == dir)
+    }
+    
+    /// Trial-load a directory as a Claude config directory without touching app state
+    pub fn test_directory(claude_dir: &Path) -> DirectoryValidation {
+        let mut errors = Vec::new();
+        
+        let default_config_valid = match Self::validate_default_config(claude_dir) {
+            Ok(()) => true,
+            Err(e) => {
+                errors.push(e.to_string());
+                false
+            }
+        };
+        
+        // A throwaway service keeps this read-only with respect to the running app
+        let mut config_service = ConfigService::new(claude_dir.to_path_buf());
+        let (scan_succeeded, profile_count) = match config_service.scan_profiles() {
+            Ok(profiles) => {
+                let mut load_errors: Vec<_> = config_service.get_profile_load_errors()
+                    .iter()
+                    .map(|(name, error)| format!("{}: {}", name, error))
+                    .collect();
+                load_errors.sort();
+                errors.extend(load_errors);
+                (true, profiles.iter().filter(|p| !p.is_live).count())
+            }
+            Err(e) => {
+                errors.push(e.to_string());
+                (false, 0)
+            }
+        };
+        
+        DirectoryValidation {
+            is_valid: default_config_valid && scan_succeeded,
+            product: Self::identify_product(claude_dir),
+            profile_count,
+            errors,
+        }
+    }
+    
     /// Show file picker dialog for manual Claude directory selection
     pub async fn show_directory_picker(app: &AppHandle) -> AppResult<Option<PathBuf>> {
         use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
@@ -48,27 +224,36 @@ impl ClaudeDetector {
             .blocking_show();
         
         if choice {
-            // Show directory picker
-            if let Some(folder_path) = app.dialog()
-                .file()
-                .set_title("Select Claude Code Directory")
-                .blocking_pick_folder()
-            {
-                let claude_path = folder_path.as_path().unwrap();
-                log::info!("User selected Claude directory: {:?}", claude_path);
-                
-                // Validate the selected directory
-                Self::validate_default_config(claude_path)?;
-                Ok(Some(claude_path.to_path_buf()))
-            } else {
-                log::info!("User cancelled directory selection");
-                Ok(None)
-            }
+            Self::pick_directory(app)
         } else {
             log::info!("User chose not to select directory manually");
             Ok(None)
         }
     }
+    
+    /// Show the folder picker and validate the selected Claude directory, `None` on cancel
+    ///
+    /// Blocks until the dialog closes, so never call it on the main thread.
+    pub fn pick_directory(app: &AppHandle) -> AppResult<Option<PathBuf>> {
+        use tauri_plugin_dialog::DialogExt;
+        
+        let Some(folder_path) = app.dialog()
+            .file()
+            .set_title("Select Claude Code Directory")
+            .blocking_pick_folder()
+        else {
+            log::info!("User cancelled directory selection");
+            return Ok(None);
+        };
+        
+        let claude_path = folder_path.into_path()
+            .map_err(|e| AppError::FileSystemError(format!("Selected folder is not a local path: {}", e)))?;
+        log::info!("User selected Claude directory: {:?}", claude_path);
+        
+        // Validate the selected directory
+        Self::validate_default_config(&claude_path)?;
+        Ok(Some(claude_path))
+    }
 }
 
 #[cfg(test)]
@@ -77,6 +262,59 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
     
+    #[cfg(feature = "process-detection")]
+    #[test]
+    fn test_config_dir_from_process() {
+        assert!(ClaudeDetector::is_claude_code_process("claude", &[]));
+        assert!(ClaudeDetector::is_claude_code_process("claude.exe", &[]));
+        assert!(ClaudeDetector::is_claude_code_process(
+            "node",
+            &["node".to_string(), "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js".to_string()],
+        ));
+        assert!(ClaudeDetector::is_claude_code_process(
+            "node",
+            &["node".to_string(), "--no-warnings".to_string(), "/usr/lib/node_modules/@anthropic-ai/claude-code/cli.js".to_string()],
+        ));
+        assert!(!ClaudeDetector::is_claude_code_process("node", &["node".to_string(), "server.js".to_string()]));
+        // Other programs that merely mention a file called claude aren't Claude Code
+        assert!(!ClaudeDetector::is_claude_code_process("vim", &["vim".to_string(), "claude".to_string()]));
+        assert!(!ClaudeDetector::is_claude_code_process("node", &["node".to_string(), "/home/me/claude".to_string()]));
+        assert!(!ClaudeDetector::is_claude_code_process(
+            "node",
+            &["node".to_string(), "/usr/lib/node_modules/@anthropic-ai/claude-code/other.js".to_string()],
+        ));
+        
+        // npm's `claude` bin is a link to the package's cli.js
+        #[cfg(unix)]
+        {
+            let prefix = TempDir::new().unwrap();
+            let package = prefix.path().join("lib/node_modules/@anthropic-ai/claude-code");
+            fs::create_dir_all(&package).unwrap();
+            fs::write(package.join("cli.js"), "").unwrap();
+            fs::create_dir(prefix.path().join("bin")).unwrap();
+            let bin = prefix.path().join("bin/claude");
+            std::os::unix::fs::symlink(package.join("cli.js"), &bin).unwrap();
+            assert!(ClaudeDetector::is_claude_code_process("node", &["node".to_string(), bin.to_string_lossy().to_string()]));
+        }
+        
+        let home = TempDir::new().unwrap();
+        let config_dir = TempDir::new().unwrap();
+        fs::create_dir(home.path().join(".claude")).unwrap();
+        fs::write(home.path().join(".claude").join("settings.json"), "{}").unwrap();
+        let home_var = format!("HOME={}", home.path().display());
+        
+        assert_eq!(ClaudeDetector::config_dir_from_environ(std::slice::from_ref(&home_var)), Some(home.path().join(".claude")));
+        
+        // CLAUDE_CONFIG_DIR wins, but only when it holds a settings.json
+        let config_var = format!("CLAUDE_CONFIG_DIR={}", config_dir.path().display());
+        let environ = vec![config_var, home_var];
+        assert_eq!(ClaudeDetector::config_dir_from_environ(&environ), Some(home.path().join(".claude")));
+        fs::write(config_dir.path().join("settings.json"), "{}").unwrap();
+        assert_eq!(ClaudeDetector::config_dir_from_environ(&environ), Some(config_dir.path().to_path_buf()));
+        
+        assert_eq!(ClaudeDetector::config_dir_from_environ(&["PATH=/usr/bin".to_string()]), None);
+    }
+    
     #[test]
     fn test_validate_default_config_success() {
         let temp_dir = TempDir::new().unwrap();
@@ -87,6 +325,64 @@ mod tests {
         assert!(result.is_ok());
     }
     
+    #[test]
+    fn test_test_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("settings.json"), "{}").unwrap();
+        fs::write(temp_dir.path().join("work.settings.json"), r#"{"theme": "dark"}"#).unwrap();
+        fs::write(temp_dir.path().join("broken.settings.json"), "{").unwrap();
+        
+        let result = ClaudeDetector::test_directory(temp_dir.path());
+        assert!(result.is_valid);
+        assert_eq!(result.profile_count, 2);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].starts_with("broken: "));
+        
+        let empty_dir = TempDir::new().unwrap();
+        let result = ClaudeDetector::test_directory(empty_dir.path());
+        assert!(!result.is_valid);
+        assert!(result.errors[0].contains("settings.json file not found"));
+        
+        let result = ClaudeDetector::test_directory(&empty_dir.path().join("missing"));
+        assert!(!result.is_valid);
+        assert_eq!(result.profile_count, 0);
+        assert_eq!(result.errors.len(), 2);
+    }
+    
+    #[test]
+    fn test_matches_candidate() {
+        let temp_dir = TempDir::new().unwrap();
+        let candidates = vec![temp_dir.path().join(".claude"), PathBuf::from("/nonexistent/claude")];
+        fs::create_dir(temp_dir.path().join(".claude")).unwrap();
+        fs::create_dir(temp_dir.path().join("x")).unwrap();
+        
+        assert!(ClaudeDetector::matches_candidate(&temp_dir.path().join("x/../.claude"), &candidates));
+        assert!(ClaudeDetector::matches_candidate(Path::new("/nonexistent/claude/"), &candidates));
+        assert!(!ClaudeDetector::matches_candidate(temp_dir.path(), &candidates));
+    }
+    
+    #[test]
+    fn test_claude_desktop_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(ClaudeDetector::identify_product(temp_dir.path()), ClaudeProduct::Unknown);
+        
+        fs::write(temp_dir.path().join("claude_desktop_config.json"), r#"{"mcpServers": {}}"#).unwrap();
+        assert_eq!(ClaudeDetector::identify_product(temp_dir.path()), ClaudeProduct::Desktop);
+        
+        let result = ClaudeDetector::validate_default_config(temp_dir.path());
+        assert!(matches!(result, Err(AppError::WrongClaudeProduct(_))));
+        
+        let validation = ClaudeDetector::test_directory(temp_dir.path());
+        assert!(!validation.is_valid);
+        assert_eq!(validation.product, ClaudeProduct::Desktop);
+        assert!(validation.errors[0].contains("Claude Desktop"));
+        
+        // A Claude Code settings.json wins if both are present
+        fs::write(temp_dir.path().join("settings.json"), "{}").unwrap();
+        assert_eq!(ClaudeDetector::identify_product(temp_dir.path()), ClaudeProduct::Code);
+        assert!(ClaudeDetector::validate_default_config(temp_dir.path()).is_ok());
+    }
+    
     #[test]
     fn test_validate_default_config_missing_file() {
         let temp_dir = TempDir::new().unwrap();
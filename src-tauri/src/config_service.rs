@@ -1,9 +1,55 @@
 // Configuration service for managing Claude Code profiles
-use crate::{AppError, AppResult, Profile, ProfileStatus, FileMetadata};
+use crate::{
+    AppError, AppResult, Profile, ProfileStatus, ProfileStatusKind, ProfileSummary, ProfileDetails, FileMetadata, FieldDiff,
+    BackupEntry, CompanionFile, FileComparison, ProfileFormatReport, ConfigFileChange, JsonSyntaxError, JsonValidation, ProfileChecksum, ProfileContentPreview, ProfileMeta, ScanProgress, SettingsRepair, StatusGlyphs, StatusSummary, SwitchMode,
+};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, Duration};
+use std::sync::Mutex;
+use std::time::{SystemTime, Duration, Instant};
+
+// Backups go here (inside the Claude directory) unless another backup directory is set
+const DEFAULT_BACKUP_DIR_NAME: &str = ".cccs_backups";
+// Number of settings.json backups kept after a switch
+const MAX_BACKUPS: usize = 5;
+
+// Markers of files CCCS itself writes next to settings.json (backups, atomic-write temp files)
+const EXCLUDED_FILE_MARKERS: &[&str] = &[".backup.", ".tmp", ".write_test", ".restore_tmp"];
+
+// Key naming a base profile that is merged underneath a profile's own settings
+const EXTENDS_KEY: &str = "extends";
+// Environment variables block, merged key by key when env merging is on
+const ENV_KEY: &str = "env";
+// Files switched together with settings.json, see `CompanionFile`
+const COMPANIONS_KEY: &str = "companions";
+// Top-level fields a profile may differ in and still be a partial match, kept across
+// switches when preserving them is on
+const PARTIAL_MATCH_FIELDS: &[&str] = &["model"];
+// Name of the synthetic profile listing the live settings.json, reserved for file profiles
+pub const LIVE_PROFILE_NAME: &str = "(current)";
+
+// Serializes everything that writes settings.json, separate from the lock around the service.
+// Separate `ConfigService` instances on the same directory share the temp file and backups,
+// so their switches must not interleave either.
+static SWITCH_LOCK: Mutex<()> = Mutex::new(());
+
+// Allowed range for the profile size limit, in bytes
+const MIN_MAX_PROFILE_SIZE: u64 = 1024;
+const MAX_MAX_PROFILE_SIZE: u64 = 100 * 1024 * 1024;
+
+// Keys whose values are masked when settings are shared, matched case-insensitively
+const SECRET_KEY_MARKERS: &[&str] = &["token", "key", "secret", "password", "auth", "credential"];
+
+// Retry policy for replacing a file that's briefly locked (antivirus, sync clients)
+const MAX_RENAME_ATTEMPTS: u32 = 3;
+const RENAME_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// Built-in profile templates, `{{profile_name}}` is replaced with the new profile's name
+const PROFILE_TEMPLATES: &[(&str, &str)] = &[
+    ("minimal", include_str!("../templates/minimal.settings.json")),
+    ("full", include_str!("../templates/full.settings.json")),
+];
 
 // Cache for profile metadata to improve performance
 #[derive(Clone, Debug)]
@@ -19,8 +65,28 @@ pub struct ConfigService {
     default_settings_path: PathBuf,
     // Performance optimization: cache file metadata and content
     profile_cache: HashMap<PathBuf, ProfileCache>,
-    default_settings_cache: Option<(String, SystemTime)>,
+    default_settings_cache: Option<ProfileCache>,
     cache_ttl: Duration,
+    // Profiles found during the last scan that failed to load, keyed by name
+    profile_load_errors: HashMap<String, String>,
+    max_profile_size: u64,
+    // When set, statuses only compare these (dotted) keys instead of the whole file
+    compare_keys: Option<Vec<String>>,
+    // Where settings.json backups are kept, None means `<claude_dir>/.cccs_backups`
+    backup_dir: Option<PathBuf>,
+    // Backups older than this many days are pruned even below MAX_BACKUPS
+    backup_max_age_days: Option<u64>,
+    switch_mode: SwitchMode,
+    // Merge a profile's env block into the current one instead of replacing settings.json
+    env_merge: bool,
+    // Write through a symlinked settings.json, otherwise switching refuses to touch it
+    follow_symlinks: bool,
+    // Allow switching to a profile with no keys, which leaves settings.json as `{}`
+    allow_empty_profiles: bool,
+    // Keep settings.json's partial match fields (the model) when switching
+    preserve_partial_match_fields: bool,
+    // Glyph set for the profile summaries, matching the tray menu
+    status_glyphs: StatusGlyphs,
 }
 
 impl ConfigService {
@@ -33,9 +99,109 @@ impl ConfigService {
             profile_cache: HashMap::new(),
             default_settings_cache: None,
             cache_ttl: Duration::from_secs(60), // 1 minute cache TTL
+            profile_load_errors: HashMap::new(),
+            max_profile_size: crate::DEFAULT_MAX_PROFILE_SIZE_BYTES,
+            compare_keys: None,
+            backup_dir: None,
+            backup_max_age_days: None,
+            switch_mode: SwitchMode::Replace,
+            env_merge: false,
+            follow_symlinks: false,
+            allow_empty_profiles: false,
+            preserve_partial_match_fields: false,
+            status_glyphs: StatusGlyphs::default(),
+        }
+    }
+    
+    /// Set the maximum size in bytes of a profile file that will be loaded
+    pub fn set_max_profile_size(&mut self, max_bytes: u64) {
+        self.max_profile_size = max_bytes;
+    }
+    
+    /// Validate a profile size limit, between 1KB and 100MB
+    pub fn validate_max_profile_size(max_bytes: u64) -> AppResult<()> {
+        if !(MIN_MAX_PROFILE_SIZE..=MAX_MAX_PROFILE_SIZE).contains(&max_bytes) {
+            return Err(AppError::ConfigError(format!(
+                "Invalid max profile size: {} bytes. Must be between {} and {} bytes.",
+                max_bytes, MIN_MAX_PROFILE_SIZE, MAX_MAX_PROFILE_SIZE
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Only compare the given keys when computing profile status, or everything when None
+    pub fn set_compare_keys(&mut self, keys: Option<Vec<String>>) {
+        self.compare_keys = keys;
+    }
+    
+    /// Choose whether switches replace settings.json or merge the profile onto it
+    ///
+    /// Also changes what counts as active: in merge mode a profile is active when
+    /// settings.json contains everything in it.
+    pub fn set_switch_mode(&mut self, mode: SwitchMode) {
+        self.switch_mode = mode;
+        if let Err(e) = self.refresh_profile_status() {
+            log::warn!("Failed to refresh profile status after changing switch mode: {}", e);
+        }
+    }
+    
+    /// Merge the `env` block of profiles that have one into settings.json's `env`
+    ///
+    /// Other top-level keys from the profile still replace the current ones, keys the
+    /// profile doesn't mention are kept. Profiles without `env` replace settings.json as usual.
+    pub fn set_env_merge(&mut self, enabled: bool) {
+        self.env_merge = enabled;
+        if let Err(e) = self.refresh_profile_status() {
+            log::warn!("Failed to refresh profile status after changing env merge: {}", e);
         }
     }
     
+    /// Allow switches to write through a symlinked settings.json to the file it points to
+    ///
+    /// Off by default, so a settings.json linked somewhere unexpected (a dotfiles repo,
+    /// another machine's share) is never changed without the user opting in.
+    pub fn set_follow_symlinks(&mut self, enabled: bool) {
+        self.follow_symlinks = enabled;
+    }
+    
+    /// Allow switching to profiles that are an empty JSON object
+    ///
+    /// Off by default, since a profile emptied by a bad edit would otherwise wipe settings.json.
+    pub fn set_allow_empty_profiles(&mut self, enabled: bool) {
+        self.allow_empty_profiles = enabled;
+    }
+    
+    /// Keep the fields a partial match ignores, like `model`, as they are in settings.json
+    ///
+    /// A field settings.json doesn't have is left out of the switched content too.
+    pub fn set_preserve_partial_match_fields(&mut self, enabled: bool) {
+        self.preserve_partial_match_fields = enabled;
+    }
+    
+    /// Set the glyph set used for the profile summaries' glyphs
+    pub fn set_status_glyphs(&mut self, glyphs: StatusGlyphs) {
+        self.status_glyphs = glyphs;
+    }
+    
+    /// Set a custom directory for settings.json backups, or None for the default
+    ///
+    /// Backups already in the previous directory are left there and no longer listed.
+    pub fn set_backup_dir(&mut self, backup_dir: Option<PathBuf>) {
+        self.backup_dir = backup_dir;
+    }
+    
+    /// Prune backups older than this many days, None only prunes by count
+    ///
+    /// The most recent backup is always kept, however old it is.
+    pub fn set_backup_max_age_days(&mut self, days: Option<u64>) {
+        self.backup_max_age_days = days;
+    }
+    
+    /// Directory settings.json backups are written to and pruned from
+    pub fn get_backup_dir(&self) -> PathBuf {
+        self.backup_dir.clone()
+            .unwrap_or_else(|| self.claude_dir.join(DEFAULT_BACKUP_DIR_NAME))
+    }
     
     /// Clear all caches when needed
     pub fn clear_cache(&mut self) {
@@ -91,32 +257,72 @@ impl ConfigService {
     /// Get default settings with caching
     fn get_default_settings_cached(&mut self) -> AppResult<String> {
         // Check cache first
-        if let Some((cached_content, cached_time)) = &self.default_settings_cache {
-            if self.is_cache_valid(*cached_time) {
-                // Check if file has been modified
-                if let Ok(metadata) = fs::metadata(&self.default_settings_path) {
-                    if let Ok(modified) = metadata.modified() {
-                        if modified <= *cached_time {
-                            log::debug!("Using cached default settings");
-                            return Ok(cached_content.clone());
-                        }
-                    }
-                }
-            }
+        if let Some(content) = self.cached_default_settings() {
+            log::debug!("Using cached default settings");
+            return Ok(content);
         }
         
         // Read fresh content
         log::debug!("Reading fresh default settings");
         let content = self.read_default_settings()?;
-        self.default_settings_cache = Some((content.clone(), SystemTime::now()));
+        
+        // Update cache with the file's own modification time
+        self.default_settings_cache = match self.get_file_metadata(&self.default_settings_path) {
+            Ok(metadata) => Some(ProfileCache {
+                metadata,
+                content: content.clone(),
+                last_updated: SystemTime::now(),
+            }),
+            Err(_) => None,
+        };
         
         Ok(content)
     }
     
+    /// Cached settings.json content, if the cache is still valid and the file hasn't changed since
+    fn cached_default_settings(&self) -> Option<String> {
+        let cache_entry = self.default_settings_cache.as_ref()?;
+        if !self.is_cache_valid(cache_entry.last_updated) {
+            return None;
+        }
+        
+        // Check if file has been modified since it was cached
+        let modified = fs::metadata(&self.default_settings_path).ok()?.modified().ok()?;
+        (modified <= cache_entry.metadata.modified_time).then(|| cache_entry.content.clone())
+    }
+    
+    /// settings.json parsed once for comparing several profiles, or the status they all get when it can't be
+    fn default_settings_for_comparison(&self) -> Result<serde_json::Value, ProfileStatus> {
+        let default_content = match self.cached_default_settings() {
+            Some(content) => content,
+            None => fs::read_to_string(&self.default_settings_path).map_err(|e| {
+                ProfileStatus::Error { message: format!("Failed to read default settings: {}", e) }
+            })?,
+        };
+        
+        serde_json::from_str::<serde_json::Value>(&default_content).map_err(|e| {
+            ProfileStatus::Error { message: format!("Invalid default settings JSON: {}", e) }
+        })
+    }
+    
     /// Scan for all profile configuration files in the Claude directory
     pub fn scan_profiles(&mut self) -> AppResult<Vec<Profile>> {
+        self.scan_profiles_inner(&mut |_| {})
+    }
+    
+    /// Same as `scan_profiles`, reporting progress as each directory entry is checked
+    pub fn scan_profiles_with_progress(
+        &mut self,
+        mut on_progress: impl FnMut(ScanProgress),
+    ) -> AppResult<Vec<Profile>> {
+        self.scan_profiles_inner(&mut on_progress)
+    }
+    
+    fn scan_profiles_inner(&mut self, on_progress: &mut dyn FnMut(ScanProgress)) -> AppResult<Vec<Profile>> {
+        let started = Instant::now();
         let mut profiles = Vec::new();
         let mut scan_errors = Vec::new();
+        self.profile_load_errors.clear();
         
         log::info!("Scanning for profiles in: {:?}", self.claude_dir);
         
@@ -133,12 +339,16 @@ impl ConfigService {
             ));
         }
         
-        let entries = fs::read_dir(&self.claude_dir)
+        // Collected first so progress can be reported against the total
+        let entries: Vec<_> = fs::read_dir(&self.claude_dir)
             .map_err(|e| AppError::FileSystemError(
                 format!("Failed to read Claude directory {:?}: {}", self.claude_dir, e)
-            ))?;
+            ))?
+            .collect();
+        let total = entries.len();
         
-        for entry in entries {
+        for (index, entry) in entries.into_iter().enumerate() {
+            on_progress(ScanProgress { current: index + 1, total });
             let entry = match entry {
                 Ok(entry) => entry,
                 Err(e) => {
@@ -150,21 +360,58 @@ impl ConfigService {
             let path = entry.path();
             
             if let Some(filename) = path.file_name().and_then(|name| name.to_str()) {
-                // Look for files with pattern "*.settings.json" but exclude "settings.json"
-                if filename.ends_with(".settings.json") && filename != "settings.json" {
-                    if let Some(profile_name) = filename.strip_suffix(".settings.json") {
-                        // Validate profile name is not empty
-                        if !profile_name.is_empty() {
-                            match self.load_profile_optimized(profile_name, &path) {
-                                Ok(profile) => {
-                                    log::info!("Found profile: {} at {:?}", profile.name, path);
-                                    profiles.push(profile);
-                                }
-                                Err(e) => {
-                                    let error_msg = format!("Failed to load profile {}: {}", profile_name, e);
-                                    log::warn!("{}", error_msg);
-                                    scan_errors.push(error_msg);
-                                }
+                // Backups and temp files are never profiles, whatever their name ends with
+                if Self::is_excluded_file(filename) || !path.is_file() {
+                    continue;
+                }
+                
+                // Look for files with pattern "*.settings.json" or "*.settings.toml" but exclude "settings.json"
+                if let Some(profile_name) = Self::profile_name_from_filename(filename) {
+                    if profile_name == LIVE_PROFILE_NAME {
+                        let error_msg = format!("Profile name {} is reserved, ignoring {:?}", profile_name, path);
+                        log::warn!("{}", error_msg);
+                        scan_errors.push(error_msg);
+                        continue;
+                    }
+                    
+                    // Validate profile name is not empty
+                    if !profile_name.is_empty() {
+                        // JSON wins when the same profile exists in both formats
+                        if Self::is_toml_profile(&path)
+                            && self.claude_dir.join(format!("{}.settings.json", profile_name)).exists()
+                        {
+                            let error_msg = format!(
+                                "Profile {} exists as both JSON and TOML, ignoring {:?}", profile_name, path
+                            );
+                            log::warn!("{}", error_msg);
+                            scan_errors.push(error_msg);
+                            continue;
+                        }
+                        
+                        match self.load_profile_optimized(profile_name, &path) {
+                            Ok(mut profile) => {
+                                // Symlinked profiles are tracked by the file they point to
+                                profile.path = Self::resolve_symlink(&path);
+                                log::info!("Found profile: {} at {:?}", profile.name, path);
+                                profiles.push(profile);
+                            }
+                            Err(e) => {
+                                let error_msg = format!("Failed to load profile {}: {}", profile_name, e);
+                                log::warn!("{}", error_msg);
+                                scan_errors.push(error_msg);
+                                
+                                // Keep the profile listed so it can be shown with an error status
+                                self.profile_load_errors.insert(profile_name.to_string(), e.to_string());
+                                profiles.push(Profile {
+                                    name: profile_name.to_string(),
+                                    path: path.clone(),
+                                    content: String::new(),
+                                    is_active: false,
+                                    locked: false,
+                                    companions: Vec::new(),
+                                    is_live: false,
+                                    alias: None,
+                                });
                             }
                         }
                     }
@@ -172,6 +419,33 @@ impl ConfigService {
             }
         }
         
+        // Merge base profiles in before anything compares or switches to them
+        scan_errors.extend(self.resolve_extends(&mut profiles));
+        scan_errors.extend(self.extract_companions(&mut profiles));
+        
+        for profile in &mut profiles {
+            let meta = self.get_profile_meta(&profile.name);
+            profile.locked = meta.locked;
+            profile.alias = meta.alias;
+        }
+        
+        // Directory order differs between platforms, sort so the menu and profile indices are stable
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        
+        // The live settings.json always comes first, as the baseline the others are compared to
+        if self.default_settings_path.is_file() {
+            profiles.insert(0, Profile {
+                name: LIVE_PROFILE_NAME.to_string(),
+                path: self.default_settings_path.clone(),
+                content: String::new(),  // Filled in from settings.json by update_profile_status_optimized
+                is_active: true,
+                locked: false,
+                companions: Vec::new(),
+                is_live: true,
+                alias: None,
+            });
+        }
+        
         // Log scan errors but don't fail the entire operation
         if !scan_errors.is_empty() {
             log::warn!("Encountered {} errors during profile scan:", scan_errors.len());
@@ -192,6 +466,11 @@ impl ConfigService {
         }
         
         self.profiles = profiles.clone();
+        
+        log::debug!(
+            "scan_profiles took {:?} ({} profiles, {} errors)",
+            started.elapsed(), profiles.len(), scan_errors.len()
+        );
         Ok(profiles)
     }
     
@@ -206,35 +485,87 @@ impl ConfigService {
             return Err(AppError::ConfigError("Profile name too long (max 255 characters)".to_string()));
         }
         
-        // Use cached content if available
-        let content = self.get_file_content_cached(path)?;
+        if name == LIVE_PROFILE_NAME {
+            return Err(AppError::ConfigError(format!("Profile name '{}' is reserved", name)));
+        }
         
-        // Validate JSON format with detailed error reporting
-        match serde_json::from_str::<serde_json::Value>(&content) {
-            Ok(_) => {
-                // JSON is valid
-                log::debug!("Profile {} loaded successfully", name);
-            }
-            Err(e) => {
-                return Err(AppError::ConfigError(
-                    format!("Invalid JSON in profile {}: {} (line {}, column {})", 
-                        name, 
-                        e.to_string(),
-                        e.line(),
-                        e.column()
-                    )
-                ));
-            }
+        // Refuse oversized files before reading them into memory
+        let size = fs::metadata(path)?.len();
+        if size > self.max_profile_size {
+            return Err(AppError::ConfigError(
+                format!("Profile {} is too large ({} bytes, max {} bytes)", name, size, self.max_profile_size)
+            ));
         }
         
+        // Use cached content if available
+        let raw_content = self.get_file_content_cached(path)?;
+        
+        // TOML profiles are converted to JSON up front so comparison and
+        // switching work on the same representation as JSON profiles
+        let content = if Self::is_toml_profile(path) {
+            Self::convert_toml_to_json(name, &raw_content)?
+        } else {
+            raw_content
+        };
+        
+        Self::validate_profile_json(name, &content)?;
+        log::debug!("Profile {} loaded successfully", name);
+        
         Ok(Profile {
             name: name.to_string(),
             path: path.to_path_buf(),
             content,
             is_active: false, // Will be updated by update_profile_status_optimized
+            locked: false,    // Will be read from the meta sidecar by scan_profiles
+            companions: Vec::new(),  // Will be extracted by scan_profiles after extends is resolved
+            is_live: false,
+            alias: None,      // Will be read from the meta sidecar by scan_profiles
         })
     }
     
+    /// Extract the profile name from a "<name>.settings.json" or "<name>.settings.toml" filename
+    fn profile_name_from_filename(filename: &str) -> Option<&str> {
+        if filename == "settings.json" {
+            return None;
+        }
+        
+        filename.strip_suffix(".settings.json")
+            .or_else(|| filename.strip_suffix(".settings.toml"))
+    }
+    
+    /// Check whether a file is a backup or temp file written by CCCS
+    fn is_excluded_file(filename: &str) -> bool {
+        EXCLUDED_FILE_MARKERS.iter().any(|marker| filename.contains(marker))
+    }
+    
+    /// Check whether a profile file is TOML-formatted (picked by extension)
+    fn is_toml_profile(path: &Path) -> bool {
+        path.extension().and_then(|ext| ext.to_str()) == Some("toml")
+    }
+    
+    /// Parse TOML profile content and re-serialize it as JSON
+    fn convert_toml_to_json(name: &str, toml_content: &str) -> AppResult<String> {
+        let value = toml::from_str::<serde_json::Value>(toml_content)
+            .map_err(|e| {
+                // Report the error position in the same shape as JSON parse errors
+                let offset = e.span().map(|span| span.start).unwrap_or(0);
+                let before = &toml_content[..offset.min(toml_content.len())];
+                let line = before.matches('\n').count() + 1;
+                let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+                AppError::ConfigError(
+                    format!("Invalid TOML in profile {}: {} (line {}, column {})", 
+                        name, 
+                        e.message().trim(),
+                        line,
+                        column
+                    )
+                )
+            })?;
+        
+        serde_json::to_string_pretty(&value)
+            .map_err(|e| AppError::ConfigError(format!("Failed to convert TOML profile {} to JSON: {}", name, e)))
+    }
+    
     /// Update the active status of profiles by comparing with default settings (optimized)
     fn update_profile_status_optimized(&mut self, profiles: &mut [Profile]) -> AppResult<()> {
         if profiles.is_empty() {
@@ -266,7 +597,13 @@ impl ConfigService {
         };
         
         for profile in profiles.iter_mut() {
-            profile.is_active = self.compare_configurations_optimized(&profile.content, &default_json);
+            if profile.is_live {
+                profile.content = default_content.clone();
+                profile.is_active = true;
+                continue;
+            }
+            profile.is_active = !self.profile_load_errors.contains_key(&profile.name)
+                && self.compare_configurations_optimized(&profile.content, &default_json);
         }
         
         Ok(())
@@ -275,7 +612,7 @@ impl ConfigService {
     /// Compare configuration with pre-parsed default JSON for better performance
     fn compare_configurations_optimized(&self, profile_content: &str, default_json: &serde_json::Value) -> bool {
         match serde_json::from_str::<serde_json::Value>(profile_content) {
-            Ok(profile_json) => profile_json == *default_json,
+            Ok(profile_json) => self.switch_result(profile_json, default_json) == *default_json,
             Err(e) => {
                 log::warn!("Failed to parse profile content as JSON: {}", e);
                 false
@@ -285,55 +622,113 @@ impl ConfigService {
     
     /// Get detailed profile status with smart comparison
     pub fn get_detailed_profile_status(&self, profile_content: &str) -> ProfileStatus {
-        let default_content = match fs::read_to_string(&self.default_settings_path) {
-            Ok(content) => content,
+        match self.default_settings_for_comparison() {
+            Ok(default_json) => self.status_against(profile_content, &default_json),
+            Err(status) => status,
+        }
+    }
+    
+    /// Compare profile content with already parsed settings.json
+    fn status_against(&self, profile_content: &str, default_json: &serde_json::Value) -> ProfileStatus {
+        let profile_json = match serde_json::from_str::<serde_json::Value>(profile_content) {
+            Ok(profile) => profile,
             Err(e) => {
-                return ProfileStatus::Error(format!("Failed to read default settings: {}", e));
+                return ProfileStatus::Error { message: format!("Invalid profile JSON: {}", e) };
             }
         };
         
-        let (default_json, profile_json) = match (
-            serde_json::from_str::<serde_json::Value>(&default_content),
-            serde_json::from_str::<serde_json::Value>(profile_content),
-        ) {
-            (Ok(default), Ok(profile)) => (default, profile),
-            (Err(e), _) => {
-                return ProfileStatus::Error(format!("Invalid default settings JSON: {}", e));
-            }
-            (_, Err(e)) => {
-                return ProfileStatus::Error(format!("Invalid profile JSON: {}", e));
-            }
-        };
+        // Compare what settings.json would become, so local-only keys don't matter in merge mode
+        let profile_json = self.switch_result(profile_json, default_json);
+        
+        // Whitelist mode replaces the model-ignoring comparison entirely
+        if let Some(keys) = &self.compare_keys {
+            return if Self::compare_json_keys(&profile_json, default_json, keys) {
+                ProfileStatus::FullMatch
+            } else {
+                ProfileStatus::NoMatch
+            };
+        }
         
         // Check for full match first
-        if profile_json == default_json {
+        if profile_json == *default_json {
             return ProfileStatus::FullMatch;
         }
         
-        // Always check if only model field is different
-        let matches_ignoring_model = self.compare_json_ignoring_field(&profile_json, &default_json, "model");
-        if matches_ignoring_model {
-            return ProfileStatus::PartialMatch;
+        // Always check if only the partial match fields are different
+        if self.compare_json_ignoring_fields(&profile_json, default_json, PARTIAL_MATCH_FIELDS) {
+            return ProfileStatus::PartialMatch {
+                fields: PARTIAL_MATCH_FIELDS.iter().map(|field| field.to_string()).collect(),
+            };
         }
         
         ProfileStatus::NoMatch
     }
     
-    /// Compare two JSON values while ignoring a specific field
-    fn compare_json_ignoring_field(
+    /// What settings.json would contain after switching to `profile_json` in the current mode
+    fn switch_result(&self, profile_json: serde_json::Value, settings_json: &serde_json::Value) -> serde_json::Value {
+        self.switch_result_in_mode(profile_json, settings_json, self.switch_mode)
+    }
+    
+    fn switch_result_in_mode(
+        &self,
+        profile_json: serde_json::Value,
+        settings_json: &serde_json::Value,
+        mode: SwitchMode,
+    ) -> serde_json::Value {
+        match mode {
+            SwitchMode::Merge => {
+                let mut merged = settings_json.clone();
+                Self::deep_merge(&mut merged, profile_json);
+                merged
+            }
+            SwitchMode::Replace if self.env_merge && Self::has_env_block(&profile_json) => {
+                Self::merge_env(&profile_json, settings_json)
+            }
+            SwitchMode::Replace => profile_json,
+        }
+    }
+    
+    fn has_env_block(json: &serde_json::Value) -> bool {
+        json.get(ENV_KEY).is_some_and(|env| env.is_object())
+    }
+    
+    /// Apply a profile's top-level keys onto settings, merging `env` variable by variable
+    fn merge_env(profile_json: &serde_json::Value, settings_json: &serde_json::Value) -> serde_json::Value {
+        let (serde_json::Value::Object(profile_map), serde_json::Value::Object(settings_map)) = (profile_json, settings_json) else {
+            return profile_json.clone();
+        };
+        
+        let mut merged = settings_map.clone();
+        for (key, value) in profile_map {
+            match (merged.get_mut(key), value) {
+                (Some(serde_json::Value::Object(current_env)), serde_json::Value::Object(env)) if key == ENV_KEY => {
+                    current_env.extend(env.clone());
+                }
+                _ => {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        serde_json::Value::Object(merged)
+    }
+    
+    /// Compare two JSON values while ignoring specific top-level fields
+    fn compare_json_ignoring_fields(
         &self,
         json1: &serde_json::Value,
         json2: &serde_json::Value,
-        ignore_field: &str,
+        ignore_fields: &[&str],
     ) -> bool {
         match (json1, json2) {
             (serde_json::Value::Object(obj1), serde_json::Value::Object(obj2)) => {
-                // Create modified copies without the ignored field
+                // Create modified copies without the ignored fields
                 let mut filtered_obj1 = obj1.clone();
                 let mut filtered_obj2 = obj2.clone();
                 
-                filtered_obj1.remove(ignore_field);
-                filtered_obj2.remove(ignore_field);
+                for field in ignore_fields {
+                    filtered_obj1.remove(*field);
+                    filtered_obj2.remove(*field);
+                }
                 
                 filtered_obj1 == filtered_obj2
             }
@@ -341,400 +736,2752 @@ impl ConfigService {
         }
     }
     
-    /// Read the default settings.json content
-    fn read_default_settings(&self) -> AppResult<String> {
-        fs::read_to_string(&self.default_settings_path)
-            .map_err(|e| AppError::ConfigError(format!("Failed to read default settings: {}", e)))
+    /// Replace the content of profiles using `"extends"` with their resolved content
+    ///
+    /// Profiles that can't be resolved (unknown base, cycle) get a load error like any
+    /// other broken profile. Returns the errors for the scan log.
+    fn resolve_extends(&mut self, profiles: &mut [Profile]) -> Vec<String> {
+        let sources: HashMap<String, serde_json::Value> = profiles.iter()
+            .filter(|p| !self.profile_load_errors.contains_key(&p.name))
+            .filter_map(|p| serde_json::from_str(&p.content).ok().map(|json| (p.name.clone(), json)))
+            .collect();
+        
+        let mut errors = Vec::new();
+        for profile in profiles.iter_mut() {
+            if !sources.get(&profile.name).is_some_and(|json| json.get(EXTENDS_KEY).is_some()) {
+                continue;
+            }
+            
+            let resolved = Self::resolve_profile_json(&profile.name, &sources)
+                .and_then(|json| serde_json::to_string_pretty(&json).map_err(AppError::from));
+            match resolved {
+                Ok(content) => profile.content = content,
+                Err(e) => {
+                    errors.push(format!("Failed to resolve profile {}: {}", profile.name, e));
+                    self.profile_load_errors.insert(profile.name.clone(), e.to_string());
+                    profile.content = String::new();
+                }
+            }
+        }
+        
+        errors
     }
     
-    /// Compare two configuration contents for equality
-    fn compare_configurations(&self, profile_content: &str, default_content: &str) -> bool {
-        // Parse both as JSON and compare to ignore formatting differences
-        match (
-            serde_json::from_str::<serde_json::Value>(profile_content),
-            serde_json::from_str::<serde_json::Value>(default_content),
-        ) {
-            (Ok(profile_json), Ok(default_json)) => profile_json == default_json,
-            _ => false,
+    /// Move each profile's `"companions"` manifest out of its content into `Profile::companions`
+    ///
+    /// The manifest is never written to settings.json. Profiles with an invalid manifest get a
+    /// load error. Returns the errors for the scan log.
+    fn extract_companions(&mut self, profiles: &mut [Profile]) -> Vec<String> {
+        let mut errors = Vec::new();
+        for profile in profiles.iter_mut() {
+            if self.profile_load_errors.contains_key(&profile.name) {
+                continue;
+            }
+            let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&profile.content) else {
+                continue;
+            };
+            let Some(manifest) = json.as_object_mut().and_then(|map| map.remove(COMPANIONS_KEY)) else {
+                continue;
+            };
+            
+            let companions = Self::parse_companions(manifest)
+                .and_then(|companions| Ok((companions, serde_json::to_string_pretty(&json)?)));
+            match companions {
+                Ok((companions, content)) => {
+                    profile.companions = companions;
+                    profile.content = content;
+                }
+                Err(e) => {
+                    errors.push(format!("Invalid companions in profile {}: {}", profile.name, e));
+                    self.profile_load_errors.insert(profile.name.clone(), e.to_string());
+                    profile.content = String::new();
+                }
+            }
         }
+        
+        errors
     }
     
-    /// Get the status of all profiles with detailed comparison
-    pub fn compare_profiles(&self) -> Vec<ProfileStatus> {
-        let mut statuses = Vec::new();
+    /// Parse and check a companions manifest, only plain file names inside the Claude directory are allowed
+    fn parse_companions(manifest: serde_json::Value) -> AppResult<Vec<CompanionFile>> {
+        let companions: Vec<CompanionFile> = serde_json::from_value(manifest)
+            .map_err(|e| AppError::ConfigError(format!("Invalid \"{}\" manifest: {}", COMPANIONS_KEY, e)))?;
         
-        for profile in &self.profiles {
-            let status = self.get_detailed_profile_status(&profile.content);
-            statuses.push(status);
+        let is_plain_file_name = |name: &str| {
+            !name.is_empty() && Path::new(name).file_name().is_some_and(|file_name| file_name == name)
+        };
+        for companion in &companions {
+            if !is_plain_file_name(&companion.target) || !is_plain_file_name(&companion.source) {
+                return Err(AppError::ConfigError(format!(
+                    "Companion files must be plain file names in the Claude directory: {} <- {}",
+                    companion.target, companion.source
+                )));
+            }
+            if companion.target == "settings.json" || Self::profile_name_from_filename(&companion.target).is_some() {
+                return Err(AppError::ConfigError(format!(
+                    "Companion target {} would overwrite settings.json or a profile", companion.target
+                )));
+            }
         }
         
-        statuses
+        Ok(companions)
     }
     
-    /// Get the status of a specific profile with detailed comparison
-    pub fn get_profile_status(&self, profile_name: &str) -> ProfileStatus {
-        if let Some(profile) = self.profiles.iter().find(|p| p.name == profile_name) {
-            self.get_detailed_profile_status(&profile.content)
-        } else {
-            ProfileStatus::Error(format!("Profile '{}' not found", profile_name))
+    /// Follow a profile's `"extends"` chain and merge it, base first, without the `"extends"` keys
+    fn resolve_profile_json(
+        profile_name: &str,
+        sources: &HashMap<String, serde_json::Value>,
+    ) -> AppResult<serde_json::Value> {
+        let mut chain: Vec<&str> = Vec::new();
+        let mut current = profile_name;
+        
+        loop {
+            if chain.contains(&current) {
+                chain.push(current);
+                return Err(AppError::ConfigError(
+                    format!("Profile extends cycle: {}", chain.join(" -> "))
+                ));
+            }
+            
+            let json = sources.get(current).ok_or_else(|| AppError::ConfigError(
+                format!("Profile '{}' extends '{}', which doesn't exist or failed to load", chain.last().unwrap_or(&profile_name), current)
+            ))?;
+            chain.push(current);
+            
+            match json.get(EXTENDS_KEY) {
+                None => break,
+                Some(serde_json::Value::String(base)) => current = base,
+                Some(_) => {
+                    return Err(AppError::ConfigError(
+                        format!("Profile '{}' has an invalid \"{}\" value, expected a profile name", current, EXTENDS_KEY)
+                    ));
+                }
+            }
+        }
+        
+        let mut merged = serde_json::Value::Object(serde_json::Map::new());
+        for name in chain.iter().rev() {
+            let mut layer = sources[*name].clone();
+            if let Some(map) = layer.as_object_mut() {
+                map.remove(EXTENDS_KEY);
+            }
+            Self::deep_merge(&mut merged, layer);
         }
+        
+        Ok(merged)
     }
     
-    /// Calculate CRC32 checksum for content comparison
-    pub fn calculate_checksum(content: &str) -> u32 {
-        crc32fast::hash(content.as_bytes())
+    /// Merge `overlay` into `base`, objects are merged key by key and anything else is replaced
+    fn deep_merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+        match (base, overlay) {
+            (serde_json::Value::Object(base_map), serde_json::Value::Object(overlay_map)) => {
+                for (key, value) in overlay_map {
+                    Self::deep_merge(base_map.entry(key).or_insert(serde_json::Value::Null), value);
+                }
+            }
+            (base, overlay) => *base = overlay,
+        }
     }
     
-    /// Get file metadata for monitoring purposes
-    pub fn get_file_metadata(&self, path: &Path) -> AppResult<FileMetadata> {
-        let metadata = fs::metadata(path)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to get file metadata: {}", e)))?;
-        
-        let content = fs::read_to_string(path)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to read file: {}", e)))?;
+    /// Compare only the given dotted keys, a key missing on both sides counts as equal
+    fn compare_json_keys(json1: &serde_json::Value, json2: &serde_json::Value, keys: &[String]) -> bool {
+        let lookup = |json: &'_ serde_json::Value, key: &str| {
+            key.split('.').try_fold(json, |value, part| value.get(part)).cloned()
+        };
         
-        Ok(FileMetadata {
-            modified_time: metadata.modified()
-                .map_err(|e| AppError::FileSystemError(format!("Failed to get modification time: {}", e)))?,
-            checksum: Self::calculate_checksum(&content),
-            size: metadata.len(),
-        })
+        keys.iter().all(|key| lookup(json1, key) == lookup(json2, key))
     }
     
-    /// Get paths of all monitored configuration files
-    pub fn get_monitored_files(&self) -> Vec<PathBuf> {
-        let mut files = vec![self.default_settings_path.clone()];
-        
-        for profile in &self.profiles {
-            files.push(profile.path.clone());
+    /// Recursively collect per-path differences between two JSON values
+    fn collect_json_diffs(
+        path: &str,
+        left: &serde_json::Value,
+        right: &serde_json::Value,
+        diffs: &mut Vec<FieldDiff>,
+    ) {
+        match (left, right) {
+            (serde_json::Value::Object(left_obj), serde_json::Value::Object(right_obj)) => {
+                // Walk the union of keys in a stable order
+                let mut keys: Vec<&String> = left_obj.keys().chain(right_obj.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                
+                for key in keys {
+                    let child_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", path, key)
+                    };
+                    
+                    match (left_obj.get(key), right_obj.get(key)) {
+                        (Some(l), Some(r)) => Self::collect_json_diffs(&child_path, l, r, diffs),
+                        (l, r) => diffs.push(FieldDiff {
+                            path: child_path,
+                            left: l.cloned(),
+                            right: r.cloned(),
+                        }),
+                    }
+                }
+            }
+            _ => {
+                if left != right {
+                    diffs.push(FieldDiff {
+                        path: path.to_string(),
+                        left: Some(left.clone()),
+                        right: Some(right.clone()),
+                    });
+                }
+            }
         }
-        
-        files
     }
     
-    /// Get current profiles
-    pub fn get_profiles(&self) -> &[Profile] {
-        &self.profiles
+    /// Name of the profile at a zero-based position in the menu, not counting the live profile
+    pub fn profile_name_at(&self, index: usize) -> AppResult<String> {
+        let mut file_profiles = self.profiles.iter().filter(|p| !p.is_live);
+        let count = file_profiles.clone().count();
+        file_profiles.nth(index)
+            .map(|p| p.name.clone())
+            .ok_or_else(|| AppError::ConfigError(format!(
+                "Profile index {} is out of range, there are {} profiles", index, count
+            )))
     }
     
-    /// Switch to a specific profile configuration with enhanced error handling
-    pub fn switch_profile(&mut self, profile_name: &str) -> AppResult<()> {
-        log::info!("Attempting to switch to profile: {}", profile_name);
-        
-        // Input validation
-        if profile_name.is_empty() {
-            return Err(AppError::ConfigError("Profile name cannot be empty".to_string()));
+    /// Look up a profile by a name that may come with stray whitespace or different casing
+    ///
+    /// An exact match wins, then an alias, then the trimmed name, then a case-insensitive
+    /// match as long as only one profile matches that way.
+    fn lookup_profile(&self, requested: &str) -> Option<&Profile> {
+        if let Some(profile) = self.profiles.iter().find(|p| p.name == requested) {
+            return Some(profile);
         }
         
-        // Find the profile
-        let profile = self.profiles.iter()
-            .find(|p| p.name == profile_name)
-            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))?;
+        let trimmed = requested.trim();
+        if let Some(profile) = self.profiles.iter().find(|p| p.alias.as_deref() == Some(trimmed)) {
+            log::debug!("Profile alias '{}' matched '{}'", requested, profile.name);
+            return Some(profile);
+        }
         
-        // Check if profile is already active
-        if profile.is_active {
-            log::info!("Profile '{}' is already active, no action needed", profile_name);
-            return Ok(());
+        if let Some(profile) = self.profiles.iter().find(|p| p.name == trimmed) {
+            log::debug!("Profile name '{}' matched '{}' after trimming", requested, profile.name);
+            return Some(profile);
         }
         
-        // Validate profile content before switching
-        match serde_json::from_str::<serde_json::Value>(&profile.content) {
-            Ok(_) => {
-                log::debug!("Profile content validation passed for: {}", profile_name);
+        let lowercase = trimmed.to_lowercase();
+        let mut matches = self.profiles.iter().filter(|p| p.name.to_lowercase() == lowercase);
+        match (matches.next(), matches.next()) {
+            (Some(profile), None) => {
+                log::info!("Profile name '{}' matched '{}' ignoring case", requested, profile.name);
+                Some(profile)
             }
-            Err(e) => {
-                return Err(AppError::ConfigError(
-                    format!("Profile '{}' contains invalid JSON: {}", profile_name, e)
-                ));
+            (Some(_), Some(_)) => {
+                log::warn!("Profile name '{}' matches several profiles ignoring case", requested);
+                None
             }
+            _ => None,
         }
+    }
+    
+    /// Find a loaded profile by name
+    fn find_profile(&self, profile_name: &str) -> AppResult<&Profile> {
+        self.profiles.iter()
+            .find(|p| p.name == profile_name)
+            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))
+    }
+    
+    /// Get the per-field differences between two profiles
+    pub fn diff_profiles(&self, profile_a: &str, profile_b: &str) -> AppResult<Vec<FieldDiff>> {
+        let parse = |profile: &Profile| {
+            serde_json::from_str::<serde_json::Value>(&profile.content)
+                .map_err(|e| AppError::ConfigError(
+                    format!("Profile '{}' contains invalid JSON: {}", profile.name, e)
+                ))
+        };
         
-        // Pre-flight checks
-        if !self.default_settings_path.exists() {
-            return Err(AppError::FileSystemError(
-                "Default settings file does not exist".to_string()
-            ));
-        }
+        let json_a = parse(self.find_profile(profile_a)?)?;
+        let json_b = parse(self.find_profile(profile_b)?)?;
         
-        // Check if settings file is writable
-        let test_write_path = self.default_settings_path.with_extension("json.write_test");
-        if let Err(e) = fs::write(&test_write_path, "test") {
-            return Err(AppError::FileSystemError(
-                format!("Cannot write to settings directory: {}", e)
+        let mut diffs = Vec::new();
+        Self::collect_json_diffs("", &json_a, &json_b, &mut diffs);
+        
+        log::debug!("Found {} differences between '{}' and '{}'", diffs.len(), profile_a, profile_b);
+        Ok(diffs)
+    }
+    
+    /// Compare any JSON or TOML file with settings.json, as if it were a profile
+    ///
+    /// The file isn't registered or copied, useful before importing it.
+    pub fn compare_with_file(&self, path: &Path) -> AppResult<FileComparison> {
+        let name = path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        let raw = fs::read_to_string(path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to read {:?}: {}", path, e)))?;
+        
+        let content = if Self::is_toml_profile(path) {
+            Self::convert_toml_to_json(&name, &raw)?
+        } else {
+            raw
+        };
+        Self::validate_profile_json(&name, &content)?;
+        
+        let file_json = serde_json::from_str::<serde_json::Value>(&content)?;
+        let settings_json = serde_json::from_str::<serde_json::Value>(&self.read_default_settings()?)
+            .map_err(|e| AppError::ConfigError(format!("Invalid default settings JSON: {}", e)))?;
+        
+        let mut diffs = Vec::new();
+        Self::collect_json_diffs("", &file_json, &settings_json, &mut diffs);
+        
+        Ok(FileComparison {
+            status: self.get_detailed_profile_status(&content),
+            diffs,
+        })
+    }
+    
+    /// Validate profile content as a JSON object with detailed error reporting
+    fn validate_profile_json(name: &str, content: &str) -> AppResult<()> {
+        let validation = Self::check_profile_json(content);
+        match validation.error {
+            Some(error) => Err(AppError::ConfigError(
+                format!("Invalid JSON in profile {}: {} (line {}, column {})", 
+                    name, 
+                    error.message,
+                    error.line,
+                    error.column
+                )
+            )),
+            // Only objects can be written as settings.json, reject other shapes up front
+            None if !validation.is_object => Err(AppError::ConfigError(
+                format!("Profile {} must contain a JSON object at the top level", name)
+            )),
+            None => Ok(()),
+        }
+    }
+    
+    /// Check content as profile JSON for live validation in the editor
+    pub fn check_profile_json(content: &str) -> JsonValidation {
+        match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(json) => JsonValidation {
+                valid: json.is_object(),
+                is_object: json.is_object(),
+                error: None,
+            },
+            Err(e) => {
+                // serde_json appends the position to its message, it's reported separately here
+                let message = e.to_string();
+                let suffix = format!(" at line {} column {}", e.line(), e.column());
+                JsonValidation {
+                    valid: false,
+                    is_object: false,
+                    error: Some(JsonSyntaxError {
+                        message: message.strip_suffix(&suffix).unwrap_or(&message).to_string(),
+                        line: e.line(),
+                        column: e.column(),
+                    }),
+                }
+            }
+        }
+    }
+    
+    /// Replace a profile's content with edited JSON and refresh its status
+    pub fn save_profile_content(&mut self, name: &str, content: &str) -> AppResult<()> {
+        let profile = self.find_profile(name)?;
+        if profile.is_live {
+            return Err(AppError::ConfigError(format!("Profile '{}' is read-only", name)));
+        }
+        let path = profile.path.clone();
+        
+        if Self::is_toml_profile(&path) {
+            return Err(AppError::ConfigError(
+                format!("Profile '{}' is a TOML profile and can't be saved as JSON", name)
             ));
         }
-        let _ = fs::remove_file(&test_write_path);
         
-        // Create backup of current settings with timestamp
-        let timestamp = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-        let backup_path = self.default_settings_path.with_extension(format!("json.backup.{}", timestamp));
+        Self::validate_profile_json(name, content)?;
+        self.write_file_atomic(&path, content)?;
+        log::info!("Saved new content for profile '{}'", name);
         
-        if let Err(e) = self.create_backup(&backup_path) {
-            return Err(AppError::FileSystemError(
-                format!("Failed to create backup before switching: {}", e)
+        // Drop the stale cache entry and rebuild statuses from disk
+        self.profile_cache.remove(&path);
+        self.scan_profiles()?;
+        Ok(())
+    }
+    
+    /// Pretty-print a profile file in place, returns whether the file changed
+    ///
+    /// Only the formatting changes, so the profile's status stays the same.
+    pub fn format_profile(&mut self, name: &str) -> AppResult<bool> {
+        let changed = self.format_profile_file(name)?;
+        if changed {
+            log::info!("Formatted profile '{}'", name);
+            self.scan_profiles()?;
+        }
+        Ok(changed)
+    }
+    
+    /// Pretty-print every JSON profile file, profiles that can't be formatted are reported and skipped
+    pub fn format_all_profiles(&mut self) -> AppResult<ProfileFormatReport> {
+        let names: Vec<String> = self.profiles.iter()
+            .filter(|p| !p.is_live && !Self::is_toml_profile(&p.path))
+            .map(|p| p.name.clone())
+            .collect();
+        
+        let mut report = ProfileFormatReport::default();
+        for name in names {
+            match self.format_profile_file(&name) {
+                Ok(true) => report.formatted.push(name),
+                Ok(false) => {}
+                Err(e) => report.errors.push(format!("{}: {}", name, e)),
+            }
+        }
+        
+        log::info!("Formatted {} profiles, {} failed", report.formatted.len(), report.errors.len());
+        if !report.formatted.is_empty() {
+            self.scan_profiles()?;
+        }
+        Ok(report)
+    }
+    
+    /// Rewrite a profile file with normalized formatting, without rescanning
+    fn format_profile_file(&mut self, name: &str) -> AppResult<bool> {
+        let profile = self.find_profile(name)?;
+        if profile.is_live {
+            return Err(AppError::ConfigError(format!("Profile '{}' is read-only", name)));
+        }
+        let path = profile.path.clone();
+        if Self::is_toml_profile(&path) {
+            return Err(AppError::ConfigError(
+                format!("Profile '{}' is a TOML profile and can't be formatted as JSON", name)
             ));
         }
         
-        // Perform atomic switch operation with rollback on failure
-        match self.perform_switch_atomic(&profile.content) {
-            Ok(()) => {
-                log::info!("Successfully switched to profile: {}", profile_name);
-                
-                // Clear caches since files have changed
-                self.clear_cache();
-                
-                // Update profile status after successful switch
-                if let Err(e) = self.refresh_profile_status() {
-                    log::warn!("Failed to refresh profile status after switch: {}", e);
-                    // Don't fail the operation since switch was successful
+        // The file itself, so "extends" and "companions" are kept as written
+        let content = fs::read_to_string(&path)?;
+        Self::validate_profile_json(name, &content)?;
+        let formatted = Self::normalize_settings_json(&content)?;
+        if formatted == content {
+            return Ok(false);
+        }
+        
+        self.write_file_atomic(&path, &formatted)?;
+        self.profile_cache.remove(&path);
+        Ok(true)
+    }
+    
+    /// Get the raw content of the current settings.json
+    pub fn get_current_settings_content(&mut self) -> AppResult<String> {
+        self.get_default_settings_cached()
+    }
+    
+    /// Content of a single profile as written in its file (TOML profiles are returned as their JSON conversion)
+    ///
+    /// This is what the editor loads and saves back, so `"extends"` and `"companions"` are kept.
+    pub fn get_profile_content(&self, profile_name: &str) -> AppResult<String> {
+        let profile = self.loaded_profile(profile_name)?;
+        if profile.is_live {
+            return Ok(profile.content.clone());
+        }
+        
+        let content = fs::read_to_string(&profile.path)?;
+        if Self::is_toml_profile(&profile.path) {
+            Self::convert_toml_to_json(profile_name, &content)
+        } else {
+            Ok(content)
+        }
+    }
+    
+    /// A profile that loaded successfully, its content resolved through `"extends"`
+    fn loaded_profile(&self, profile_name: &str) -> AppResult<&Profile> {
+        let profile = self.profiles.iter()
+            .find(|p| p.name == profile_name)
+            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))?;
+        
+        if let Some(error) = self.profile_load_errors.get(profile_name) {
+            return Err(AppError::ConfigError(
+                format!("Profile '{}' failed to load: {}", profile_name, error)
+            ));
+        }
+        
+        Ok(profile)
+    }
+    
+    /// Exactly what switching to a profile would write to settings.json
+    pub fn normalized_content(&self, profile_name: &str) -> AppResult<String> {
+        let profile = self.loaded_profile(profile_name)?;
+        Self::normalize_settings_json(&self.switch_content(&profile.content, self.switch_mode)?)
+    }
+    
+    /// settings.json content for switching to a profile, merged onto the current file in merge mode
+    fn switch_content(&self, profile_content: &str, mode: SwitchMode) -> AppResult<String> {
+        let profile_json = serde_json::from_str::<serde_json::Value>(profile_content)
+            .map_err(|e| AppError::ConfigError(format!("Invalid profile JSON: {}", e)))?;
+        
+        // A plain replace doesn't depend on the current settings.json
+        let uses_settings = mode == SwitchMode::Merge
+            || (self.env_merge && Self::has_env_block(&profile_json))
+            || self.preserve_partial_match_fields;
+        if !uses_settings {
+            return Ok(profile_content.to_string());
+        }
+        if !profile_json.is_object() {
+            return Err(AppError::ConfigError("Configuration must be a JSON object".to_string()));
+        }
+        
+        let settings_content = fs::read_to_string(&self.default_settings_path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to read default settings: {}", e)))?;
+        let settings_json = serde_json::from_str::<serde_json::Value>(&settings_content)
+            .map_err(|e| AppError::ConfigError(format!("Invalid default settings JSON: {}", e)))?;
+        
+        let mut result = self.switch_result_in_mode(profile_json, &settings_json, mode);
+        if self.preserve_partial_match_fields {
+            Self::copy_fields(&mut result, &settings_json, PARTIAL_MATCH_FIELDS);
+        }
+        Ok(serde_json::to_string(&result)?)
+    }
+    
+    /// Make `fields` in `target` match `source`, removing the ones `source` doesn't have
+    fn copy_fields(target: &mut serde_json::Value, source: &serde_json::Value, fields: &[&str]) {
+        let serde_json::Value::Object(target_map) = target else {
+            return;
+        };
+        for field in fields {
+            match source.get(*field) {
+                Some(value) => {
+                    target_map.insert(field.to_string(), value.clone());
+                }
+                None => {
+                    target_map.remove(*field);
                 }
-                
-                // Remove backup file on success (keep only a few recent backups)
-                self.cleanup_old_backups();
-                
-                Ok(())
             }
-            Err(e) => {
-                log::error!("Failed to switch profile, attempting rollback: {}", e);
-                
-                // Attempt to restore from backup
-                if let Err(rollback_err) = self.restore_from_backup(&backup_path) {
-                    log::error!("CRITICAL: Failed to rollback after failed switch: {}", rollback_err);
-                    return Err(AppError::ConfigError(
-                        format!("Profile switch failed and rollback failed. Original error: {}. Rollback error: {}", e, rollback_err)
-                    ));
+        }
+    }
+    
+    /// A profile's file as it is on disk, paired with what a switch would write
+    pub fn content_preview(&self, profile_name: &str) -> AppResult<ProfileContentPreview> {
+        let normalized = self.normalized_content(profile_name)?;
+        let profile = self.profiles.iter()
+            .find(|p| p.name == profile_name)
+            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))?;
+        let raw = fs::read_to_string(&profile.path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to read profile {:?}: {}", profile.path, e)))?;
+        
+        Ok(ProfileContentPreview { raw, normalized })
+    }
+    
+    /// Mask values of secret-looking keys (tokens, API keys, ...) anywhere in a JSON document
+    pub fn redact_secrets(content: &str) -> AppResult<String> {
+        fn redact(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    for (key, value) in map.iter_mut() {
+                        if ConfigService::is_secret_key(key) && !value.is_object() && !value.is_array() {
+                            *value = serde_json::Value::String("[REDACTED]".to_string());
+                        } else {
+                            redact(value);
+                        }
+                    }
                 }
-                
-                log::info!("Successfully rolled back after failed switch");
-                Err(e)
+                serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+                _ => {}
             }
         }
+        
+        let mut json = serde_json::from_str::<serde_json::Value>(content)
+            .map_err(|e| AppError::ConfigError(format!("Invalid JSON content: {}", e)))?;
+        redact(&mut json);
+        
+        Ok(serde_json::to_string_pretty(&json)?)
     }
     
-    /// Perform the actual configuration switch with enhanced atomic operation
-    fn perform_switch_atomic(&self, new_content: &str) -> AppResult<()> {
-        // Validate the new content is valid JSON with proper structure
-        let json_value = serde_json::from_str::<serde_json::Value>(new_content)
+    /// Dotted paths of the secret-looking values in a JSON document, e.g. `env.ANTHROPIC_AUTH_TOKEN`
+    pub fn find_secret_keys(content: &str) -> AppResult<Vec<String>> {
+        fn find(path: &str, value: &serde_json::Value, found: &mut Vec<String>) {
+            let join = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+            match value {
+                serde_json::Value::Object(map) => {
+                    for (key, value) in map {
+                        if ConfigService::is_secret_key(key) && !value.is_object() && !value.is_array() {
+                            found.push(join(key));
+                        } else {
+                            find(&join(key), value, found);
+                        }
+                    }
+                }
+                serde_json::Value::Array(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        find(&join(&index.to_string()), item, found);
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        let json = serde_json::from_str::<serde_json::Value>(content)
             .map_err(|e| AppError::ConfigError(format!("Invalid JSON content: {}", e)))?;
+        let mut found = Vec::new();
+        find("", &json, &mut found);
+        Ok(found)
+    }
+    
+    fn is_secret_key(key: &str) -> bool {
+        let key = key.to_lowercase();
+        SECRET_KEY_MARKERS.iter().any(|marker| key.contains(marker))
+    }
+    
+    /// Names of the built-in profile templates
+    pub fn template_names() -> Vec<&'static str> {
+        PROFILE_TEMPLATES.iter().map(|(name, _)| *name).collect()
+    }
+    
+    /// Create a new profile from a built-in template
+    pub fn create_from_template(&mut self, template: &str, new_name: &str) -> AppResult<()> {
+        let body = PROFILE_TEMPLATES.iter()
+            .find(|(name, _)| *name == template)
+            .map(|(_, body)| *body)
+            .ok_or_else(|| AppError::ConfigError(format!("Unknown profile template '{}'", template)))?;
+        
+        Self::validate_new_profile_name(new_name)?;
+        
+        if self.profiles.iter().any(|p| p.name == new_name) {
+            return Err(AppError::ConfigError(format!("Profile '{}' already exists", new_name)));
+        }
+        
+        let path = self.claude_dir.join(format!("{}.settings.json", new_name));
+        if path.exists() {
+            return Err(AppError::ConfigError(format!("Profile file already exists: {:?}", path)));
+        }
+        
+        // Validated names contain no quotes, backslashes or control characters,
+        // so they can be substituted into JSON strings as-is
+        let content = body.replace("{{profile_name}}", new_name);
+        
+        fs::write(&path, content)?;
+        log::info!("Created profile '{}' from template '{}'", new_name, template);
+        
+        self.scan_profiles()?;
+        Ok(())
+    }
+    
+    /// Import an external JSON file as a new profile
+    pub fn import_profile(&mut self, src: &Path, name: &str, overwrite: bool) -> AppResult<()> {
+        Self::validate_new_profile_name(name)?;
+        
+        let size = fs::metadata(src)?.len();
+        if size > self.max_profile_size {
+            return Err(AppError::ConfigError(
+                format!("Import file is too large ({} bytes, max {} bytes)", size, self.max_profile_size)
+            ));
+        }
+        
+        let content = fs::read_to_string(src)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to read import file {:?}: {}", src, e)))?;
+        self.import_profile_content(name, &content, overwrite)?;
+        log::info!("Imported profile '{}' from {:?}", name, src);
+        Ok(())
+    }
+    
+    /// Save JSON from somewhere other than a file, such as the clipboard, as a new profile
+    pub fn import_profile_content(&mut self, name: &str, content: &str, overwrite: bool) -> AppResult<()> {
+        Self::validate_new_profile_name(name)?;
+        
+        if content.len() as u64 > self.max_profile_size {
+            return Err(AppError::ConfigError(
+                format!("Profile content is too large ({} bytes, max {} bytes)", content.len(), self.max_profile_size)
+            ));
+        }
+        Self::validate_profile_json(name, content)?;
+        
+        let target = self.claude_dir.join(format!("{}.settings.json", name));
+        let exists = target.exists() || self.profiles.iter().any(|p| p.name == name);
+        if exists && !overwrite {
+            return Err(AppError::ConfigError(format!("Profile '{}' already exists", name)));
+        }
+        
+        self.write_file_atomic(&target, content)?;
+        log::info!("Saved imported content as profile '{}'", name);
+        
+        self.profile_cache.remove(&target);
+        self.scan_profiles()?;
+        Ok(())
+    }
+    
+    /// Save the current settings.json as a new `snapshot.<timestamp>` profile, returning its name
+    pub fn snapshot_current_settings(&mut self) -> AppResult<String> {
+        let content = self.read_default_settings()?;
+        Self::validate_profile_json("settings", &content)?;
+        
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let name = self.unique_profile_name(&format!("snapshot.{}", timestamp));
+        
+        let target = self.claude_dir.join(format!("{}.settings.json", name));
+        self.write_file_atomic(&target, &content)?;
+        log::info!("Saved current settings as snapshot profile '{}'", name);
+        
+        self.scan_profiles()?;
+        Ok(name)
+    }
+    
+    /// Get a profile name based on `base` that isn't used by any existing profile
+    pub fn unique_profile_name(&self, base: &str) -> String {
+        let taken = |name: &str| {
+            self.profiles.iter().any(|p| p.name == name)
+                || self.claude_dir.join(format!("{}.settings.json", name)).exists()
+        };
+        
+        if !taken(base) {
+            return base.to_string();
+        }
+        
+        (2..)
+            .map(|n| format!("{} {}", base, n))
+            .find(|name| !taken(name))
+            .unwrap_or_else(|| base.to_string())
+    }
+    
+    /// Check that a new profile name can be used as a file name
+    fn validate_new_profile_name(name: &str) -> AppResult<()> {
+        if name.trim().is_empty() {
+            return Err(AppError::ConfigError("Profile name cannot be empty".to_string()));
+        }
+        
+        if name.len() > 255 {
+            return Err(AppError::ConfigError("Profile name too long (max 255 characters)".to_string()));
+        }
+        
+        let invalid_chars = ['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+        if name.contains(invalid_chars) || name.starts_with('.') || name.chars().any(|c| c.is_control()) {
+            return Err(AppError::ConfigError(format!("Invalid profile name '{}'", name)));
+        }
+        
+        Ok(())
+    }
+    
+    /// Read the default settings.json content
+    fn read_default_settings(&self) -> AppResult<String> {
+        fs::read_to_string(&self.default_settings_path)
+            .map_err(|e| AppError::ConfigError(format!("Failed to read default settings: {}", e)))
+    }
+    
+    /// Compare two configuration contents for equality
+    fn compare_configurations(&self, profile_content: &str, default_content: &str) -> AppResult<bool> {
+        // Parse both as JSON and compare to ignore formatting differences
+        let profile_json = serde_json::from_str::<serde_json::Value>(profile_content)
+            .map_err(|e| AppError::ConfigError(format!("Invalid profile JSON: {}", e)))?;
+        let default_json = serde_json::from_str::<serde_json::Value>(default_content)
+            .map_err(|e| AppError::ConfigError(format!("Invalid default settings JSON: {}", e)))?;
+        
+        Ok(profile_json == default_json)
+    }
+    
+    /// Get the status of all profiles with detailed comparison
+    ///
+    /// settings.json is read and parsed once for all of them, not once per profile.
+    pub fn compare_profiles(&self) -> Vec<ProfileStatus> {
+        let default_json = self.default_settings_for_comparison();
+        self.profiles.iter()
+            .map(|profile| self.profile_status_against(profile, &default_json))
+            .collect()
+    }
+    
+    /// Count profiles per status, using the same comparison as the tray menu
+    ///
+    /// The live "(current)" profile always matches and isn't counted.
+    pub fn status_summary(&self) -> StatusSummary {
+        let statuses = self.profiles.iter()
+            .zip(self.compare_profiles())
+            .filter(|(profile, _)| !profile.is_live)
+            .map(|(_, status)| status);
+        statuses.fold(StatusSummary::default(), |mut summary, status| {
+            match status {
+                ProfileStatus::FullMatch => summary.full_match += 1,
+                ProfileStatus::PartialMatch { .. } => summary.partial_match += 1,
+                ProfileStatus::NoMatch => summary.no_match += 1,
+                ProfileStatus::Error { .. } => summary.error += 1,
+            }
+            summary
+        })
+    }
+    
+    /// Get every profile's status keyed by profile name, in profile order
+    pub fn compare_profiles_by_name(&self) -> Vec<(String, ProfileStatus)> {
+        self.profiles.iter()
+            .map(|profile| profile.name.clone())
+            .zip(self.compare_profiles())
+            .collect()
+    }
+    
+    /// Get every profile's name and flattened status, in profile order
+    pub fn profile_summaries(&self) -> Vec<ProfileSummary> {
+        self.profiles.iter()
+            .zip(self.compare_profiles())
+            .map(|(profile, status)| {
+                let status = ProfileStatusKind::from(&status);
+                ProfileSummary {
+                    name: profile.name.clone(),
+                    status,
+                    glyph: self.status_glyphs.glyph(status).to_string(),
+                }
+            })
+            .collect()
+    }
+    
+    /// Every profile with its status, path and size, from the loaded content without touching disk
+    pub fn list_profiles_detailed(&self) -> Vec<ProfileDetails> {
+        self.profiles.iter()
+            .zip(self.compare_profiles())
+            .map(|(profile, status)| ProfileDetails {
+                name: profile.name.clone(),
+                is_active: profile.is_active,
+                status,
+                path: profile.path.to_string_lossy().to_string(),
+                size: profile.content.len() as u64,
+            })
+            .collect()
+    }
+    
+    /// Get the status of a loaded profile, reporting scan-time load errors first
+    fn profile_status(&self, profile: &Profile) -> ProfileStatus {
+        self.profile_status_against(profile, &self.default_settings_for_comparison())
+    }
+    
+    fn profile_status_against(
+        &self,
+        profile: &Profile,
+        default_json: &Result<serde_json::Value, ProfileStatus>,
+    ) -> ProfileStatus {
+        if profile.is_live {
+            return ProfileStatus::FullMatch;
+        }
+        if let Some(error) = self.profile_load_errors.get(&profile.name) {
+            return ProfileStatus::Error { message: error.clone() };
+        }
+        match default_json {
+            Ok(default_json) => self.status_against(&profile.content, default_json),
+            Err(status) => status.clone(),
+        }
+    }
+    
+    /// Get the status of a specific profile with detailed comparison
+    pub fn get_profile_status(&self, profile_name: &str) -> ProfileStatus {
+        if let Some(profile) = self.lookup_profile(profile_name) {
+            self.profile_status(profile)
+        } else {
+            ProfileStatus::Error { message: format!("Profile '{}' not found", profile_name) }
+        }
+    }
+    
+    /// Whether settings.json fully matches a profile, unknown profiles are an error
+    pub fn matches_profile(&self, profile_name: &str) -> AppResult<bool> {
+        self.find_profile(profile_name)?;
+        Ok(matches!(self.get_profile_status(profile_name), ProfileStatus::FullMatch))
+    }
+    
+    /// Calculate CRC32 checksum for content comparison
+    pub fn calculate_checksum(content: &str) -> u32 {
+        crc32fast::hash(content.as_bytes())
+    }
+    
+    /// Checksum and size of a profile file as it is on disk
+    pub fn get_profile_checksum(&self, profile_name: &str) -> AppResult<ProfileChecksum> {
+        let profile = self.profiles.iter()
+            .find(|p| p.name == profile_name)
+            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))?;
+        
+        let metadata = self.get_file_metadata(&profile.path)?;
+        
+        Ok(ProfileChecksum {
+            name: profile.name.clone(),
+            checksum: metadata.checksum,
+            size: metadata.size,
+        })
+    }
+    
+    /// Modification time of each profile file in Unix seconds, reusing cached metadata while it's valid
+    ///
+    /// Profiles whose file can't be read are left out.
+    pub fn get_profile_mtimes(&self) -> Vec<(String, u64)> {
+        self.profiles.iter()
+            .filter_map(|profile| {
+                let modified = match self.profile_cache.get(&profile.path) {
+                    Some(entry) if self.is_cache_valid(entry.last_updated) => entry.metadata.modified_time,
+                    _ => match fs::metadata(&profile.path).and_then(|m| m.modified()) {
+                        Ok(modified) => modified,
+                        Err(e) => {
+                            log::debug!("Failed to get modification time of {:?}: {}", profile.path, e);
+                            return None;
+                        }
+                    },
+                };
+                let seconds = modified.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+                Some((profile.name.clone(), seconds))
+            })
+            .collect()
+    }
+    
+    /// Get file metadata for monitoring purposes
+    pub fn get_file_metadata(&self, path: &Path) -> AppResult<FileMetadata> {
+        let metadata = fs::metadata(path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to get file metadata: {}", e)))?;
+        
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to read file: {}", e)))?;
+        
+        Ok(FileMetadata {
+            modified_time: metadata.modified()
+                .map_err(|e| AppError::FileSystemError(format!("Failed to get modification time: {}", e)))?,
+            checksum: Self::calculate_checksum(&content),
+            size: metadata.len(),
+        })
+    }
+    
+    /// Get paths of all monitored configuration files
+    pub fn get_monitored_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![self.default_settings_path.clone()];
+        
+        // The live profile is settings.json itself, already in the list
+        for profile in self.profiles.iter().filter(|p| !p.is_live) {
+            files.push(profile.path.clone());
+        }
+        
+        files
+    }
+    
+    /// Path of a profile's meta sidecar, `<name>.meta.json` in the Claude directory
+    fn profile_meta_path(&self, profile_name: &str) -> PathBuf {
+        self.claude_dir.join(format!("{}.meta.json", profile_name))
+    }
+    
+    /// Read a profile's meta sidecar, a missing or unreadable sidecar gives the defaults
+    pub fn get_profile_meta(&self, profile_name: &str) -> ProfileMeta {
+        let path = self.profile_meta_path(profile_name);
+        let Ok(content) = fs::read_to_string(&path) else {
+            return ProfileMeta::default();
+        };
+        
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("Ignoring invalid profile meta {:?}: {}", path, e);
+            ProfileMeta::default()
+        })
+    }
+    
+    /// Lock or unlock a profile, stored in its meta sidecar
+    pub fn set_profile_locked(&mut self, profile_name: &str, locked: bool) -> AppResult<()> {
+        if self.find_profile(profile_name)?.is_live {
+            return Err(AppError::ConfigError(format!("Profile '{}' is read-only", profile_name)));
+        }
+        
+        let mut meta = self.get_profile_meta(profile_name);
+        meta.locked = locked;
+        self.write_file_atomic(&self.profile_meta_path(profile_name), &serde_json::to_string_pretty(&meta)?)?;
+        
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == profile_name) {
+            profile.locked = locked;
+        }
+        
+        log::info!("Profile '{}' {}", profile_name, if locked { "locked" } else { "unlocked" });
+        Ok(())
+    }
+    
+    /// Give a profile a shorter label for the tray, or remove it with None
+    ///
+    /// Aliases are unique and can't be another profile's name, so switching by either stays unambiguous.
+    pub fn set_profile_alias(&mut self, profile_name: &str, alias: Option<&str>) -> AppResult<()> {
+        if self.find_profile(profile_name)?.is_live {
+            return Err(AppError::ConfigError(format!("Profile '{}' is read-only", profile_name)));
+        }
+        
+        let alias = alias.map(str::trim).filter(|alias| !alias.is_empty());
+        if let Some(alias) = alias {
+            let taken = self.profiles.iter()
+                .filter(|p| p.name != profile_name)
+                .any(|p| p.name == alias || p.alias.as_deref() == Some(alias));
+            if taken {
+                return Err(AppError::ConfigError(format!("Alias '{}' is already used by another profile", alias)));
+            }
+        }
+        
+        let mut meta = self.get_profile_meta(profile_name);
+        meta.alias = alias.map(str::to_string);
+        self.write_file_atomic(&self.profile_meta_path(profile_name), &serde_json::to_string_pretty(&meta)?)?;
+        
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == profile_name) {
+            profile.alias = meta.alias;
+        }
+        
+        log::info!("Profile '{}' alias set to {:?}", profile_name, alias);
+        Ok(())
+    }
+    
+    /// Load errors for profiles found during the last scan, keyed by profile name
+    pub fn get_profile_load_errors(&self) -> &HashMap<String, String> {
+        &self.profile_load_errors
+    }
+    
+    /// Get current profiles
+    pub fn get_profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+    
+    /// Switch to a specific profile configuration with enhanced error handling
+    #[allow(dead_code)]
+    pub fn switch_profile(&mut self, profile_name: &str) -> AppResult<()> {
+        self.switch_profile_with_force(profile_name, false)
+    }
+    
+    /// Switch profiles, with `force` allowing a switch away from a locked active profile
+    pub fn switch_profile_with_force(&mut self, profile_name: &str, force: bool) -> AppResult<()> {
+        self.switch_profile_in_mode(profile_name, force, self.switch_mode)
+    }
+    
+    /// Switch by deep-merging the profile onto settings.json, whatever the configured mode
+    ///
+    /// Keys the profile doesn't mention (machine-specific paths and such) are kept.
+    #[allow(dead_code)]
+    pub fn merge_switch(&mut self, profile_name: &str) -> AppResult<()> {
+        self.switch_profile_in_mode(profile_name, false, SwitchMode::Merge)
+    }
+    
+    /// Switch to a profile, writing it in the given mode
+    ///
+    /// Writes are serialized: `SWITCH_LOCK` is held across the backup, write, verification
+    /// and any rollback. Reads of settings.json and profiles don't take it and may run
+    /// alongside a switch, every write is an atomic rename so they never see a partial file.
+    fn switch_profile_in_mode(&mut self, profile_name: &str, force: bool, mode: SwitchMode) -> AppResult<()> {
+        let started = Instant::now();
+        log::info!("Attempting to switch to profile: {}", profile_name);
+        
+        // A panic in another switch leaves nothing half-done that the next one can't handle
+        let _switch_guard = SWITCH_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        
+        // Input validation
+        if profile_name.trim().is_empty() {
+            return Err(AppError::ConfigError("Profile name cannot be empty".to_string()));
+        }
+        
+        // Find the profile, from here on under its real name
+        let profile_name = self.lookup_profile(profile_name)
+            .map(|p| p.name.clone())
+            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))?;
+        let profile = self.find_profile(&profile_name)?;
+        
+        // Profiles that failed to load during scanning can't be switched to
+        if let Some(error) = self.profile_load_errors.get(&profile_name) {
+            return Err(AppError::ConfigError(
+                format!("Profile '{}' failed to load: {}", profile_name, error)
+            ));
+        }
+        
+        // The live profile is settings.json as it is, there's nothing to switch
+        if profile.is_live {
+            log::info!("Profile '{}' is the live settings.json, no action needed", profile_name);
+            return Ok(());
+        }
+        
+        // Check if profile is already active
+        if profile.is_active {
+            log::info!("Profile '{}' is already active, no action needed", profile_name);
+            return Ok(());
+        }
+        
+        if !force {
+            if let Some(locked) = self.profiles.iter().find(|p| p.is_active && p.locked) {
+                return Err(AppError::ProfileLocked(format!(
+                    "Profile '{}' is locked, unlock it or force the switch to '{}'", locked.name, profile_name
+                )));
+            }
+        }
+        
+        // Validate profile content before switching
+        match serde_json::from_str::<serde_json::Value>(&profile.content) {
+            Ok(serde_json::Value::Object(map)) if map.is_empty() && !self.allow_empty_profiles => {
+                return Err(AppError::ConfigError(format!(
+                    "Profile '{}' is empty, switching to it would clear settings.json. Allow empty profiles to switch anyway",
+                    profile_name
+                )));
+            }
+            Ok(_) => {
+                log::debug!("Profile content validation passed for: {}", profile_name);
+            }
+            Err(e) => {
+                return Err(AppError::ConfigError(
+                    format!("Profile '{}' contains invalid JSON: {}", profile_name, e)
+                ));
+            }
+        }
+        
+        // Pre-flight checks
+        if !self.default_settings_path.exists() {
+            return Err(AppError::FileSystemError(
+                "Default settings file does not exist".to_string()
+            ));
+        }
+        
+        let new_content = self.switch_content(&profile.content, mode)?;
+        
+        // Companion sources are read up front so a missing one fails before anything is written
+        let companion_writes = profile.companions.iter()
+            .map(|companion| {
+                let source = self.claude_dir.join(&companion.source);
+                fs::read_to_string(&source)
+                    .map(|content| (self.claude_dir.join(&companion.target), content))
+                    .map_err(|e| AppError::FileSystemError(
+                        format!("Failed to read companion file {:?}: {}", source, e)
+                    ))
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        
+        // Nothing to write when settings.json already holds exactly what the switch would produce,
+        // e.g. a profile that only differs from it in formatting
+        if self.is_already_current(&new_content, &companion_writes)? {
+            log::info!("Profile '{}' is already current, skipping switch", profile_name);
+            return Ok(());
+        }
+        
+        // A symlinked settings.json is only written through when allowed
+        let settings_target = self.settings_write_target()?;
+        
+        // Check if settings file is writable
+        let test_write_path = settings_target.with_extension("json.write_test");
+        if let Err(e) = fs::write(&test_write_path, "test") {
+            return Err(AppError::FileSystemError(
+                format!("Cannot write to settings directory: {}", e)
+            ));
+        }
+        let _ = fs::remove_file(&test_write_path);
+        
+        // Create backup of current settings with timestamp
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let backup_path = self.get_backup_dir().join(format!("settings.json.backup.{}", timestamp));
+        
+        if let Err(e) = self.create_backup(&backup_path) {
+            return Err(AppError::FileSystemError(
+                format!("Failed to create backup before switching: {}", e)
+            ));
+        }
+        
+        // Perform atomic switch operation with rollback on failure
+        match self.perform_switch_atomic(&new_content, companion_writes) {
+            Ok(()) => {
+                log::info!("Successfully switched to profile: {}", profile_name);
+                
+                // Clear caches since files have changed
+                self.clear_cache();
+                
+                // Update profile status after successful switch
+                if let Err(e) = self.refresh_profile_status() {
+                    log::warn!("Failed to refresh profile status after switch: {}", e);
+                    // Don't fail the operation since switch was successful
+                }
+                
+                // Remove backup file on success (keep only a few recent backups)
+                self.cleanup_old_backups();
+                
+                log::debug!(
+                    "switch_profile to '{}' took {:?} ({} profiles)",
+                    profile_name, started.elapsed(), self.profiles.len()
+                );
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("Failed to switch profile, attempting rollback: {}", e);
+                
+                // Attempt to restore from backup
+                if let Err(rollback_err) = self.restore_from_backup(&backup_path) {
+                    log::error!("CRITICAL: Failed to rollback after failed switch: {}", rollback_err);
+                    return Err(AppError::ConfigError(
+                        format!("Profile switch failed and rollback failed. Original error: {}. Rollback error: {}", e, rollback_err)
+                    ));
+                }
+                
+                log::info!("Successfully rolled back after failed switch");
+                Err(e)
+            }
+        }
+    }
+    
+    /// The file a path points to when it's a symlink, otherwise the path itself
+    fn resolve_symlink(path: &Path) -> PathBuf {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+            }
+            _ => path.to_path_buf(),
+        }
+    }
+    
+    /// The file switches write to: settings.json, or what it links to when following symlinks
+    ///
+    /// Writing over a symlink would replace the link itself, so a symlinked settings.json
+    /// is refused unless following symlinks is enabled.
+    fn settings_write_target(&self) -> AppResult<PathBuf> {
+        let target = Self::resolve_symlink(&self.default_settings_path);
+        if target == self.default_settings_path {
+            return Ok(target);
+        }
+        
+        if !self.follow_symlinks {
+            return Err(AppError::FileSystemError(format!(
+                "settings.json is a symlink to {:?}, enable following symlinks to switch profiles through it",
+                target
+            )));
+        }
+        log::debug!("Writing settings.json through its symlink to {:?}", target);
+        Ok(target)
+    }
+    
+    /// Perform the actual configuration switch with enhanced atomic operation
+    fn perform_switch_atomic(&self, new_content: &str, companion_writes: Vec<(PathBuf, String)>) -> AppResult<()> {
+        let normalized_content = Self::normalize_settings_json(new_content)?;
+        
+        let mut writes = vec![(self.settings_write_target()?, normalized_content)];
+        writes.extend(companion_writes);
+        self.write_files_transaction(&writes)?;
+        
+        log::debug!("Atomic switch operation completed successfully");
+        Ok(())
+    }
+    
+    /// Write several files atomically one by one, restoring all of them if any write fails
+    fn write_files_transaction(&self, writes: &[(PathBuf, String)]) -> AppResult<()> {
+        // None for files that don't exist yet, they're removed again on rollback. A file that
+        // exists but can't be read couldn't be restored, so nothing is written at all then
+        let previous = writes.iter()
+            .map(|(path, _)| match fs::read(path) {
+                Ok(content) => Ok(Some(content)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(AppError::FileSystemError(
+                    format!("Failed to read {:?} before replacing it: {}", path, e)
+                )),
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        
+        for (written, (path, content)) in writes.iter().enumerate() {
+            let Err(e) = self.write_file_atomic(path, content) else {
+                continue;
+            };
+            
+            log::error!("Failed to write {:?}, rolling back {} written files", path, written);
+            for index in (0..written).rev() {
+                let path = &writes[index].0;
+                let restored = match &previous[index] {
+                    Some(content) => self.write_file_atomic(path, content),
+                    None => fs::remove_file(path).map_err(AppError::from),
+                };
+                if let Err(rollback_err) = restored {
+                    log::error!("Failed to roll back {:?}: {}", path, rollback_err);
+                }
+            }
+            return Err(e);
+        }
+        
+        Ok(())
+    }
+    
+    /// Whether settings.json and the companion targets already hold what a switch would write
+    fn is_already_current(&self, new_content: &str, companion_writes: &[(PathBuf, String)]) -> AppResult<bool> {
+        let expected = Self::normalize_settings_json(new_content)?;
+        match fs::read_to_string(&self.default_settings_path) {
+            Ok(current) if current == expected => {}
+            _ => return Ok(false),
+        }
+        
+        Ok(companion_writes.iter().all(|(target, content)| {
+            fs::read_to_string(target).is_ok_and(|existing| existing == *content)
+        }))
+    }
+    
+    /// Validate settings content and format it the way a switch writes it to settings.json
+    fn normalize_settings_json(content: &str) -> AppResult<String> {
+        // Validate the new content is valid JSON with proper structure
+        let json_value = serde_json::from_str::<serde_json::Value>(content)
+            .map_err(|e| AppError::ConfigError(format!("Invalid JSON content: {}", e)))?;
+        
+        // Additional validation: ensure it's an object
+        if !json_value.is_object() {
+            return Err(AppError::ConfigError(
+                "Configuration must be a JSON object".to_string()
+            ));
+        }
+        
+        // Normalize JSON formatting for consistency
+        serde_json::to_string_pretty(&json_value)
+            .map_err(|e| AppError::ConfigError(format!("Failed to serialize JSON: {}", e)))
+    }
+    
+    /// Write a file via a verified temp file and rename, then verify the result
+    fn write_file_atomic(&self, target: &Path, content: impl AsRef<[u8]>) -> AppResult<()> {
+        let content = content.as_ref();
+        
+        // Write to temporary file first (in same directory for atomic rename)
+        let temp_path = target.with_extension("json.tmp");
+        
+        // Clean up any existing temp file
+        if temp_path.exists() {
+            fs::remove_file(&temp_path)
+                .map_err(|e| AppError::FileSystemError(format!("Failed to clean up temp file: {}", e)))?;
+        }
+        
+        fs::write(&temp_path, content)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to write temporary file: {}", e)))?;
+        
+        // Verify temp file was written correctly
+        let temp_verification = fs::read(&temp_path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to verify temp file: {}", e)))?;
+        
+        if temp_verification != content {
+            let _ = fs::remove_file(&temp_path);
+            return Err(AppError::FileSystemError(
+                "Temp file verification failed - data corruption detected".to_string()
+            ));
+        }
+        
+        // Atomic move (rename) operation, retried while the target is transiently locked
+        let mut attempt = 1;
+        loop {
+            match fs::rename(&temp_path, target) {
+                Ok(()) => break,
+                Err(e) if attempt < MAX_RENAME_ATTEMPTS && Self::is_transient_rename_error(&e) => {
+                    log::warn!("Failed to replace {:?} (attempt {}/{}): {}, retrying",
+                        target, attempt, MAX_RENAME_ATTEMPTS, e);
+                    std::thread::sleep(RENAME_RETRY_DELAY * attempt);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    // Clean up temp file only after the final failure
+                    let _ = fs::remove_file(&temp_path);
+                    return Err(AppError::FileSystemError(format!("Failed to replace {:?}: {}", target, e)));
+                }
+            }
+        }
+        
+        // Final verification
+        let final_verification = fs::read(target)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to verify final file: {}", e)))?;
+        
+        if final_verification != content {
+            return Err(AppError::FileSystemError(
+                format!("Final file verification failed - {:?} may have been corrupted", target)
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    /// Whether a rename failure is likely caused by another process briefly holding the file
+    fn is_transient_rename_error(error: &std::io::Error) -> bool {
+        matches!(
+            error.kind(),
+            std::io::ErrorKind::PermissionDenied
+                | std::io::ErrorKind::AlreadyExists
+                | std::io::ErrorKind::WouldBlock
+                | std::io::ErrorKind::Interrupted
+        )
+    }
+    
+    /// Clean up old backup files (keep only the most recent ones)
+    fn cleanup_old_backups(&self) {
+        // list_backups is already sorted newest first
+        let backups = match self.list_backups() {
+            Ok(backups) => backups,
+            Err(e) => {
+                log::warn!("Failed to list backups for cleanup: {}", e);
+                return;
+            }
+        };
+        
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let max_age_secs = self.backup_max_age_days.map(|days| days.saturating_mul(24 * 60 * 60));
+        let is_expired = |backup: &BackupEntry| {
+            max_age_secs.is_some_and(|max_age| now.saturating_sub(backup.timestamp) > max_age)
+        };
+        
+        // The newest backup survives the age limit, so there's always one to restore
+        let old_backups: Vec<_> = backups.iter()
+            .enumerate()
+            .filter(|(index, backup)| *index >= MAX_BACKUPS || (*index > 0 && is_expired(backup)))
+            .map(|(_, backup)| backup)
+            .collect();
+        
+        for old_backup in &old_backups {
+            if let Err(e) = fs::remove_file(&old_backup.path) {
+                log::warn!("Failed to remove old backup {:?}: {}", old_backup.path, e);
+            }
+        }
+        
+        if !old_backups.is_empty() {
+            log::debug!("Cleaned up {} old backup files", old_backups.len());
+        }
+    }
+    
+    /// Parse the timestamp out of a `settings.json.backup.<ts>` file name
+    fn backup_timestamp(file_name: &str) -> Option<u64> {
+        file_name.strip_prefix("settings.json.backup.")?.parse().ok()
+    }
+    
+    /// Format a byte count for display
+    fn format_size(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+        
+        if bytes < 1024 {
+            return format!("{} B", bytes);
+        }
+        
+        let mut size = bytes as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+    
+    /// List settings.json backups, newest first
+    pub fn list_backups(&self) -> AppResult<Vec<BackupEntry>> {
+        let backup_dir = self.get_backup_dir();
+        
+        let entries = match fs::read_dir(&backup_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(AppError::FileSystemError(
+                    format!("Failed to read backup directory {:?}: {}", backup_dir, e)
+                ));
+            }
+        };
+        
+        let mut backups: Vec<BackupEntry> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let timestamp = Self::backup_timestamp(&entry.file_name().to_string_lossy())?;
+                let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+                Some(BackupEntry {
+                    path: entry.path().to_string_lossy().to_string(),
+                    timestamp,
+                    size: Self::format_size(metadata.len()),
+                })
+            })
+            .collect();
+        
+        backups.sort_by_key(|b| std::cmp::Reverse(b.timestamp));
+        Ok(backups)
+    }
+    
+    /// Resolve a backup path from the frontend, refusing anything outside the backup directory
+    pub fn resolve_backup_path(&self, path: &Path) -> AppResult<PathBuf> {
+        let backup_dir = self.get_backup_dir().canonicalize()
+            .map_err(|e| AppError::FileSystemError(format!("Failed to resolve backup directory: {}", e)))?;
+        let resolved = path.canonicalize()
+            .map_err(|e| AppError::FileSystemError(format!("Failed to resolve backup {:?}: {}", path, e)))?;
+        
+        let is_backup = resolved.parent() == Some(backup_dir.as_path())
+            && resolved.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(Self::backup_timestamp)
+                .is_some();
+        if !is_backup || !resolved.is_file() {
+            return Err(AppError::PermissionError(
+                format!("{:?} is not a backup in {:?}", path, backup_dir)
+            ));
+        }
+        
+        Ok(resolved)
+    }
+    
+    /// Recover an unparseable settings.json from the newest valid backup, or with `{}` when `reset`
+    ///
+    /// Without `reset` it's an error when no backup is usable, so the user can decide on
+    /// resetting. The broken file is kept as `settings.json.corrupt.<timestamp>` in the backup directory.
+    pub fn repair_settings(&mut self, reset: bool) -> AppResult<SettingsRepair> {
+        // Repairing writes settings.json too, so it mustn't interleave with a switch
+        let _switch_guard = SWITCH_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        
+        let content = self.read_default_settings()?;
+        if Self::check_profile_json(&content).valid {
+            return Ok(SettingsRepair::NotNeeded);
+        }
+        
+        let backup = if reset {
+            None
+        } else {
+            let backups = self.list_backups()?;
+            let valid = backups.into_iter().find(|backup| {
+                fs::read_to_string(&backup.path).is_ok_and(|content| Self::check_profile_json(&content).valid)
+            });
+            Some(valid.ok_or_else(|| AppError::ConfigError(
+                "settings.json is corrupted and there is no valid backup to restore, reset it to {} instead".to_string()
+            ))?)
+        };
+        
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.create_backup(&self.get_backup_dir().join(format!("settings.json.corrupt.{}", timestamp)))?;
+        
+        let repair = match backup {
+            Some(backup) => {
+                self.restore_from_backup(Path::new(&backup.path))?;
+                log::warn!("Repaired corrupted settings.json from backup {}", backup.path);
+                SettingsRepair::RestoredBackup { backup: backup.path }
+            }
+            None => {
+                self.write_file_atomic(&self.settings_write_target()?, "{}")?;
+                log::warn!("Repaired corrupted settings.json by resetting it to {{}}");
+                SettingsRepair::ResetToEmpty
+            }
+        };
+        
+        self.clear_cache();
+        self.refresh_profile_status()?;
+        Ok(repair)
+    }
+    
+    /// Back up settings.json, write new content with verification, then roll back to the backup
+    ///
+    /// One full switch cycle without touching profiles, used to measure switch latency.
+    #[cfg(debug_assertions)]
+    pub(crate) fn switch_and_roll_back(&self, content: &str) -> AppResult<()> {
+        let backup_path = self.get_backup_dir().join("settings.json.benchmark");
+        self.create_backup(&backup_path)?;
+        self.perform_switch_atomic(content, Vec::new())?;
+        // Restoring also removes the backup
+        self.restore_from_backup(&backup_path)
+    }
+    
+    /// Create a backup of the current settings
+    fn create_backup(&self, backup_path: &Path) -> AppResult<()> {
+        if let Some(parent) = backup_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::FileSystemError(format!("Failed to create backup directory: {}", e)))?;
+        }
+        
+        fs::copy(&self.default_settings_path, backup_path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to create backup: {}", e)))?;
+        
+        log::debug!("Created backup at: {:?}", backup_path);
+        Ok(())
+    }
+    
+    /// Restore settings from backup with enhanced error handling
+    fn restore_from_backup(&self, backup_path: &Path) -> AppResult<()> {
+        if !backup_path.exists() {
+            return Err(AppError::FileSystemError(
+                format!("Backup file does not exist: {:?}", backup_path)
+            ));
+        }
+        
+        // Verify backup file integrity before restoring
+        let backup_content = fs::read_to_string(backup_path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to read backup file: {}", e)))?;
+        
+        // Validate backup content is valid JSON
+        serde_json::from_str::<serde_json::Value>(&backup_content)
+            .map_err(|e| AppError::ConfigError(format!("Backup file contains invalid JSON: {}", e)))?;
+        
+        // Use atomic operation for restore too
+        let settings_path = self.settings_write_target()?;
+        let temp_path = settings_path.with_extension("json.restore_tmp");
+        
+        fs::copy(backup_path, &temp_path)
+            .map_err(|e| AppError::FileSystemError(format!("Failed to copy backup to temp: {}", e)))?;
+        
+        fs::rename(&temp_path, &settings_path)
+            .map_err(|e| {
+                let _ = fs::remove_file(&temp_path);
+                AppError::FileSystemError(format!("Failed to restore from backup: {}", e))
+            })?;
+        
+        let _ = fs::remove_file(backup_path);
+        log::info!("Successfully restored settings from backup");
+        Ok(())
+    }
+    
+    /// Refresh the active status of all profiles (optimized)
+    ///
+    /// Only settings.json is re-read (through its cache), the directory isn't enumerated
+    /// and profile files aren't reloaded.
+    pub fn refresh_profile_status(&mut self) -> AppResult<()> {
+        // Create a temporary copy of profiles to avoid mutable borrow conflicts
+        let mut profiles_copy = self.profiles.clone();
+        self.update_profile_status_optimized(&mut profiles_copy)?;
+        self.profiles = profiles_copy;
+        Ok(())
+    }
+    
+    /// Bring profiles up to date after monitored files changed
+    ///
+    /// A change to settings.json alone can only move which profile is active, so that is all
+    /// that's recomputed. Changes to profile files need a full rescan to reload them.
+    pub fn refresh_after_changes(&mut self, changes: &[ConfigFileChange]) -> AppResult<()> {
+        if changes.iter().all(|change| change.file_path == self.default_settings_path) {
+            log::debug!("Only settings.json changed, refreshing status without a rescan");
+            return self.refresh_profile_status();
+        }
+        
+        self.scan_profiles().map(|_| ())
+    }
+    
+    /// Validate the integrity of a configuration switch
+    pub fn validate_switch(&self, profile_name: &str) -> AppResult<bool> {
+        let profile = self.lookup_profile(profile_name)
+            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))?;
+        
+        let current_content = self.read_default_settings()?;
+        self.compare_configurations(&profile.content, &current_content)
+    }
+    
+    /// Get Claude directory path
+    pub fn get_claude_dir(&self) -> &Path {
+        &self.claude_dir
+    }
+    
+    /// Point the service at a different Claude directory, dropping state from the old one
+    pub fn set_claude_dir(&mut self, claude_dir: PathBuf) {
+        log::info!("Changing Claude directory from {:?} to {:?}", self.claude_dir, claude_dir);
+        
+        self.default_settings_path = claude_dir.join("settings.json");
+        self.claude_dir = claude_dir;
+        self.profiles.clear();
+        self.clear_cache();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    fn create_test_config_service() -> (ConfigService, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let claude_dir = temp_dir.path().to_path_buf();
+        
+        // Create default settings.json
+        let settings_content = r#"{"theme": "dark", "language": "en"}"#;
+        fs::write(claude_dir.join("settings.json"), settings_content).unwrap();
+        
+        let service = ConfigService::new(claude_dir);
+        (service, temp_dir)
+    }
+    
+    #[test]
+    fn test_scan_profiles_empty_directory() {
+        let (mut service, _temp_dir) = create_test_config_service();
+        
+        // Only the live settings.json is listed
+        let profiles = service.scan_profiles().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].is_live);
+    }
+    
+    #[test]
+    fn test_scan_profiles_with_profiles() {
+        let (mut service, temp_dir) = create_test_config_service();
+        
+        // Create test profile files
+        let profile1_content = r#"{"theme": "light", "language": "en"}"#;
+        let profile2_content = r#"{"theme": "dark", "language": "en"}"#; // Same as default
+        
+        fs::write(temp_dir.path().join("work.settings.json"), profile1_content).unwrap();
+        fs::write(temp_dir.path().join("personal.settings.json"), profile2_content).unwrap();
+        
+        let profiles = service.scan_profiles().unwrap();
+        assert_eq!(profiles.len(), 3);
+        
+        // Check that personal profile is active (matches default)
+        let personal_profile = profiles.iter().find(|p| p.name == "personal").unwrap();
+        assert!(personal_profile.is_active);
+        
+        let work_profile = profiles.iter().find(|p| p.name == "work").unwrap();
+        assert!(!work_profile.is_active);
+    }
+    
+    #[test]
+    fn test_scan_profiles_with_progress() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("work.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "not a profile").unwrap();
+        
+        // Every entry counts, profile or not: settings.json, work and notes.txt
+        let mut reported = Vec::new();
+        let profiles = service.scan_profiles_with_progress(|progress| reported.push(progress)).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(reported.len(), 3);
+        assert!(reported.iter().all(|p| p.total == 3));
+        assert_eq!(reported.last().unwrap().current, 3);
+    }
+    
+    #[test]
+    fn test_scan_profiles_skips_backup_and_temp_files() {
+        let (mut service, temp_dir) = create_test_config_service();
+        
+        fs::write(temp_dir.path().join("work.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        for name in [
+            "foo.settings.json.backup.123",
+            "foo.backup.1.settings.json",
+            "foo.tmp.settings.json",
+            "foo.write_test.settings.json",
+            "foo.restore_tmp.settings.json",
+        ] {
+            fs::write(temp_dir.path().join(name), r#"{"theme": "dark"}"#).unwrap();
+        }
+        
+        let profiles = service.scan_profiles().unwrap();
+        let names: Vec<_> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec![LIVE_PROFILE_NAME, "work"]);
+        assert!(service.get_profile_load_errors().is_empty());
+    }
+    
+    #[test]
+    fn test_snapshot_current_settings() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let current = fs::read_to_string(temp_dir.path().join("settings.json")).unwrap();
+        
+        let first = service.snapshot_current_settings().unwrap();
+        let second = service.snapshot_current_settings().unwrap();
+        assert!(first.starts_with("snapshot."));
+        assert_ne!(first, second);
+        
+        assert_eq!(service.get_profile_content(&first).unwrap(), current);
+        let snapshot = service.get_profiles().iter().find(|p| p.name == first).unwrap();
+        assert!(snapshot.is_active);
+        
+        fs::write(temp_dir.path().join("settings.json"), "[1, 2]").unwrap();
+        assert!(service.snapshot_current_settings().is_err());
+        assert_eq!(service.get_profiles().len(), 3);
+    }
+    
+    #[test]
+    fn test_profile_extends_chain() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(
+            temp_dir.path().join("base.settings.json"),
+            r#"{"theme": "dark", "env": {"A": "1", "B": "1"}}"#,
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("team.settings.json"),
+            r#"{"extends": "base", "env": {"B": "2"}}"#,
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("me.settings.json"),
+            r#"{"extends": "team", "theme": "light"}"#,
+        ).unwrap();
+        service.scan_profiles().unwrap();
+        
+        let content = service.normalized_content("me").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(json, serde_json::json!({"theme": "light", "env": {"A": "1", "B": "2"}}));
+        
+        // The editor gets the file as written, so saving it back keeps the extends link
+        let raw = service.get_profile_content("me").unwrap();
+        assert_eq!(raw, r#"{"extends": "team", "theme": "light"}"#);
+        service.save_profile_content("me", &raw).unwrap();
+        assert_eq!(service.normalized_content("me").unwrap(), content);
+        
+        // The written settings.json never contains the extends key
+        service.switch_profile("me").unwrap();
+        let written: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.path().join("settings.json")).unwrap()
+        ).unwrap();
+        assert_eq!(written, json);
+        assert!(service.get_profiles().iter().find(|p| p.name == "me").unwrap().is_active);
+    }
+    
+    #[test]
+    fn test_profile_extends_cycle_and_missing_base() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("a.settings.json"), r#"{"extends": "b", "theme": "a"}"#).unwrap();
+        fs::write(temp_dir.path().join("b.settings.json"), r#"{"extends": "a", "theme": "b"}"#).unwrap();
+        fs::write(temp_dir.path().join("orphan.settings.json"), r#"{"extends": "missing"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        let errors = service.get_profile_load_errors();
+        assert!(errors["a"].contains("cycle: a -> b -> a"));
+        assert!(errors["b"].contains("cycle: b -> a -> b"));
+        assert!(errors["orphan"].contains("'missing'"));
+        assert!(service.switch_profile("a").is_err());
+        assert!(matches!(service.compare_profiles()[1], ProfileStatus::Error { .. }));
+    }
+    
+    #[test]
+    fn test_locked_profile_blocks_switch() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("prod.settings.json"), r#"{"theme": "dark", "language": "en"}"#).unwrap();
+        fs::write(temp_dir.path().join("dev.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        service.set_profile_locked("prod", true).unwrap();
+        assert!(service.set_profile_locked("missing", true).is_err());
+        
+        // The lock survives a rescan through the meta sidecar
+        service.scan_profiles().unwrap();
+        assert!(service.get_profile_meta("prod").locked);
+        assert!(service.get_profiles().iter().find(|p| p.name == "prod").unwrap().locked);
+        
+        let err = service.switch_profile("dev").unwrap_err();
+        assert!(matches!(err, AppError::ProfileLocked(_)));
+        assert!(err.to_string().contains("'prod' is locked"));
+        
+        service.switch_profile_with_force("dev", true).unwrap();
+        
+        // Switching back to a locked profile is fine, only switching away is blocked
+        service.switch_profile("prod").unwrap();
+        service.set_profile_locked("prod", false).unwrap();
+        service.switch_profile("dev").unwrap();
+    }
+    
+    #[test]
+    fn test_switch_profile_by_alias() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let settings_path = temp_dir.path().join("settings.json");
+        fs::write(temp_dir.path().join("anthropic-work-team.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        fs::write(temp_dir.path().join("home.settings.json"), r#"{"theme": "solarized"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        service.set_profile_alias("anthropic-work-team", Some(" work ")).unwrap();
+        assert!(service.set_profile_alias("home", Some("work")).is_err());
+        assert!(service.set_profile_alias("home", Some("anthropic-work-team")).is_err());
+        
+        // The alias survives a rescan through the meta sidecar
+        service.scan_profiles().unwrap();
+        let profile = service.find_profile("anthropic-work-team").unwrap();
+        assert_eq!(profile.alias.as_deref(), Some("work"));
+        assert_eq!(profile.label(), "work");
+        
+        service.switch_profile("work").unwrap();
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), "{\n  \"theme\": \"light\"\n}");
+        service.switch_profile("home").unwrap();
+        service.switch_profile("anthropic-work-team").unwrap();
+        assert!(service.find_profile("anthropic-work-team").unwrap().is_active);
+        
+        service.set_profile_alias("anthropic-work-team", None).unwrap();
+        assert!(service.switch_profile("work").is_err());
+        assert_eq!(service.get_profile_meta("anthropic-work-team"), ProfileMeta::default());
+    }
+    
+    #[test]
+    fn test_normalized_content_matches_switch() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let raw = "{\"theme\":\"light\",  \"env\": {\"B\": \"2\", \"A\": \"1\"}}";
+        fs::write(temp_dir.path().join("light.settings.json"), raw).unwrap();
+        fs::write(temp_dir.path().join("list.settings.json"), "[1, 2]").unwrap();
+        service.scan_profiles().unwrap();
+        
+        let preview = service.content_preview("light").unwrap();
+        assert_eq!(preview.raw, raw);
+        assert_eq!(preview.normalized, service.normalized_content("light").unwrap());
+        
+        service.switch_profile("light").unwrap();
+        let written = fs::read_to_string(temp_dir.path().join("settings.json")).unwrap();
+        assert_eq!(preview.normalized, written);
+        
+        // Content a switch would reject can't be previewed either
+        assert!(service.normalized_content("list").is_err());
+        assert!(service.normalized_content("missing").is_err());
+    }
+    
+    #[test]
+    fn test_switch_skips_when_already_current() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let settings = serde_json::to_string_pretty(&serde_json::json!({"a": 1, "b": 2})).unwrap();
+        fs::write(temp_dir.path().join("settings.json"), &settings).unwrap();
+        fs::write(temp_dir.path().join("work.settings.json"), r#"{"a": 1}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        // Merging yields exactly the current file, so nothing is backed up or rewritten
+        service.merge_switch("work").unwrap();
+        assert!(service.list_backups().unwrap().is_empty());
+        assert_eq!(fs::read_to_string(temp_dir.path().join("settings.json")).unwrap(), settings);
+    }
+    
+    #[test]
+    fn test_merge_switch_keeps_local_keys() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(
+            temp_dir.path().join("settings.json"),
+            r#"{"theme": "dark", "localPath": "/opt/tools", "env": {"A": "1", "LOCAL": "x"}}"#,
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("work.settings.json"),
+            r#"{"theme": "light", "env": {"A": "2"}}"#,
+        ).unwrap();
+        service.scan_profiles().unwrap();
+        
+        service.merge_switch("work").unwrap();
+        let written: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(temp_dir.path().join("settings.json")).unwrap()
+        ).unwrap();
+        assert_eq!(written, serde_json::json!({
+            "theme": "light", "localPath": "/opt/tools", "env": {"A": "2", "LOCAL": "x"}
+        }));
+        assert_eq!(service.list_backups().unwrap().len(), 1);
+        
+        // Only merge mode treats a profile contained in settings.json as active
+        assert!(matches!(service.compare_profiles()[1], ProfileStatus::NoMatch));
+        service.set_switch_mode(SwitchMode::Merge);
+        assert!(matches!(service.compare_profiles()[1], ProfileStatus::FullMatch));
+        assert!(service.get_profiles()[1].is_active);
+        
+        // In merge mode the regular switch merges too, and the preview shows the merged result
+        fs::write(temp_dir.path().join("home.settings.json"), r#"{"theme": "solarized"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        let preview = service.normalized_content("home").unwrap();
+        service.switch_profile("home").unwrap();
+        let written = fs::read_to_string(temp_dir.path().join("settings.json")).unwrap();
+        assert_eq!(written, preview);
+        assert!(written.contains("/opt/tools"));
+    }
+    
+    #[test]
+    fn test_check_profile_json() {
+        let result = ConfigService::check_profile_json(r#"{"theme": "dark"}"#);
+        assert_eq!(result, JsonValidation { valid: true, is_object: true, error: None });
+        
+        let result = ConfigService::check_profile_json("[1, 2]");
+        assert!(!result.valid && !result.is_object && result.error.is_none());
+        
+        let result = ConfigService::check_profile_json("{\n  \"theme\": \"dark\",\n}");
+        assert!(!result.valid);
+        let error = result.error.unwrap();
+        assert_eq!((error.line, error.column), (3, 1));
+        assert!(!error.message.contains("at line"));
+    }
+    
+    #[test]
+    fn test_refresh_after_changes() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let settings_path = temp_dir.path().join("settings.json");
+        let profile_path = temp_dir.path().join("light.settings.json");
+        fs::write(&profile_path, r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        assert!(!service.get_profiles()[1].is_active);
+        
+        // settings.json changing only updates the status of the loaded profiles
+        fs::write(&settings_path, r#"{"theme": "light"}"#).unwrap();
+        fs::write(temp_dir.path().join("new.settings.json"), "{}").unwrap();
+        let settings_changed = ConfigFileChange { file_path: settings_path.clone(), change_type: crate::ChangeType::Modified };
+        service.refresh_after_changes(&[settings_changed]).unwrap();
+        assert_eq!(service.get_profiles().len(), 2);
+        assert!(service.get_profiles()[1].is_active);
+        
+        // A profile changing reloads profiles from disk
+        fs::write(&profile_path, r#"{"theme": "light", "model": "opus"}"#).unwrap();
+        let profile_changed = ConfigFileChange { file_path: profile_path, change_type: crate::ChangeType::Modified };
+        service.refresh_after_changes(&[profile_changed]).unwrap();
+        assert_eq!(service.get_profiles().len(), 3);
+        let light = service.get_profiles().iter().find(|p| p.name == "light").unwrap();
+        assert!(light.content.contains("opus"));
+        assert!(!light.is_active);
+    }
+    
+    #[test]
+    fn test_env_merge_switch() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let settings_path = temp_dir.path().join("settings.json");
+        fs::write(&settings_path, r#"{"theme": "dark", "env": {"API_KEY": "a", "LOCAL": "x"}}"#).unwrap();
+        fs::write(temp_dir.path().join("keys.settings.json"), r#"{"env": {"API_KEY": "b"}}"#).unwrap();
+        fs::write(temp_dir.path().join("plain.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        service.set_env_merge(true);
+        
+        // An env-only profile only touches the variables it sets
+        service.switch_profile("keys").unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!({"theme": "dark", "env": {"API_KEY": "b", "LOCAL": "x"}}));
+        assert!(service.get_profiles().iter().find(|p| p.name == "keys").unwrap().is_active);
+        
+        // Profiles without an env block still replace the file
+        service.switch_profile("plain").unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!({"theme": "light"}));
+        
+        // With env merging off, an env-only profile replaces everything
+        service.set_env_merge(false);
+        service.switch_profile("keys").unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!({"env": {"API_KEY": "b"}}));
+    }
+    
+    #[test]
+    fn test_matches_profile() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("same.settings.json"), r#"{"theme": "dark", "language": "en"}"#).unwrap();
+        fs::write(temp_dir.path().join("model.settings.json"), r#"{"theme": "dark", "language": "en", "model": "opus"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        assert!(service.matches_profile("same").unwrap());
+        // A partial match is not a match
+        assert!(!service.matches_profile("model").unwrap());
+        assert!(service.matches_profile("missing").is_err());
+    }
+    
+    #[test]
+    fn test_switch_profile_bundle() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("work.settings.json"),
+            r#"{"theme": "light", "companions": [{"target": "settings.local.json", "source": "work.local.json"}]}"#,
+        ).unwrap();
+        fs::write(dir.join("work.local.json"), r#"{"permissions": {"allow": ["Bash"]}}"#).unwrap();
+        fs::write(
+            dir.join("escape.settings.json"),
+            r#"{"companions": [{"target": "../settings.json", "source": "work.local.json"}]}"#,
+        ).unwrap();
+        service.scan_profiles().unwrap();
+        
+        // The manifest is kept out of the content that gets compared and written
+        let work = service.get_profiles().iter().find(|p| p.name == "work").unwrap();
+        assert_eq!(work.companions.len(), 1);
+        assert!(!work.content.contains("companions"));
+        assert!(service.get_profile_load_errors()["escape"].contains("plain file names"));
+        
+        // The editor gets the manifest back, so saving the profile keeps its companions
+        let raw = service.get_profile_content("work").unwrap();
+        assert!(raw.contains("companions"));
+        service.save_profile_content("work", &raw).unwrap();
+        assert_eq!(service.get_profiles().iter().find(|p| p.name == "work").unwrap().companions.len(), 1);
+        
+        service.switch_profile("work").unwrap();
+        assert!(!fs::read_to_string(dir.join("settings.json")).unwrap().contains("companions"));
+        assert_eq!(
+            fs::read_to_string(dir.join("settings.local.json")).unwrap(),
+            r#"{"permissions": {"allow": ["Bash"]}}"#
+        );
+    }
+    
+    #[test]
+    fn test_switch_profile_bundle_rolls_back_all_files() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("work.settings.json"),
+            r#"{"theme": "light", "companions": [
+                {"target": "settings.local.json", "source": "work.local.json"},
+                {"target": "blocked.json", "source": "work.local.json"}
+            ]}"#,
+        ).unwrap();
+        fs::write(dir.join("work.local.json"), r#"{"new": true}"#).unwrap();
+        fs::write(dir.join("settings.local.json"), r#"{"old": true}"#).unwrap();
+        // A directory where the second companion's temp file goes makes its write fail
+        fs::create_dir(dir.join("blocked.json.tmp")).unwrap();
+        service.scan_profiles().unwrap();
+        
+        assert!(service.switch_profile("work").is_err());
+        
+        // settings.json and the first companion are back to what they were
+        let settings: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("settings.json")).unwrap()).unwrap();
+        assert_eq!(settings, serde_json::json!({"theme": "dark", "language": "en"}));
+        assert_eq!(fs::read_to_string(dir.join("settings.local.json")).unwrap(), r#"{"old": true}"#);
+        assert!(!dir.join("blocked.json").exists());
+    }
+    
+    #[test]
+    fn test_switch_profile_bundle_keeps_existing_targets() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("work.settings.json"),
+            r#"{"theme": "light", "companions": [
+                {"target": "notes.json", "source": "work.local.json"},
+                {"target": "blocked.json", "source": "work.local.json"}
+            ]}"#,
+        ).unwrap();
+        fs::write(dir.join("work.local.json"), r#"{"new": true}"#).unwrap();
+        // Not valid UTF-8, but still a file the user had before the switch
+        fs::write(dir.join("notes.json"), [0xff, 0xfe, 0x00]).unwrap();
+        fs::create_dir(dir.join("blocked.json.tmp")).unwrap();
+        service.scan_profiles().unwrap();
+        
+        // Rolling back restores it byte for byte instead of removing it
+        assert!(service.switch_profile("work").is_err());
+        assert_eq!(fs::read(dir.join("notes.json")).unwrap(), vec![0xff, 0xfe, 0x00]);
+        
+        // A target that exists but can't be read stops the switch before anything is written
+        fs::remove_dir(dir.join("blocked.json.tmp")).unwrap();
+        fs::create_dir(dir.join("blocked.json")).unwrap();
+        let error = service.switch_profile("work").unwrap_err().to_string();
+        assert!(error.contains("before replacing it"), "{}", error);
+        let settings: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("settings.json")).unwrap()).unwrap();
+        assert_eq!(settings, serde_json::json!({"theme": "dark", "language": "en"}));
+        assert_eq!(fs::read(dir.join("notes.json")).unwrap(), vec![0xff, 0xfe, 0x00]);
+    }
+    
+    #[test]
+    fn test_switch_to_empty_profile() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let settings_path = temp_dir.path().join("settings.json");
+        fs::write(temp_dir.path().join("blank.settings.json"), "{}").unwrap();
+        service.scan_profiles().unwrap();
+        
+        let result = service.switch_profile("blank");
+        match result {
+            Err(AppError::ConfigError(msg)) => assert!(msg.contains("is empty")),
+            other => panic!("Expected ConfigError, got {:?}", other),
+        }
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), r#"{"theme": "dark", "language": "en"}"#);
+        
+        service.set_allow_empty_profiles(true);
+        service.switch_profile("blank").unwrap();
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), "{}");
+    }
+    
+    #[test]
+    fn test_switch_preserves_partial_match_fields() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let settings_path = temp_dir.path().join("settings.json");
+        fs::write(&settings_path, r#"{"theme": "dark", "model": "opus"}"#).unwrap();
+        fs::write(temp_dir.path().join("work.settings.json"), r#"{"theme": "light", "model": "sonnet"}"#).unwrap();
+        fs::write(temp_dir.path().join("home.settings.json"), r#"{"theme": "solarized"}"#).unwrap();
+        service.set_preserve_partial_match_fields(true);
+        service.scan_profiles().unwrap();
+        
+        service.switch_profile("work").unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!({"theme": "light", "model": "opus"}));
+        
+        // A profile without a model keeps the current one too
+        service.switch_profile("home").unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::json!({"theme": "solarized", "model": "opus"}));
+    }
+    
+    #[test]
+    fn test_switch_profile_by_index() {
+        let (mut service, temp_dir) = create_test_config_service();
+        for name in ["work", "home", "beta"] {
+            fs::write(temp_dir.path().join(format!("{}.settings.json", name)), format!(r#"{{"profile": "{}"}}"#, name)).unwrap();
+        }
+        service.scan_profiles().unwrap();
+        
+        // The live profile stays first, the rest are sorted by name
+        let names: Vec<&str> = service.get_profiles().iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec![LIVE_PROFILE_NAME, "beta", "home", "work"]);
+        
+        assert_eq!(service.profile_name_at(0).unwrap(), "beta");
+        assert_eq!(service.profile_name_at(2).unwrap(), "work");
+        match service.profile_name_at(3) {
+            Err(AppError::ConfigError(msg)) => assert!(msg.contains("out of range")),
+            other => panic!("Expected ConfigError, got {:?}", other),
+        }
+        
+        let name = service.profile_name_at(1).unwrap();
+        service.switch_profile(&name).unwrap();
+        let settings = fs::read_to_string(temp_dir.path().join("settings.json")).unwrap();
+        assert!(settings.contains("home"));
+    }
+    
+    #[test]
+    fn test_switch_profile_with_loose_name() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("Work.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        // Trailing whitespace and a different case still find the profile
+        assert!(matches!(service.get_profile_status("Work "), ProfileStatus::NoMatch));
+        assert!(!service.validate_switch(" work").unwrap());
+        service.switch_profile("WORK\n").unwrap();
+        assert!(matches!(service.get_profile_status("Work"), ProfileStatus::FullMatch));
+        
+        // An exact match is preferred over a case-insensitive one, added in memory since
+        // "work" and "Work" may be the same file on case-insensitive file systems
+        let mut lowercase = service.get_profiles()[1].clone();
+        lowercase.name = "work".to_string();
+        lowercase.content = r#"{"theme": "dark"}"#.to_string();
+        service.profiles.push(lowercase);
+        assert!(matches!(service.get_profile_status("work"), ProfileStatus::NoMatch));
+        assert!(matches!(service.get_profile_status("Work"), ProfileStatus::FullMatch));
+        
+        // Ambiguous without an exact match
+        assert!(service.validate_switch("WORK").is_err());
+    }
+    
+    #[test]
+    fn test_live_profile() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("work.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        // Listed first, always matching, and not counted or monitored as a profile file
+        let live = &service.get_profiles()[0];
+        assert!(live.is_live && live.is_active);
+        assert_eq!(live.name, LIVE_PROFILE_NAME);
+        assert!(matches!(service.get_profile_status(LIVE_PROFILE_NAME), ProfileStatus::FullMatch));
+        assert_eq!(service.status_summary().no_match, 1);
+        assert_eq!(service.status_summary().full_match, 0);
+        assert_eq!(service.get_monitored_files().len(), 2);
+        
+        // Read-only, and switching to it changes nothing
+        assert!(service.save_profile_content(LIVE_PROFILE_NAME, "{}").is_err());
+        assert!(service.set_profile_locked(LIVE_PROFILE_NAME, true).is_err());
+        assert!(service.create_from_template("blank", LIVE_PROFILE_NAME).is_err());
+        service.switch_profile(LIVE_PROFILE_NAME).unwrap();
+        
+        // Follows settings.json after a switch
+        service.switch_profile("work").unwrap();
+        let live: serde_json::Value = serde_json::from_str(&service.get_profiles()[0].content).unwrap();
+        assert_eq!(live, serde_json::json!({"theme": "light"}));
+        assert!(matches!(service.get_profile_status(LIVE_PROFILE_NAME), ProfileStatus::FullMatch));
+    }
+    
+    #[test]
+    fn test_format_profile() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let work_path = temp_dir.path().join("work.settings.json");
+        fs::write(&work_path, r#"{"theme":"dark",   "language":"en"}"#).unwrap();
+        fs::write(temp_dir.path().join("broken.settings.json"), "{\n  \"theme\": }").unwrap();
+        service.scan_profiles().unwrap();
+        assert!(matches!(service.get_profile_status("work"), ProfileStatus::FullMatch));
+        
+        assert!(service.format_profile("work").unwrap());
+        assert_eq!(fs::read_to_string(&work_path).unwrap(), "{\n  \"language\": \"en\",\n  \"theme\": \"dark\"\n}");
+        assert!(matches!(service.get_profile_status("work"), ProfileStatus::FullMatch));
+        assert!(!service.format_profile("work").unwrap());
+        
+        let err = service.format_profile("broken").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+        assert!(service.format_profile(LIVE_PROFILE_NAME).is_err());
+    }
+    
+    #[test]
+    fn test_format_all_profiles() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("a.settings.json"), r#"{"theme":"light"}"#).unwrap();
+        fs::write(temp_dir.path().join("b.settings.json"), "{\n  \"theme\": \"dark\"\n}").unwrap();
+        fs::write(temp_dir.path().join("c.settings.json"), "[1]").unwrap();
+        service.scan_profiles().unwrap();
+        
+        let report = service.format_all_profiles().unwrap();
+        assert_eq!(report.formatted, vec!["a".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("c: "));
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_profile_and_settings() {
+        use std::os::unix::fs::symlink;
+        
+        let (mut service, temp_dir) = create_test_config_service();
+        let dir = temp_dir.path();
+        let shared = TempDir::new().unwrap();
+        
+        // A linked profile is monitored through the file it points to
+        let real_profile = shared.path().join("work.json");
+        fs::write(&real_profile, r#"{"theme": "light"}"#).unwrap();
+        symlink(&real_profile, dir.join("work.settings.json")).unwrap();
+        service.scan_profiles().unwrap();
+        let work = service.get_profiles().iter().find(|p| p.name == "work").unwrap();
+        assert_eq!(work.path, real_profile.canonicalize().unwrap());
+        assert!(service.get_monitored_files().contains(&work.path));
+        
+        // A linked settings.json is left alone unless following symlinks
+        let real_settings = shared.path().join("settings.json");
+        fs::rename(dir.join("settings.json"), &real_settings).unwrap();
+        symlink(&real_settings, dir.join("settings.json")).unwrap();
+        service.scan_profiles().unwrap();
+        let err = service.switch_profile("work").unwrap_err();
+        assert!(err.to_string().contains("symlink"));
+        assert_eq!(fs::read_to_string(&real_settings).unwrap(), r#"{"theme": "dark", "language": "en"}"#);
+        
+        service.set_follow_symlinks(true);
+        service.switch_profile("work").unwrap();
+        assert!(fs::symlink_metadata(dir.join("settings.json")).unwrap().file_type().is_symlink());
+        let settings: serde_json::Value = serde_json::from_str(&fs::read_to_string(&real_settings).unwrap()).unwrap();
+        assert_eq!(settings, serde_json::json!({"theme": "light"}));
+    }
+    
+    #[test]
+    fn test_compare_with_file() {
+        let (service, _temp_dir) = create_test_config_service();
+        let outside = TempDir::new().unwrap();
+        
+        let same = outside.path().join("teammate.json");
+        fs::write(&same, r#"{"language": "en", "theme": "dark"}"#).unwrap();
+        let comparison = service.compare_with_file(&same).unwrap();
+        assert_eq!(comparison.status, ProfileStatus::FullMatch);
+        assert!(comparison.diffs.is_empty());
+        
+        let different = outside.path().join("teammate.toml");
+        fs::write(&different, "theme = \"light\"\nlanguage = \"en\"\n").unwrap();
+        let comparison = service.compare_with_file(&different).unwrap();
+        assert_eq!(comparison.status, ProfileStatus::NoMatch);
+        assert_eq!(comparison.diffs, vec![FieldDiff {
+            path: "theme".to_string(),
+            left: Some(serde_json::json!("light")),
+            right: Some(serde_json::json!("dark")),
+        }]);
+        
+        fs::write(&same, "{").unwrap();
+        assert!(service.compare_with_file(&same).unwrap_err().to_string().contains("line 1"));
+        assert!(service.compare_with_file(&outside.path().join("missing.json")).is_err());
+    }
+    
+    #[test]
+    fn test_concurrent_switches() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("a.settings.json"), r#"{"theme": "a"}"#).unwrap();
+        fs::write(temp_dir.path().join("b.settings.json"), r#"{"theme": "b"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        let settings_path = temp_dir.path().join("settings.json");
+        let switch_on_own_service = |name: &'static str| {
+            let claude_dir = temp_dir.path().to_path_buf();
+            std::thread::spawn(move || {
+                let mut service = ConfigService::new(claude_dir);
+                service.scan_profiles().unwrap();
+                service.switch_profile(name)
+            })
+        };
+        
+        // Another service's switch is in flight, so one on a separate service has to wait for it
+        let in_flight = SWITCH_LOCK.lock().unwrap();
+        let waiting = switch_on_own_service("a");
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!waiting.is_finished());
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), r#"{"theme": "dark", "language": "en"}"#);
+        drop(in_flight);
+        waiting.join().unwrap().unwrap();
+        
+        // Two services on the same directory, like a scheduled and a manual switch
+        let handles: Vec<_> = ["a", "b"].into_iter()
+            .flat_map(|name| std::iter::repeat_n(name, 20))
+            .map(switch_on_own_service)
+            .collect();
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+        
+        // settings.json is exactly one of the profiles and nothing was left behind
+        let settings: serde_json::Value = serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert!(settings == serde_json::json!({"theme": "a"}) || settings == serde_json::json!({"theme": "b"}));
+        assert!(!temp_dir.path().join("settings.json.tmp").exists());
+        service.scan_profiles().unwrap();
+        assert_eq!(service.get_profiles().iter().filter(|p| !p.is_live && p.is_active).count(), 1);
+    }
+    
+    #[test]
+    fn test_status_summary() {
+        let (mut service, temp_dir) = create_test_config_service();
+        assert_eq!(service.status_summary(), StatusSummary::default());
+        
+        let current = fs::read_to_string(temp_dir.path().join("settings.json")).unwrap();
+        fs::write(temp_dir.path().join("same.settings.json"), &current).unwrap();
+        fs::write(temp_dir.path().join("other.settings.json"), r#"{"theme": "solarized"}"#).unwrap();
+        fs::write(temp_dir.path().join("broken.settings.json"), "{").unwrap();
+        service.scan_profiles().unwrap();
+        
+        let summary = service.status_summary();
+        assert_eq!(summary, StatusSummary { full_match: 1, partial_match: 0, no_match: 1, error: 1 });
+    }
+    
+    #[test]
+    fn test_get_profile_content() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let content = r#"{"theme": "light"}"#;
+        fs::write(temp_dir.path().join("work.settings.json"), content).unwrap();
+        fs::write(temp_dir.path().join("broken.settings.json"), "{").unwrap();
+        service.scan_profiles().unwrap();
+        
+        assert_eq!(service.get_profile_content("work").unwrap(), content);
+        assert!(service.get_profile_content("broken").unwrap_err().to_string().contains("failed to load"));
+        assert!(service.get_profile_content("missing").unwrap_err().to_string().contains("'missing' not found"));
+    }
+    
+    #[test]
+    fn test_get_profile_checksum() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let content = r#"{"theme": "light"}"#;
+        fs::write(temp_dir.path().join("work.settings.json"), content).unwrap();
+        service.scan_profiles().unwrap();
+        
+        let checksum = service.get_profile_checksum("work").unwrap();
+        assert_eq!(checksum.name, "work");
+        assert_eq!(checksum.checksum, ConfigService::calculate_checksum(content));
+        assert_eq!(checksum.size, content.len() as u64);
+        
+        let err = service.get_profile_checksum("missing").unwrap_err();
+        assert!(err.to_string().contains("'missing' not found"));
+    }
+    
+    #[test]
+    fn test_compare_configurations() {
+        let (service, _temp_dir) = create_test_config_service();
+        
+        let config1 = r#"{"theme": "dark", "language": "en"}"#;
+        let config2 = r#"{"language": "en", "theme": "dark"}"#; // Same content, different order
+        let config3 = r#"{"theme": "light", "language": "en"}"#;
+        
+        assert!(service.compare_configurations(config1, config2).unwrap());
+        assert!(!service.compare_configurations(config1, config3).unwrap());
+        
+        // Unparseable content is an error, not a mismatch
+        assert!(service.compare_configurations("{", config1).is_err());
+        assert!(service.compare_configurations(config1, "").is_err());
+    }
+    
+    #[test]
+    fn test_validate_switch_ignores_whitespace() {
+        let (mut service, temp_dir) = create_test_config_service();
+        
+        fs::write(
+            temp_dir.path().join("settings.json"),
+            "{\n    \"theme\": \"light\",\n    \"env\": {\"A\": \"1\"}\n}\n\n",
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("work.settings.json"),
+            "{\"env\":{\n\t\"A\":\"1\"},\"theme\":\"light\"}",
+        ).unwrap();
+        service.scan_profiles().unwrap();
+        assert!(service.validate_switch("work").unwrap());
+        
+        fs::write(temp_dir.path().join("settings.json"), "{\"theme\": ").unwrap();
+        let err = service.validate_switch("work").unwrap_err();
+        assert!(err.to_string().contains("Invalid default settings JSON"));
+    }
+    
+    #[test]
+    fn test_scan_profiles_rejects_non_object_json() {
+        let (mut service, temp_dir) = create_test_config_service();
         
-        // Additional validation: ensure it's an object
-        if !json_value.is_object() {
-            return Err(AppError::ConfigError(
-                "Configuration must be a JSON object".to_string()
-            ));
-        }
+        fs::write(temp_dir.path().join("array.settings.json"), r#"["theme", "dark"]"#).unwrap();
+        fs::write(temp_dir.path().join("valid.settings.json"), r#"{"theme": "light"}"#).unwrap();
         
-        // Normalize JSON formatting for consistency
-        let normalized_content = serde_json::to_string_pretty(&json_value)
-            .map_err(|e| AppError::ConfigError(format!("Failed to serialize JSON: {}", e)))?;
+        let profiles = service.scan_profiles().unwrap();
+        assert_eq!(profiles.len(), 3);
         
-        // Write to temporary file first (in same directory for atomic rename)
-        let temp_path = self.default_settings_path.with_extension("json.tmp");
+        let array_profile = profiles.iter().find(|p| p.name == "array").unwrap();
+        assert!(!array_profile.is_active);
         
-        // Clean up any existing temp file
-        if temp_path.exists() {
-            fs::remove_file(&temp_path)
-                .map_err(|e| AppError::FileSystemError(format!("Failed to clean up temp file: {}", e)))?;
+        match service.get_profile_status("array") {
+            ProfileStatus::Error { message: msg } => assert!(msg.contains("JSON object")),
+            other => panic!("Expected error status, got {:?}", other),
         }
+        assert_eq!(service.get_profile_status("valid"), ProfileStatus::NoMatch);
         
-        fs::write(&temp_path, &normalized_content)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to write temporary file: {}", e)))?;
+        let result = service.switch_profile("array");
+        assert!(matches!(result, Err(AppError::ConfigError(msg)) if msg.contains("failed to load")));
+    }
+    
+    #[test]
+    fn test_scan_profiles_skips_oversized_profile() {
+        let (mut service, temp_dir) = create_test_config_service();
+        service.set_max_profile_size(64);
         
-        // Verify temp file was written correctly
-        let temp_verification = fs::read_to_string(&temp_path)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to verify temp file: {}", e)))?;
+        let large_value = "x".repeat(128);
+        fs::write(
+            temp_dir.path().join("large.settings.json"),
+            format!(r#"{{"padding": "{}"}}"#, large_value),
+        ).unwrap();
+        fs::write(temp_dir.path().join("small.settings.json"), r#"{"theme": "light"}"#).unwrap();
         
-        if temp_verification != normalized_content {
-            let _ = fs::remove_file(&temp_path);
-            return Err(AppError::FileSystemError(
-                "Temp file verification failed - data corruption detected".to_string()
-            ));
+        let profiles = service.scan_profiles().unwrap();
+        let large = profiles.iter().find(|p| p.name == "large").unwrap();
+        assert!(large.content.is_empty());
+        
+        match service.get_profile_status("large") {
+            ProfileStatus::Error { message: msg } => assert!(msg.contains("too large")),
+            other => panic!("Expected error status, got {:?}", other),
         }
+        assert_eq!(service.get_profile_status("small"), ProfileStatus::NoMatch);
+    }
+    
+    #[test]
+    fn test_diff_profiles() {
+        let (mut service, temp_dir) = create_test_config_service();
         
-        // Atomic move (rename) operation
-        fs::rename(&temp_path, &self.default_settings_path)
-            .map_err(|e| {
-                // Clean up temp file on failure
-                let _ = fs::remove_file(&temp_path);
-                AppError::FileSystemError(format!("Failed to replace settings file: {}", e))
-            })?;
+        fs::write(
+            temp_dir.path().join("work.settings.json"),
+            r#"{"theme": "dark", "env": {"API_BASE": "work", "TIMEOUT": 30}, "model": "a"}"#,
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("personal.settings.json"),
+            r#"{"theme": "dark", "env": {"API_BASE": "home", "TIMEOUT": 30}, "language": "en"}"#,
+        ).unwrap();
+        service.scan_profiles().unwrap();
         
-        // Final verification
-        let final_verification = fs::read_to_string(&self.default_settings_path)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to verify final file: {}", e)))?;
+        let diffs = service.diff_profiles("work", "personal").unwrap();
+        let paths: Vec<&str> = diffs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["env.API_BASE", "language", "model"]);
         
-        if final_verification != normalized_content {
-            return Err(AppError::FileSystemError(
-                "Final file verification failed - switch may have been corrupted".to_string()
-            ));
-        }
+        assert_eq!(diffs[0].left, Some(serde_json::json!("work")));
+        assert_eq!(diffs[0].right, Some(serde_json::json!("home")));
+        assert_eq!(diffs[1].left, None);
+        assert_eq!(diffs[2].right, None);
         
-        log::debug!("Atomic switch operation completed successfully");
-        Ok(())
+        assert!(service.diff_profiles("work", "work").unwrap().is_empty());
+        
+        let err = service.diff_profiles("work", "missing").unwrap_err();
+        assert!(err.to_string().contains("'missing' not found"));
     }
     
-    /// Clean up old backup files (keep only the 5 most recent)
-    fn cleanup_old_backups(&self) {
-        let backup_pattern = format!("{}.backup.", self.default_settings_path.display());
-        
-        if let Ok(entries) = fs::read_dir(self.default_settings_path.parent().unwrap_or(&self.claude_dir)) {
-            let mut backup_files: Vec<_> = entries
-                .filter_map(|entry| entry.ok())
-                .filter(|entry| {
-                    entry.file_name()
-                        .to_string_lossy()
-                        .starts_with(&backup_pattern)
-                })
-                .collect();
-            
-            // Sort by modification time (newest first)
-            backup_files.sort_by(|a, b| {
-                let time_a = a.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
-                let time_b = b.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
-                time_b.cmp(&time_a)
-            });
-            
-            // Remove old backups (keep only 5 most recent)
-            for old_backup in backup_files.iter().skip(5) {
-                if let Err(e) = fs::remove_file(old_backup.path()) {
-                    log::warn!("Failed to remove old backup {:?}: {}", old_backup.path(), e);
-                }
-            }
-            
-            if backup_files.len() > 5 {
-                log::debug!("Cleaned up {} old backup files", backup_files.len() - 5);
-            }
-        }
+    #[test]
+    fn test_create_from_template() {
+        let (mut service, temp_dir) = create_test_config_service();
+        service.scan_profiles().unwrap();
+        
+        service.create_from_template("minimal", "Work").unwrap();
+        
+        let profile = service.find_profile("Work").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&profile.content).unwrap();
+        assert_eq!(json["env"]["CCCS_PROFILE"], "Work");
+        assert!(temp_dir.path().join("Work.settings.json").exists());
+        
+        service.create_from_template("full", "工作").unwrap();
+        let profile = service.find_profile("工作").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&profile.content).unwrap();
+        assert_eq!(json["env"]["CCCS_PROFILE"], "工作");
+        
+        let err = service.create_from_template("unknown", "Other").unwrap_err();
+        assert!(err.to_string().contains("Unknown profile template"));
+        
+        assert!(service.create_from_template("minimal", "Work").is_err());
+        assert!(service.create_from_template("minimal", "../escape").is_err());
+        assert!(service.create_from_template("minimal", "  ").is_err());
+        assert!(service.create_from_template("minimal", "Say \"hi\"").is_err());
+        
+        assert_eq!(service.unique_profile_name("Work"), "Work 2");
+        assert_eq!(service.unique_profile_name("minimal"), "minimal");
     }
     
-    /// Create a backup of the current settings
-    fn create_backup(&self, backup_path: &Path) -> AppResult<()> {
-        fs::copy(&self.default_settings_path, backup_path)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to create backup: {}", e)))?;
+    #[test]
+    fn test_import_profile() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let source_dir = TempDir::new().unwrap();
+        let source = source_dir.path().join("exported.json");
+        fs::write(&source, r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
         
-        log::debug!("Created backup at: {:?}", backup_path);
-        Ok(())
+        service.import_profile(&source, "imported", false).unwrap();
+        assert_eq!(service.find_profile("imported").unwrap().content, r#"{"theme": "light"}"#);
+        assert!(temp_dir.path().join("imported.settings.json").exists());
+        
+        // Existing profiles are only replaced when asked to
+        fs::write(&source, r#"{"theme": "dark", "language": "en"}"#).unwrap();
+        let err = service.import_profile(&source, "imported", false).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        service.import_profile(&source, "imported", true).unwrap();
+        assert!(service.find_profile("imported").unwrap().is_active);
+        
+        fs::write(&source, "{\n  \"theme\": ,\n}").unwrap();
+        let err = service.import_profile(&source, "broken", false).unwrap_err();
+        assert!(err.to_string().contains("line 2, column"));
+        
+        fs::write(&source, "[]").unwrap();
+        assert!(service.import_profile(&source, "array", false).is_err());
+        assert!(service.import_profile(&source, "a/b", false).is_err());
+        assert!(service.import_profile(&source_dir.path().join("missing.json"), "missing", false).is_err());
+        assert!(!temp_dir.path().join("broken.settings.json").exists());
     }
     
-    /// Restore settings from backup with enhanced error handling
-    fn restore_from_backup(&self, backup_path: &Path) -> AppResult<()> {
-        if !backup_path.exists() {
-            return Err(AppError::FileSystemError(
-                format!("Backup file does not exist: {:?}", backup_path)
-            ));
+    #[test]
+    fn test_templates_are_valid_json_objects() {
+        for name in ConfigService::template_names() {
+            let (_, body) = PROFILE_TEMPLATES.iter().find(|(n, _)| *n == name).unwrap();
+            let json: serde_json::Value = serde_json::from_str(body).unwrap();
+            assert!(json.is_object(), "template {} is not an object", name);
         }
+    }
+    
+    #[test]
+    fn test_save_profile_content() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let profile_path = temp_dir.path().join("work.settings.json");
+        fs::write(&profile_path, r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        assert!(!service.find_profile("work").unwrap().is_active);
         
-        // Verify backup file integrity before restoring
-        let backup_content = fs::read_to_string(backup_path)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to read backup file: {}", e)))?;
+        let new_content = "{\n  \"theme\": \"dark\",\n  \"language\": \"en\"\n}";
+        service.save_profile_content("work", new_content).unwrap();
         
-        // Validate backup content is valid JSON
-        serde_json::from_str::<serde_json::Value>(&backup_content)
-            .map_err(|e| AppError::ConfigError(format!("Backup file contains invalid JSON: {}", e)))?;
+        assert_eq!(fs::read_to_string(&profile_path).unwrap(), new_content);
+        assert!(!temp_dir.path().join("work.settings.json.tmp").exists());
+        assert!(service.find_profile("work").unwrap().is_active);
+        assert_eq!(service.get_profile_status("work"), ProfileStatus::FullMatch);
         
-        // Use atomic operation for restore too
-        let temp_path = self.default_settings_path.with_extension("json.restore_tmp");
+        let err = service.save_profile_content("work", "{\n  \"theme\": }").unwrap_err();
+        assert!(err.to_string().contains("line 2, column"));
+        assert!(service.save_profile_content("work", "[1, 2]").is_err());
+        assert!(service.save_profile_content("missing", "{}").is_err());
+        assert_eq!(fs::read_to_string(&profile_path).unwrap(), new_content);
+    }
+    
+    #[test]
+    fn test_compare_keys_whitelist() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(
+            temp_dir.path().join("settings.json"),
+            r#"{"model": "opus", "env": {"API_BASE": "work"}, "theme": "dark"}"#,
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("work.settings.json"),
+            r#"{"model": "opus", "env": {"API_BASE": "work", "TIMEOUT": 30}, "theme": "light"}"#,
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("sonnet.settings.json"),
+            r#"{"model": "sonnet", "env": {"API_BASE": "work"}, "theme": "dark"}"#,
+        ).unwrap();
+        service.scan_profiles().unwrap();
         
-        fs::copy(backup_path, &temp_path)
-            .map_err(|e| AppError::FileSystemError(format!("Failed to copy backup to temp: {}", e)))?;
+        assert_eq!(service.get_profile_status("work"), ProfileStatus::NoMatch);
+        assert_eq!(service.get_profile_status("sonnet"), ProfileStatus::PartialMatch { fields: vec!["model".to_string()] });
         
-        fs::rename(&temp_path, &self.default_settings_path)
-            .map_err(|e| {
-                let _ = fs::remove_file(&temp_path);
-                AppError::FileSystemError(format!("Failed to restore from backup: {}", e))
-            })?;
+        service.set_compare_keys(Some(vec!["model".to_string(), "env.API_BASE".to_string()]));
+        assert_eq!(service.get_profile_status("work"), ProfileStatus::FullMatch);
+        assert_eq!(service.get_profile_status("sonnet"), ProfileStatus::NoMatch);
         
-        let _ = fs::remove_file(backup_path);
-        log::info!("Successfully restored settings from backup");
-        Ok(())
-    }
-    
-    /// Refresh the active status of all profiles (optimized)
-    pub fn refresh_profile_status(&mut self) -> AppResult<()> {
-        // Create a temporary copy of profiles to avoid mutable borrow conflicts
-        let mut profiles_copy = self.profiles.clone();
-        self.update_profile_status_optimized(&mut profiles_copy)?;
-        self.profiles = profiles_copy;
-        Ok(())
+        // Keys missing from both sides are treated as matching
+        service.set_compare_keys(Some(vec!["apiBase".to_string()]));
+        assert_eq!(service.get_profile_status("work"), ProfileStatus::FullMatch);
+        
+        service.set_compare_keys(None);
+        assert_eq!(service.get_profile_status("work"), ProfileStatus::NoMatch);
     }
     
-    /// Validate the integrity of a configuration switch
-    pub fn validate_switch(&self, profile_name: &str) -> AppResult<bool> {
-        let profile = self.profiles.iter()
-            .find(|p| p.name == profile_name)
-            .ok_or_else(|| AppError::ConfigError(format!("Profile '{}' not found", profile_name)))?;
+    #[test]
+    fn test_is_transient_rename_error() {
+        use std::io::{Error, ErrorKind};
         
-        let current_content = self.read_default_settings()?;
-        Ok(self.compare_configurations(&profile.content, &current_content))
+        assert!(ConfigService::is_transient_rename_error(&Error::from(ErrorKind::PermissionDenied)));
+        assert!(ConfigService::is_transient_rename_error(&Error::from(ErrorKind::AlreadyExists)));
+        assert!(!ConfigService::is_transient_rename_error(&Error::from(ErrorKind::NotFound)));
+        assert!(!ConfigService::is_transient_rename_error(&Error::from(ErrorKind::InvalidInput)));
     }
     
-    /// Get Claude directory path
-    pub fn get_claude_dir(&self) -> &Path {
-        &self.claude_dir
+    #[test]
+    fn test_redact_secrets() {
+        let content = r#"{
+            "model": "opus",
+            "apiKeyHelper": "/bin/get-key",
+            "env": {"ANTHROPIC_AUTH_TOKEN": "sk-123", "ANTHROPIC_BASE_URL": "https://example.com"},
+            "hooks": [{"secret": "s"}]
+        }"#;
+        
+        let redacted: serde_json::Value =
+            serde_json::from_str(&ConfigService::redact_secrets(content).unwrap()).unwrap();
+        assert_eq!(redacted["model"], "opus");
+        assert_eq!(redacted["apiKeyHelper"], "[REDACTED]");
+        assert_eq!(redacted["env"]["ANTHROPIC_AUTH_TOKEN"], "[REDACTED]");
+        assert_eq!(redacted["env"]["ANTHROPIC_BASE_URL"], "https://example.com");
+        assert_eq!(redacted["hooks"][0]["secret"], "[REDACTED]");
+        
+        assert!(ConfigService::redact_secrets("not json").is_err());
+        
+        let secrets = ConfigService::find_secret_keys(content).unwrap();
+        assert_eq!(secrets, vec!["apiKeyHelper", "env.ANTHROPIC_AUTH_TOKEN", "hooks.0.secret"]);
+        assert!(ConfigService::find_secret_keys(r#"{"model": "opus"}"#).unwrap().is_empty());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
     
-    fn create_test_config_service() -> (ConfigService, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let claude_dir = temp_dir.path().to_path_buf();
+    #[test]
+    fn test_import_profile_content() {
+        let (mut service, temp_dir) = create_test_config_service();
+        service.scan_profiles().unwrap();
         
-        // Create default settings.json
-        let settings_content = r#"{"theme": "dark", "language": "en"}"#;
-        fs::write(claude_dir.join("settings.json"), settings_content).unwrap();
+        service.import_profile_content("pasted", r#"{"theme": "light"}"#, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("pasted.settings.json")).unwrap(),
+            r#"{"theme": "light"}"#
+        );
+        assert!(service.find_profile("pasted").is_ok());
         
-        let service = ConfigService::new(claude_dir);
-        (service, temp_dir)
+        assert!(service.import_profile_content("pasted", r#"{"theme": "dark"}"#, false).is_err());
+        assert!(service.import_profile_content("text", "hello from chat", false).is_err());
+        assert!(!temp_dir.path().join("text.settings.json").exists());
     }
     
     #[test]
-    fn test_scan_profiles_empty_directory() {
-        let (mut service, _temp_dir) = create_test_config_service();
+    fn test_profile_status_serialization() {
+        let partial = ProfileStatus::PartialMatch { fields: vec!["model".to_string()] };
+        assert_eq!(
+            serde_json::to_value(&partial).unwrap(),
+            serde_json::json!({"status": "partial_match", "fields": ["model"]})
+        );
+        assert_eq!(
+            serde_json::to_value(ProfileStatus::Error { message: "bad".to_string() }).unwrap(),
+            serde_json::json!({"status": "error", "message": "bad"})
+        );
         
-        let profiles = service.scan_profiles().unwrap();
-        assert!(profiles.is_empty());
+        let parsed: ProfileStatus = serde_json::from_str(r#"{"status": "full_match"}"#).unwrap();
+        assert_eq!(parsed, ProfileStatus::FullMatch);
+        
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("other.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        assert_eq!(
+            service.compare_profiles_by_name(),
+            vec![
+                (LIVE_PROFILE_NAME.to_string(), ProfileStatus::FullMatch),
+                ("other".to_string(), ProfileStatus::NoMatch),
+            ]
+        );
     }
     
     #[test]
-    fn test_scan_profiles_with_profiles() {
+    fn test_profile_summaries() {
         let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("active.settings.json"), r#"{"theme": "dark", "language": "en"}"#).unwrap();
+        fs::write(temp_dir.path().join("broken.settings.json"), "{").unwrap();
+        service.scan_profiles().unwrap();
         
-        // Create test profile files
-        let profile1_content = r#"{"theme": "light", "language": "en"}"#;
-        let profile2_content = r#"{"theme": "dark", "language": "en"}"#; // Same as default
+        let mut summaries = service.profile_summaries();
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(summaries, vec![
+            ProfileSummary { name: LIVE_PROFILE_NAME.to_string(), status: ProfileStatusKind::FullMatch, glyph: "✅".to_string() },
+            ProfileSummary { name: "active".to_string(), status: ProfileStatusKind::FullMatch, glyph: "✅".to_string() },
+            ProfileSummary { name: "broken".to_string(), status: ProfileStatusKind::Error, glyph: "❌".to_string() },
+        ]);
         
-        fs::write(temp_dir.path().join("work.settings.json"), profile1_content).unwrap();
-        fs::write(temp_dir.path().join("personal.settings.json"), profile2_content).unwrap();
+        let json = serde_json::to_value(&summaries[0]).unwrap();
+        assert_eq!(json["status"], "full_match");
         
-        let profiles = service.scan_profiles().unwrap();
-        assert_eq!(profiles.len(), 2);
+        service.set_status_glyphs(StatusGlyphs::Ascii);
+        let glyphs: Vec<String> = service.profile_summaries().into_iter().map(|summary| summary.glyph).collect();
+        assert!(glyphs.contains(&"[*]".to_string()) && glyphs.contains(&"[x]".to_string()));
         
-        // Check that personal profile is active (matches default)
-        let personal_profile = profiles.iter().find(|p| p.name == "personal").unwrap();
-        assert!(personal_profile.is_active);
+        service.set_status_glyphs(StatusGlyphs::None);
+        assert!(service.profile_summaries().iter().all(|summary| summary.glyph.is_empty()));
+    }
+    
+    #[test]
+    fn test_list_profiles_detailed() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let active_content = r#"{"theme": "dark", "language": "en"}"#;
+        fs::write(temp_dir.path().join("active.settings.json"), active_content).unwrap();
+        fs::write(temp_dir.path().join("broken.settings.json"), "{").unwrap();
+        service.scan_profiles().unwrap();
         
-        let work_profile = profiles.iter().find(|p| p.name == "work").unwrap();
-        assert!(!work_profile.is_active);
+        let details = service.list_profiles_detailed();
+        assert_eq!(details.len(), service.get_profiles().len());
+        
+        let active = details.iter().find(|d| d.name == "active").unwrap();
+        assert!(active.is_active);
+        assert_eq!(active.status, ProfileStatus::FullMatch);
+        assert_eq!(active.size, active_content.len() as u64);
+        assert!(active.path.ends_with("active.settings.json"));
+        
+        let broken = details.iter().find(|d| d.name == "broken").unwrap();
+        assert!(matches!(broken.status, ProfileStatus::Error { .. }));
+        
+        let json = serde_json::to_value(active).unwrap();
+        assert_eq!(json["status"]["status"], "full_match");
     }
     
     #[test]
-    fn test_compare_configurations() {
-        let (service, _temp_dir) = create_test_config_service();
+    fn test_get_current_settings_content() {
+        let (mut service, temp_dir) = create_test_config_service();
         
-        let config1 = r#"{"theme": "dark", "language": "en"}"#;
-        let config2 = r#"{"language": "en", "theme": "dark"}"#; // Same content, different order
-        let config3 = r#"{"theme": "light", "language": "en"}"#;
+        let content = service.get_current_settings_content().unwrap();
+        assert_eq!(content, r#"{"theme": "dark", "language": "en"}"#);
         
-        assert!(service.compare_configurations(config1, config2));
-        assert!(!service.compare_configurations(config1, config3));
+        fs::remove_file(temp_dir.path().join("settings.json")).unwrap();
+        assert!(service.get_current_settings_content().is_err());
     }
     
     #[test]
@@ -803,6 +3550,257 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_default_settings_cache_refreshes_on_modification() {
+        let (mut service, _temp_dir) = create_test_config_service();
+        let settings_path = service.default_settings_path.clone();
+        
+        // Pin the original modification time well in the past
+        let original_mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        fs::File::options().write(true).open(&settings_path).unwrap()
+            .set_modified(original_mtime).unwrap();
+        
+        let cached = service.get_default_settings_cached().unwrap();
+        assert_eq!(cached, r#"{"theme": "dark", "language": "en"}"#);
+        
+        // Modify the file; its new mtime is still older than the moment we cached
+        fs::write(&settings_path, r#"{"theme": "light"}"#).unwrap();
+        fs::File::options().write(true).open(&settings_path).unwrap()
+            .set_modified(original_mtime + Duration::from_secs(1)).unwrap();
+        
+        let refreshed = service.get_default_settings_cached().unwrap();
+        assert_eq!(refreshed, r#"{"theme": "light"}"#);
+    }
+    
+    #[test]
+    fn test_get_profile_mtimes() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let old_path = temp_dir.path().join("old.settings.json");
+        fs::write(&old_path, r#"{"theme": "light"}"#).unwrap();
+        fs::File::options().write(true).open(&old_path).unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000)).unwrap();
+        fs::write(temp_dir.path().join("new.settings.json"), r#"{"theme": "dark"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        let mtimes: HashMap<String, u64> = service.get_profile_mtimes().into_iter().collect();
+        assert_eq!(mtimes.len(), 3);
+        assert_eq!(mtimes["old"], 1_000);
+        assert!(mtimes["new"] > 1_000);
+        assert!(mtimes.contains_key(LIVE_PROFILE_NAME));
+        
+        // A profile deleted since the scan is left out
+        fs::remove_file(&old_path).unwrap();
+        service.clear_cache();
+        let mtimes: HashMap<String, u64> = service.get_profile_mtimes().into_iter().collect();
+        assert!(!mtimes.contains_key("old"));
+        assert!(mtimes.contains_key("new"));
+    }
+    
+    #[test]
+    fn test_scan_profiles_with_toml_profile() {
+        let (mut service, temp_dir) = create_test_config_service();
+        
+        // Same values as the default settings.json, written as TOML
+        fs::write(
+            temp_dir.path().join("toml.settings.toml"),
+            "theme = \"dark\"\nlanguage = \"en\"\n",
+        ).unwrap();
+        fs::write(temp_dir.path().join("other.settings.toml"), "theme = \"light\"\n").unwrap();
+        
+        let profiles = service.scan_profiles().unwrap();
+        assert_eq!(profiles.len(), 3);
+        
+        let toml_profile = profiles.iter().find(|p| p.name == "toml").unwrap();
+        assert!(toml_profile.is_active);
+        assert_eq!(service.get_profile_status("toml"), ProfileStatus::FullMatch);
+        
+        // Switching writes JSON to settings.json
+        service.switch_profile("other").unwrap();
+        let written = fs::read_to_string(temp_dir.path().join("settings.json")).unwrap();
+        let written_json: serde_json::Value = serde_json::from_str(&written).unwrap();
+        assert_eq!(written_json, serde_json::json!({"theme": "light"}));
+    }
+    
+    #[test]
+    fn test_scan_profiles_invalid_toml_and_json_precedence() {
+        let (mut service, temp_dir) = create_test_config_service();
+        
+        fs::write(temp_dir.path().join("broken.settings.toml"), "theme = ").unwrap();
+        fs::write(temp_dir.path().join("both.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        fs::write(temp_dir.path().join("both.settings.toml"), "theme = \"blue\"\n").unwrap();
+        
+        let err = ConfigService::convert_toml_to_json("broken", "theme = \"dark\"\nlanguage = ").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+        
+        let profiles = service.scan_profiles().unwrap();
+        assert_eq!(profiles.len(), 3);
+        let both = profiles.iter().find(|p| p.name == "both").unwrap();
+        assert!(both.path.ends_with("both.settings.json"));
+        assert!(matches!(service.get_profile_status("broken"), ProfileStatus::Error { .. }));
+    }
+    
+    #[test]
+    fn test_set_claude_dir_rescans_new_directory() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("old.settings.json"), "{}").unwrap();
+        service.scan_profiles().unwrap();
+        assert_eq!(service.get_profiles().len(), 2);
+        
+        let new_dir = TempDir::new().unwrap();
+        fs::write(new_dir.path().join("settings.json"), "{}").unwrap();
+        fs::write(new_dir.path().join("new.settings.json"), "{}").unwrap();
+        
+        service.set_claude_dir(new_dir.path().to_path_buf());
+        assert!(service.get_profiles().is_empty());
+        
+        let profiles = service.scan_profiles().unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[1].name, "new");
+        assert!(profiles[1].is_active);
+    }
+    
+    #[test]
+    fn test_repair_settings() {
+        let (mut service, temp_dir) = create_test_config_service();
+        let settings_path = temp_dir.path().join("settings.json");
+        service.scan_profiles().unwrap();
+        assert_eq!(service.repair_settings(false).unwrap(), SettingsRepair::NotNeeded);
+        
+        // Without a valid backup only a reset can repair it
+        let backup_dir = service.get_backup_dir();
+        fs::create_dir_all(&backup_dir).unwrap();
+        fs::write(backup_dir.join("settings.json.backup.1700000200"), "{ broken").unwrap();
+        fs::write(&settings_path, r#"{"theme": "#).unwrap();
+        assert!(service.repair_settings(false).is_err());
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), r#"{"theme": "#);
+        
+        // The newest backup that parses is restored, skipping the broken one
+        let valid = backup_dir.join("settings.json.backup.1700000100");
+        fs::write(&valid, r#"{"theme": "light"}"#).unwrap();
+        let repair = service.repair_settings(false).unwrap();
+        assert_eq!(repair, SettingsRepair::RestoredBackup { backup: valid.to_string_lossy().to_string() });
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), r#"{"theme": "light"}"#);
+        
+        fs::write(&settings_path, "[]").unwrap();
+        assert_eq!(service.repair_settings(true).unwrap(), SettingsRepair::ResetToEmpty);
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), "{}");
+        
+        // The broken file is kept next to the backups
+        let corrupt = fs::read_dir(&backup_dir).unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with("settings.json.corrupt."))
+            .count();
+        assert!(corrupt >= 1);
+    }
+    
+    #[test]
+    fn test_list_backups() {
+        let (service, _temp_dir) = create_test_config_service();
+        assert!(service.list_backups().unwrap().is_empty());
+        
+        let backup_dir = service.get_backup_dir();
+        fs::create_dir_all(&backup_dir).unwrap();
+        fs::write(backup_dir.join("settings.json.backup.1700000000"), "{}").unwrap();
+        fs::write(backup_dir.join("settings.json.backup.1700000100"), "x".repeat(1536)).unwrap();
+        fs::write(backup_dir.join("settings.json.tmp"), "{}").unwrap();
+        fs::write(backup_dir.join("settings.json.backup.notatime"), "{}").unwrap();
+        fs::write(backup_dir.join("work.settings.json"), "{}").unwrap();
+        
+        let backups = service.list_backups().unwrap();
+        let timestamps: Vec<u64> = backups.iter().map(|b| b.timestamp).collect();
+        assert_eq!(timestamps, vec![1700000100, 1700000000]);
+        assert_eq!(backups[0].size, "1.5 KB");
+        assert_eq!(backups[1].size, "2 B");
+        assert!(backups[0].path.ends_with("settings.json.backup.1700000100"));
+        
+        let missing = ConfigService::new(backup_dir.join("missing"));
+        assert!(missing.list_backups().unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_cleanup_prunes_backups_by_age() {
+        let (mut service, _temp_dir) = create_test_config_service();
+        let backup_dir = service.get_backup_dir();
+        fs::create_dir_all(&backup_dir).unwrap();
+        
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+        let day = 24 * 60 * 60;
+        let recent = backup_dir.join(format!("settings.json.backup.{}", now - day));
+        let old = backup_dir.join(format!("settings.json.backup.{}", now - 40 * day));
+        fs::write(&recent, "{}").unwrap();
+        fs::write(&old, "{}").unwrap();
+        
+        // Without a maximum age only the count matters
+        service.cleanup_old_backups();
+        assert!(old.exists());
+        
+        service.set_backup_max_age_days(Some(30));
+        service.cleanup_old_backups();
+        assert!(recent.exists());
+        assert!(!old.exists());
+        
+        // The newest backup is kept even when it's too old
+        fs::remove_file(&recent).unwrap();
+        fs::write(&old, "{}").unwrap();
+        service.cleanup_old_backups();
+        assert!(old.exists());
+    }
+    
+    #[test]
+    fn test_resolve_backup_path() {
+        let (service, temp_dir) = create_test_config_service();
+        let backup_dir = service.get_backup_dir();
+        fs::create_dir_all(&backup_dir).unwrap();
+        let backup = backup_dir.join("settings.json.backup.1700000000");
+        fs::write(&backup, "{}").unwrap();
+        fs::write(backup_dir.join("notes.txt"), "").unwrap();
+        
+        assert_eq!(service.resolve_backup_path(&backup).unwrap(), backup.canonicalize().unwrap());
+        
+        // Traversal out of the backup directory is rejected even when the target exists
+        let traversal = backup_dir.join("..").join("settings.json");
+        assert!(matches!(service.resolve_backup_path(&traversal), Err(AppError::PermissionError(_))));
+        assert!(service.resolve_backup_path(&temp_dir.path().join("settings.json")).is_err());
+        assert!(service.resolve_backup_path(&backup_dir.join("notes.txt")).is_err());
+        assert!(service.resolve_backup_path(&backup_dir.join("settings.json.backup.1")).is_err());
+    }
+    
+    #[test]
+    fn test_switch_keeps_backups_in_backup_dir() {
+        let (mut service, temp_dir) = create_test_config_service();
+        fs::write(temp_dir.path().join("light.settings.json"), r#"{"theme": "light"}"#).unwrap();
+        service.scan_profiles().unwrap();
+        
+        let backup_dir = service.get_backup_dir();
+        assert_eq!(backup_dir, temp_dir.path().join(".cccs_backups"));
+        
+        // Old backups beyond the limit are pruned on the next switch
+        fs::create_dir_all(&backup_dir).unwrap();
+        for i in 0..7 {
+            fs::write(backup_dir.join(format!("settings.json.backup.{}", 1000 + i)), "{}").unwrap();
+        }
+        
+        service.switch_profile("light").unwrap();
+        
+        let backups = service.list_backups().unwrap();
+        assert_eq!(backups.len(), 5);
+        assert!(backups[0].timestamp > 1006);
+        
+        // Nothing backup-related leaks into the Claude directory or the profile list
+        let stray: Vec<_> = fs::read_dir(temp_dir.path()).unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("settings.json.backup."))
+            .collect();
+        assert!(stray.is_empty());
+        
+        fs::write(temp_dir.path().join("settings.json.backup.1"), "{}").unwrap();
+        let names: Vec<_> = service.scan_profiles().unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec![LIVE_PROFILE_NAME, "light"]);
+        
+        let custom_dir = temp_dir.path().join("elsewhere");
+        service.set_backup_dir(Some(custom_dir.clone()));
+        assert_eq!(service.get_backup_dir(), custom_dir);
+    }
+    
     #[test]
     fn test_backup_and_restore() {
         let (service, temp_dir) = create_test_config_service();
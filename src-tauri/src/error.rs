@@ -1,11 +1,20 @@
 // CCCS Error handling
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::SystemTime;
 use thiserror::Error;
 
+/// Number of errors kept in the error log, older entries are dropped first
+pub const MAX_ERROR_LOG_ENTRIES: usize = 50;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Claude Code installation not found")]
     ClaudeNotFound,
     
+    #[error("Not a Claude Code directory: {0}")]
+    WrongClaudeProduct(String),
+    
     #[error("Configuration file error: {0}")]
     ConfigError(String),
     
@@ -18,9 +27,15 @@ pub enum AppError {
     #[error("Permission denied: {0}")]
     PermissionError(String),
     
+    #[error("Profile locked: {0}")]
+    ProfileLocked(String),
+    
     #[error("Settings error: {0}")]
     SettingsError(String),
     
+    #[error("Settings won't be saved: {0}")]
+    SettingsNotPersisted(String),
+    
     #[error("Monitor service error: {0}")]
     MonitorError(String),
     
@@ -37,5 +52,133 @@ pub enum AppError {
     TauriError(#[from] tauri::Error),
 }
 
+impl AppError {
+    /// Stable, machine-readable code for the error variant
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::ClaudeNotFound => "claude_not_found",
+            AppError::WrongClaudeProduct(_) => "wrong_claude_product",
+            AppError::ConfigError(_) => "config_error",
+            AppError::TrayError(_) => "tray_error",
+            AppError::FileSystemError(_) => "file_system_error",
+            AppError::PermissionError(_) => "permission_denied",
+            AppError::ProfileLocked(_) => "profile_locked",
+            AppError::SettingsError(_) => "settings_error",
+            AppError::SettingsNotPersisted(_) => "settings_not_persisted",
+            AppError::MonitorError(_) => "monitor_error",
+            AppError::I18nError(_) => "i18n_error",
+            AppError::IoError(_) => "io_error",
+            AppError::JsonError(_) => "json_error",
+            AppError::TauriError(_) => "tauri_error",
+        }
+    }
+}
+
+// Error returned to the frontend, so it can handle errors by category instead of parsing messages
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorDto {
+    pub code: String,
+    pub message: String,
+}
+
+impl ErrorDto {
+    /// Error for failures outside AppError, such as a poisoned state lock
+    pub fn state_unavailable(message: &str) -> Self {
+        Self {
+            code: "state_unavailable".to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<AppError> for ErrorDto {
+    fn from(error: AppError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
 // Convenience type alias
-pub type AppResult<T> = Result<T, AppError>;
\ No newline at end of file
+pub type AppResult<T> = Result<T, AppError>;
+
+// A recorded error, for reporting bugs after the fact
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorLogEntry {
+    pub timestamp: u64,        // Unix seconds
+    pub operation: String,     // What was being done, e.g. "switch_profile"
+    pub code: String,
+    pub message: String,
+}
+
+/// Ring buffer of the most recent errors
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorLogEntry>,
+}
+
+impl ErrorLog {
+    /// Record an error, dropping the oldest entry once the log is full
+    pub fn record(&mut self, operation: &str, error: &AppError) {
+        if self.entries.len() == MAX_ERROR_LOG_ENTRIES {
+            self.entries.pop_front();
+        }
+        
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.entries.push_back(ErrorLogEntry {
+            timestamp,
+            operation: operation.to_string(),
+            code: error.code().to_string(),
+            message: error.to_string(),
+        });
+    }
+    
+    /// Recorded errors, oldest first
+    pub fn entries(&self) -> Vec<ErrorLogEntry> {
+        self.entries.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_error_dto_from_app_error() {
+        let dto = ErrorDto::from(AppError::ConfigError("Profile 'work' not found".to_string()));
+        assert_eq!(dto.code, "config_error");
+        assert_eq!(dto.message, "Configuration file error: Profile 'work' not found");
+        
+        let dto = ErrorDto::from(AppError::PermissionError("settings.json".to_string()));
+        assert_eq!(dto.code, "permission_denied");
+        
+        let dto = ErrorDto::from(AppError::ProfileLocked("work".to_string()));
+        assert_eq!(dto.code, "profile_locked");
+        
+        let json = serde_json::to_value(ErrorDto::state_unavailable("busy")).unwrap();
+        assert_eq!(json, serde_json::json!({"code": "state_unavailable", "message": "busy"}));
+    }
+    
+    #[test]
+    fn test_error_log_is_capped() {
+        let mut log = ErrorLog::default();
+        assert!(log.entries().is_empty());
+        
+        for i in 0..MAX_ERROR_LOG_ENTRIES + 5 {
+            log.record("switch_profile", &AppError::ConfigError(format!("failure {}", i)));
+        }
+        
+        let entries = log.entries();
+        assert_eq!(entries.len(), MAX_ERROR_LOG_ENTRIES);
+        // The oldest entries were dropped
+        assert_eq!(entries[0].message, "Configuration file error: failure 5");
+        assert_eq!(entries.last().unwrap().message, format!("Configuration file error: failure {}", MAX_ERROR_LOG_ENTRIES + 4));
+        assert_eq!(entries[0].operation, "switch_profile");
+        assert_eq!(entries[0].code, "config_error");
+        assert!(entries[0].timestamp > 0);
+    }
+}
\ No newline at end of file
@@ -126,6 +126,17 @@ impl I18nService {
         en_resources.insert("save".to_string(), "Save".to_string());
         en_resources.insert("loading".to_string(), "Loading...".to_string());
         en_resources.insert("saving".to_string(), "Saving...".to_string());
+        en_resources.insert("time_just_now".to_string(), "just now".to_string());
+        en_resources.insert("time_minute_ago".to_string(), "{} minute ago".to_string());
+        en_resources.insert("time_minutes_ago".to_string(), "{} minutes ago".to_string());
+        en_resources.insert("time_hour_ago".to_string(), "{} hour ago".to_string());
+        en_resources.insert("time_hours_ago".to_string(), "{} hours ago".to_string());
+        en_resources.insert("time_day_ago".to_string(), "{} day ago".to_string());
+        en_resources.insert("time_days_ago".to_string(), "{} days ago".to_string());
+        en_resources.insert("profile_load_error".to_string(), "{} profile couldn't be loaded — open Settings to view".to_string());
+        en_resources.insert("profile_load_errors".to_string(), "{} profiles couldn't be loaded — open Settings to view".to_string());
+        en_resources.insert("external_change_detected".to_string(), "settings.json was changed while CCCS wasn't running and no longer matches profile '{}'".to_string());
+        en_resources.insert("create_default_settings".to_string(), "No Claude directory was selected. Create an empty settings.json in ~/.claude?".to_string());
         
         // Chinese resources
         let mut zh_resources = HashMap::new();
@@ -155,6 +166,17 @@ impl I18nService {
         zh_resources.insert("save".to_string(), "保存".to_string());
         zh_resources.insert("loading".to_string(), "加载中...".to_string());
         zh_resources.insert("saving".to_string(), "保存中...".to_string());
+        zh_resources.insert("time_just_now".to_string(), "刚刚".to_string());
+        zh_resources.insert("time_minute_ago".to_string(), "{} 分钟前".to_string());
+        zh_resources.insert("time_minutes_ago".to_string(), "{} 分钟前".to_string());
+        zh_resources.insert("time_hour_ago".to_string(), "{} 小时前".to_string());
+        zh_resources.insert("time_hours_ago".to_string(), "{} 小时前".to_string());
+        zh_resources.insert("time_day_ago".to_string(), "{} 天前".to_string());
+        zh_resources.insert("time_days_ago".to_string(), "{} 天前".to_string());
+        zh_resources.insert("profile_load_error".to_string(), "{} 个配置无法加载 — 打开设置查看".to_string());
+        zh_resources.insert("profile_load_errors".to_string(), "{} 个配置无法加载 — 打开设置查看".to_string());
+        zh_resources.insert("external_change_detected".to_string(), "CCCS 未运行时 settings.json 被修改，已不再匹配配置 '{}'".to_string());
+        zh_resources.insert("create_default_settings".to_string(), "未选择 Claude 目录。是否在 ~/.claude 中创建空的 settings.json？".to_string());
         
         self.text_resources.insert("en".to_string(), en_resources);
         self.text_resources.insert("zh".to_string(), zh_resources);
@@ -168,6 +190,18 @@ impl I18nService {
         self.get_text_with_args(key, &[])
     }
     
+    /// Get the text for several keys at once, formatting the ones that have arguments
+    pub fn get_texts(&self, keys: &[String], args: &HashMap<String, Vec<String>>) -> HashMap<String, String> {
+        keys.iter()
+            .map(|key| {
+                let key_args: Vec<&str> = args.get(key)
+                    .map(|values| values.iter().map(|s| s.as_str()).collect())
+                    .unwrap_or_default();
+                (key.clone(), self.get_text_with_args(key, &key_args))
+            })
+            .collect()
+    }
+    
     /// Get text for a specific key with arguments for formatting
     pub fn get_text_with_args(&self, key: &str, args: &[&str]) -> String {
         let resources = self.text_resources.get(&self.current_locale)
@@ -191,6 +225,20 @@ impl I18nService {
         }
     }
     
+    /// Format a duration in the past as localized relative time, e.g. "2 minutes ago"
+    pub fn format_relative_time(&self, seconds_ago: u64) -> String {
+        let (count, singular_key, plural_key) = match seconds_ago {
+            0..=59 => return self.get_text("time_just_now"),
+            60..=3599 => (seconds_ago / 60, "time_minute_ago", "time_minutes_ago"),
+            3600..=86399 => (seconds_ago / 3600, "time_hour_ago", "time_hours_ago"),
+            _ => (seconds_ago / 86400, "time_day_ago", "time_days_ago"),
+        };
+        
+        // 中文没有单复数之分，两个 key 的文本相同
+        let key = if count == 1 { singular_key } else { plural_key };
+        self.get_text_with_args(key, &[&count.to_string()])
+    }
+    
     /// Get all supported locales
     pub fn get_supported_locales() -> Vec<String> {
         vec!["en".to_string(), "zh".to_string()]
@@ -286,6 +334,25 @@ pub async fn get_text(
     }
 }
 
+#[tauri::command]
+pub async fn get_texts(
+    keys: Vec<String>,
+    args: Option<HashMap<String, Vec<String>>>,
+    state: tauri::State<'_, std::sync::Mutex<I18nService>>,
+) -> Result<HashMap<String, String>, String> {
+    let service = state.lock().map_err(|e| format!("Failed to lock i18n service: {}", e))?;
+    Ok(service.get_texts(&keys, &args.unwrap_or_default()))
+}
+
+#[tauri::command]
+pub async fn format_relative_time(
+    seconds_ago: u64,
+    state: tauri::State<'_, std::sync::Mutex<I18nService>>,
+) -> Result<String, String> {
+    let service = state.lock().map_err(|e| format!("Failed to lock i18n service: {}", e))?;
+    Ok(service.format_relative_time(seconds_ago))
+}
+
 #[tauri::command]
 pub async fn get_supported_locales() -> Result<Vec<String>, String> {
     Ok(I18nService::get_supported_locales())
@@ -324,6 +391,19 @@ mod tests {
         assert!(text.contains("test"));
     }
     
+    #[test]
+    fn test_get_texts() {
+        let service = I18nService::new();
+        let keys = vec!["app_name".to_string(), "switch_profile".to_string(), "non_existent_key".to_string()];
+        let args = HashMap::from([("switch_profile".to_string(), vec!["work".to_string()])]);
+        
+        let texts = service.get_texts(&keys, &args);
+        assert_eq!(texts.len(), 3);
+        assert_eq!(texts["app_name"], "CCCS");
+        assert_eq!(texts["switch_profile"], service.get_text_with_args("switch_profile", &["work"]));
+        assert_eq!(texts["non_existent_key"], "non_existent_key");
+    }
+    
     #[test]
     fn test_set_locale() {
         let mut service = I18nService::new();
@@ -349,6 +429,27 @@ mod tests {
         assert_eq!(service.get_text("settings"), "设置");
     }
     
+    #[test]
+    fn test_format_relative_time() {
+        let mut service = I18nService::new();
+        
+        service.set_locale("en").unwrap();
+        assert_eq!(service.format_relative_time(0), "just now");
+        assert_eq!(service.format_relative_time(59), "just now");
+        assert_eq!(service.format_relative_time(60), "1 minute ago");
+        assert_eq!(service.format_relative_time(150), "2 minutes ago");
+        assert_eq!(service.format_relative_time(3600), "1 hour ago");
+        assert_eq!(service.format_relative_time(3 * 3600), "3 hours ago");
+        assert_eq!(service.format_relative_time(86400), "1 day ago");
+        assert_eq!(service.format_relative_time(10 * 86400), "10 days ago");
+        
+        service.set_locale("zh").unwrap();
+        assert_eq!(service.format_relative_time(30), "刚刚");
+        assert_eq!(service.format_relative_time(60), "1 分钟前");
+        assert_eq!(service.format_relative_time(120), "2 分钟前");
+        assert_eq!(service.format_relative_time(2 * 86400), "2 天前");
+    }
+    
     #[test]
     fn test_get_supported_locales() {
         let locales = I18nService::get_supported_locales();
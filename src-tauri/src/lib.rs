@@ -15,15 +15,16 @@ mod types;
 pub mod performance_tests;
 
 // Re-exports for public API
-pub use error::AppError;
+pub use error::{AppError, ErrorDto, ErrorLogEntry};
 pub use types::*;
 
 pub type AppResult<T> = Result<T, AppError>;
 
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Emitter, Manager};
 use serde::Serialize;
 use std::sync::{Arc, Mutex};
 use app::App;
+use config_service::ConfigService;
 
 #[derive(Serialize)]
 struct ProfilesInfo {
@@ -56,16 +57,951 @@ async fn get_profiles_info(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Resu
     
     let profiles = config.get_profiles();
     let claude_dir = config.get_claude_dir();
+    let monitor_status = if app.is_monitoring() { "active" } else { "inactive" };
     
-    log::info!("Returning profiles info: {} profiles found in {}", profiles.len(), claude_dir.display());
+    // The live "(current)" profile isn't a profile file
+    let profiles_count = profiles.iter().filter(|p| !p.is_live).count();
+    
+    log::info!("Returning profiles info: {} profiles found in {}", profiles_count, claude_dir.display());
     
     Ok(ProfilesInfo {
         claude_directory: claude_dir.to_string_lossy().to_string(),
-        profiles_count: profiles.len(),
-        monitor_status: "inactive".to_string(),
+        profiles_count,
+        monitor_status: monitor_status.to_string(),
+    })
+}
+
+#[tauri::command]
+async fn is_monitoring(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<bool, String> {
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    Ok(app.is_monitoring())
+}
+
+/// Run an operation against the config service owned by the app state
+fn with_config_service<T>(
+    app_state: &Arc<Mutex<App>>,
+    operation: impl FnOnce(&mut ConfigService) -> AppResult<T>,
+) -> Result<T, String> {
+    with_config_service_structured(app_state, operation).map_err(|e| e.message)
+}
+
+/// Same as `with_config_service`, keeping the error category for the frontend
+fn with_config_service_structured<T>(
+    app_state: &Arc<Mutex<App>>,
+    operation: impl FnOnce(&mut ConfigService) -> AppResult<T>,
+) -> Result<T, ErrorDto> {
+    let config_service = match app_state.lock() {
+        Ok(app) => app.get_config_service(),
+        Err(e) => {
+            log::error!("Failed to lock app state: {}", e);
+            return Err(ErrorDto::state_unavailable("Failed to access application state"));
+        }
+    };
+    
+    let mut config = match config_service.lock() {
+        Ok(guard) => guard,
+        Err(e) => {
+            log::error!("Failed to lock config service: {}", e);
+            return Err(ErrorDto::state_unavailable("Failed to access configuration service"));
+        }
+    };
+    
+    operation(&mut config).map_err(ErrorDto::from)
+}
+
+#[tauri::command]
+async fn get_profile_names(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<Vec<String>, String> {
+    // Only hold the lock while copying names, sort afterwards
+    let mut names = with_config_service(&app_state, |config| {
+        Ok(config.get_profiles().iter().map(|p| p.name.clone()).collect::<Vec<_>>())
+    })?;
+    names.sort();
+    Ok(names)
+}
+
+#[tauri::command]
+async fn switch_profile(
+    profile_name: String,
+    force: Option<bool>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), ErrorDto> {
+    log::info!("switch_profile called: {}", profile_name);
+    
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        ErrorDto::state_unavailable("Failed to access application state")
+    })?;
+    
+    // Same path as a tray click so the menu status stays in sync, but the error comes back here
+    app.switch_profile(&profile_name, force.unwrap_or(false)).map_err(ErrorDto::from)
+}
+
+#[tauri::command]
+async fn switch_profile_by_index(
+    index: usize,
+    force: Option<bool>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), ErrorDto> {
+    log::info!("switch_profile_by_index called: {}", index);
+    
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        ErrorDto::state_unavailable("Failed to access application state")
+    })?;
+    
+    // Resolved under the config lock, which is released again before switching
+    let profile_name = app.get_config_service().lock()
+        .map_err(|_| ErrorDto::state_unavailable("Failed to access configuration service"))?
+        .profile_name_at(index)?;
+    app.switch_profile(&profile_name, force.unwrap_or(false)).map_err(ErrorDto::from)
+}
+
+#[tauri::command]
+async fn lock_profile(profile_name: String, app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<(), ErrorDto> {
+    set_profile_locked(&app_state, &profile_name, true)
+}
+
+#[tauri::command]
+async fn unlock_profile(profile_name: String, app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<(), ErrorDto> {
+    set_profile_locked(&app_state, &profile_name, false)
+}
+
+fn set_profile_locked(app_state: &Arc<Mutex<App>>, profile_name: &str, locked: bool) -> Result<(), ErrorDto> {
+    log::info!("{} profile: {}", if locked { "Locking" } else { "Unlocking" }, profile_name);
+    
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        ErrorDto::state_unavailable("Failed to access application state")
+    })?;
+    app.set_profile_locked(profile_name, locked).map_err(ErrorDto::from)
+}
+
+#[tauri::command]
+async fn set_profile_alias(
+    profile_name: String,
+    alias: Option<String>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), ErrorDto> {
+    log::info!("set_profile_alias called: {} -> {:?}", profile_name, alias);
+    
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        ErrorDto::state_unavailable("Failed to access application state")
+    })?;
+    app.set_profile_alias(&profile_name, alias.as_deref()).map_err(ErrorDto::from)
+}
+
+/// Time switch cycles in a scratch directory next to settings.json, for diagnosing slow drives
+#[cfg(debug_assertions)]
+#[tauri::command]
+async fn benchmark_switch(
+    iterations: Option<u64>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<performance_tests::PerformanceTestResult, ErrorDto> {
+    let claude_dir = with_config_service_structured(&app_state, |service| Ok(service.get_claude_dir().to_path_buf()))?;
+    performance_tests::benchmark_switch(&claude_dir, iterations.unwrap_or(20)).map_err(ErrorDto::from)
+}
+
+#[tauri::command]
+async fn compare_profiles(
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<Vec<(String, ProfileStatus)>, String> {
+    with_config_service(&app_state, |config| Ok(config.compare_profiles_by_name()))
+}
+
+#[tauri::command]
+async fn matches_profile(
+    profile_name: String,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<bool, String> {
+    with_config_service(&app_state, |config| config.matches_profile(&profile_name))
+}
+
+#[tauri::command]
+async fn get_status_summary(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<StatusSummary, String> {
+    with_config_service(&app_state, |config| Ok(config.status_summary()))
+}
+
+#[tauri::command]
+async fn list_backups(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<Vec<BackupEntry>, String> {
+    with_config_service(&app_state, |config| config.list_backups())
+}
+
+#[tauri::command]
+async fn get_profile_checksum(
+    profile_name: String,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<ProfileChecksum, String> {
+    with_config_service(&app_state, |config| config.get_profile_checksum(&profile_name))
+}
+
+#[tauri::command]
+async fn scan_profiles_with_progress(
+    app: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<Vec<Profile>, String> {
+    with_config_service(&app_state, |config| {
+        config.scan_profiles_with_progress(|progress| {
+            let _ = app.emit("scan_progress", progress);
+        })
+    })
+}
+
+#[tauri::command]
+async fn list_profiles_detailed(
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<Vec<ProfileDetails>, String> {
+    with_config_service(&app_state, |config| Ok(config.list_profiles_detailed()))
+}
+
+#[tauri::command]
+async fn get_profile_mtimes(
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<std::collections::HashMap<String, u64>, String> {
+    with_config_service(&app_state, |config| Ok(config.get_profile_mtimes().into_iter().collect()))
+}
+
+#[tauri::command]
+async fn repair_settings(
+    reset: bool,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<SettingsRepair, ErrorDto> {
+    log::info!("repair_settings called (reset: {})", reset);
+    
+    let (repair, summaries) = with_config_service_structured(&app_state, |config| {
+        let repair = config.repair_settings(reset)?;
+        Ok((repair, config.profile_summaries()))
+    })?;
+    if repair != SettingsRepair::NotNeeded {
+        app::emit_profiles_changed(&app_handle, summaries);
+    }
+    Ok(repair)
+}
+
+#[tauri::command]
+async fn reveal_backup(path: String, app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<(), String> {
+    log::info!("reveal_backup called: {}", path);
+    
+    let backup = with_config_service(&app_state, |config| {
+        config.resolve_backup_path(std::path::Path::new(&path))
+    })?;
+    reveal_in_file_manager(&backup).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_log_path(app_handle: AppHandle) -> Result<String, String> {
+    log_file_path(&app_handle)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn open_log_file(app_handle: AppHandle) -> Result<(), String> {
+    log::info!("open_log_file called");
+    
+    let path = log_file_path(&app_handle).map_err(|e| e.to_string())?;
+    reveal_in_file_manager(&path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_diagnostics(
+    redact: Option<bool>,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    i18n_state: tauri::State<'_, Mutex<i18n_service::I18nService>>,
+) -> Result<Diagnostics, String> {
+    log::info!("get_diagnostics called");
+    
+    let redact = redact.unwrap_or(false);
+    let show_path = |path: &std::path::Path| {
+        let home = if redact { dirs::home_dir() } else { None };
+        match home.and_then(|home| path.strip_prefix(home).ok().map(|rest| rest.to_path_buf())) {
+            Some(rest) => std::path::Path::new("~").join(rest).to_string_lossy().to_string(),
+            None => path.to_string_lossy().to_string(),
+        }
+    };
+    
+    let (mut claude_dir, monitoring) = {
+        let app = app_state.lock().map_err(|e| {
+            log::error!("Failed to lock app state: {}", e);
+            "Failed to access application state".to_string()
+        })?;
+        (app.claude_dir_info(), app.monitoring_stats())
+    };
+    claude_dir.path = show_path(std::path::Path::new(&claude_dir.path));
+    
+    let settings_file = settings_state.lock()
+        .map_err(|e| format!("Failed to lock settings service: {}", e))
+        .map(|settings| show_path(settings.get_settings_file_path()))?;
+    let locale = i18n_state.lock()
+        .map_err(|e| format!("Failed to lock i18n service: {}", e))
+        .map(|i18n| i18n.get_current_locale().to_string())?;
+    
+    Ok(Diagnostics {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        app_version: app_handle.package_info().version.to_string(),
+        claude_dir,
+        settings_file,
+        log_file: log_file_path(&app_handle).ok().map(|path| show_path(&path)),
+        locale,
+        monitoring,
+    })
+}
+
+#[tauri::command]
+async fn get_error_log(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<Vec<ErrorLogEntry>, String> {
+    log::info!("get_error_log called");
+    
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    Ok(app.error_log())
+}
+
+/// The file the log plugin's default LogDir target writes to, `<app log dir>/<app name>.log`
+fn log_file_path(app_handle: &AppHandle) -> AppResult<std::path::PathBuf> {
+    let path = app_handle.path().app_log_dir()?
+        .join(format!("{}.log", app_handle.package_info().name));
+    
+    if !path.is_file() {
+        return Err(AppError::FileSystemError(format!(
+            "No log file at {:?}, logging to a file may not be enabled", path
+        )));
+    }
+    Ok(path)
+}
+
+/// Show a file selected in the platform file manager
+fn reveal_in_file_manager(path: &std::path::Path) -> AppResult<()> {
+    use std::process::Command;
+    
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg("-R").arg(path).spawn();
+    
+    // Explorer doesn't understand the \\?\ prefix canonicalize adds on Windows
+    #[cfg(target_os = "windows")]
+    let result = Command::new("explorer")
+        .arg(format!("/select,{}", path.display().to_string().trim_start_matches(r"\\?\")))
+        .spawn();
+    
+    // Most Linux file managers can't select a file, so open its folder instead
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result = Command::new("xdg-open").arg(path.parent().unwrap_or(path)).spawn();
+    
+    result
+        .map(|_| ())
+        .map_err(|e| AppError::FileSystemError(format!("Failed to open file manager for {:?}: {}", path, e)))
+}
+
+#[tauri::command]
+async fn diff_profiles(
+    profile_a: String,
+    profile_b: String,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<Vec<FieldDiff>, String> {
+    with_config_service(&app_state, |config| config.diff_profiles(&profile_a, &profile_b))
+}
+
+#[tauri::command]
+async fn compare_with_file(
+    path: String,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<FileComparison, String> {
+    log::info!("compare_with_file called: {}", path);
+    with_config_service(&app_state, |config| config.compare_with_file(std::path::Path::new(&path)))
+}
+
+#[tauri::command]
+async fn get_current_settings_content(
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<String, String> {
+    with_config_service(&app_state, |config| config.get_current_settings_content())
+}
+
+#[tauri::command]
+async fn get_profile_content(
+    profile_name: String,
+    redact_secrets: Option<bool>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<String, String> {
+    let content = with_config_service(&app_state, |config| config.get_profile_content(&profile_name))?;
+    
+    if redact_secrets.unwrap_or(false) {
+        ConfigService::redact_secrets(&content).map_err(|e| e.to_string())
+    } else {
+        Ok(content)
+    }
+}
+
+#[tauri::command]
+async fn preview_profile_content(
+    profile_name: String,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<ProfileContentPreview, String> {
+    with_config_service(&app_state, |config| config.content_preview(&profile_name))
+}
+
+#[tauri::command]
+async fn copy_settings_to_clipboard(
+    redact_secrets: bool,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    
+    log::info!("copy_settings_to_clipboard called (redact: {})", redact_secrets);
+    
+    let content = with_config_service(&app_state, |config| config.get_current_settings_content())?;
+    let content = if redact_secrets {
+        ConfigService::redact_secrets(&content).map_err(|e| e.to_string())?
+    } else {
+        content
+    };
+    
+    app_handle.clipboard().write_text(content).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn create_profile_from_template(
+    template: String,
+    name: String,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), ErrorDto> {
+    log::info!("create_profile_from_template called: {} -> {}", template, name);
+    
+    let summaries = with_config_service_structured(&app_state, |config| {
+        config.create_from_template(&template, &name)?;
+        Ok(config.profile_summaries())
+    })?;
+    app::emit_profiles_changed(&app_handle, summaries);
+    Ok(())
+}
+
+#[tauri::command]
+async fn snapshot_current_settings(
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<String, String> {
+    log::info!("snapshot_current_settings called");
+    
+    let (name, summaries) = with_config_service(&app_state, |config| {
+        let name = config.snapshot_current_settings()?;
+        Ok((name, config.profile_summaries()))
+    })?;
+    app::emit_profiles_changed(&app_handle, summaries);
+    Ok(name)
+}
+
+#[tauri::command]
+async fn validate_profile_json(content: String) -> Result<JsonValidation, String> {
+    Ok(ConfigService::check_profile_json(&content))
+}
+
+#[tauri::command]
+async fn save_profile_content(
+    name: String,
+    content: String,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    log::info!("save_profile_content called: {}", name);
+    
+    let summaries = with_config_service(&app_state, |config| {
+        config.save_profile_content(&name, &content)?;
+        Ok(config.profile_summaries())
+    })?;
+    app::emit_profiles_changed(&app_handle, summaries);
+    Ok(())
+}
+
+#[tauri::command]
+async fn format_profile(
+    name: String,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<bool, String> {
+    log::info!("format_profile called: {}", name);
+    
+    let (changed, summaries) = with_config_service(&app_state, |config| {
+        let changed = config.format_profile(&name)?;
+        Ok((changed, config.profile_summaries()))
+    })?;
+    if changed {
+        app::emit_profiles_changed(&app_handle, summaries);
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+async fn format_all_profiles(
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<ProfileFormatReport, String> {
+    log::info!("format_all_profiles called");
+    
+    let (report, summaries) = with_config_service(&app_state, |config| {
+        let report = config.format_all_profiles()?;
+        Ok((report, config.profile_summaries()))
+    })?;
+    if !report.formatted.is_empty() {
+        app::emit_profiles_changed(&app_handle, summaries);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+async fn update_compare_keys(
+    keys: Option<Vec<String>>,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_compare_keys(keys.clone()).map_err(|e| e.to_string())?;
+    }
+    
+    with_config_service(&app_state, |config| {
+        config.set_compare_keys(keys);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+async fn update_switch_mode(
+    mode: SwitchMode,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_switch_mode(mode).map_err(|e| e.to_string())?;
+    }
+    
+    // Which profile counts as active depends on the mode
+    let summaries = with_config_service(&app_state, |config| {
+        config.set_switch_mode(mode);
+        Ok(config.profile_summaries())
+    })?;
+    app::emit_profiles_changed(&app_handle, summaries);
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_env_merge(
+    enabled: bool,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_env_merge(enabled).map_err(|e| e.to_string())?;
+    }
+    
+    let summaries = with_config_service(&app_state, |config| {
+        config.set_env_merge(enabled);
+        Ok(config.profile_summaries())
+    })?;
+    app::emit_profiles_changed(&app_handle, summaries);
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_follow_symlinks(
+    enabled: bool,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_follow_symlinks(enabled).map_err(|e| e.to_string())?;
+    }
+    
+    with_config_service(&app_state, |config| {
+        config.set_follow_symlinks(enabled);
+        Ok(())
     })
 }
 
+#[tauri::command]
+async fn apply_monitor_interval(
+    minutes: u64,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<u64, String> {
+    log::info!("apply_monitor_interval called: {}", minutes);
+    
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_monitor_interval(minutes).map_err(|e| e.to_string())?;
+    }
+    
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    app.apply_monitor_interval(minutes).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn update_auto_start_monitoring(
+    enabled: bool,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_auto_start_monitoring(enabled).map_err(|e| e.to_string())?;
+    }
+    
+    // Applied right away instead of on next launch
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    if enabled && !app.is_monitoring() {
+        app.start_monitoring().map_err(|e| e.to_string())?;
+    } else if !enabled && app.is_monitoring() {
+        app.stop_monitoring();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn update_allow_empty_profiles(
+    enabled: bool,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_allow_empty_profiles(enabled).map_err(|e| e.to_string())?;
+    }
+    
+    with_config_service(&app_state, |config| {
+        config.set_allow_empty_profiles(enabled);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+async fn update_preserve_model_on_switch(
+    enabled: bool,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_preserve_model_on_switch(enabled).map_err(|e| e.to_string())?;
+    }
+    
+    with_config_service(&app_state, |config| {
+        config.set_preserve_partial_match_fields(enabled);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+async fn update_status_glyphs(
+    glyphs: StatusGlyphs,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_status_glyphs(glyphs).map_err(|e| e.to_string())?;
+    }
+    
+    let app = app_state.lock().map_err(|e| format!("Failed to lock app state: {}", e))?;
+    app.set_status_glyphs(glyphs);
+    Ok(())
+}
+
+#[tauri::command]
+async fn would_auto_detect(path: String) -> Result<bool, String> {
+    Ok(claude_detector::ClaudeDetector::would_auto_detect(std::path::Path::new(&path)))
+}
+
+#[tauri::command]
+async fn update_max_profile_size(
+    max_bytes: u64,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_max_profile_size(max_bytes).map_err(|e| e.to_string())?;
+    }
+    
+    // Takes effect on the next scan
+    with_config_service(&app_state, |config| {
+        config.set_max_profile_size(max_bytes);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+async fn update_backup_dir(
+    path: Option<String>,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    let backup_dir = path.map(std::path::PathBuf::from);
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_backup_dir(backup_dir.clone()).map_err(|e| e.to_string())?;
+    }
+    
+    with_config_service(&app_state, |config| {
+        config.set_backup_dir(backup_dir);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+async fn update_backup_max_age_days(
+    days: Option<u64>,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<(), String> {
+    {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.update_backup_max_age_days(days).map_err(|e| e.to_string())?;
+    }
+    
+    with_config_service(&app_state, |config| {
+        config.set_backup_max_age_days(days);
+        Ok(())
+    })
+}
+
+#[tauri::command]
+async fn add_extra_monitored_file(
+    path: String,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<bool, String> {
+    log::info!("add_extra_monitored_file called: {}", path);
+    let path = std::path::PathBuf::from(path);
+    settings_service::SettingsService::validate_extra_monitored_file(&path).map_err(|e| e.to_string())?;
+    
+    // Only saved once the monitor accepted it, so the list never shows a file that isn't watched
+    app_state.lock()
+        .map_err(|e| format!("Failed to lock app state: {}", e))?
+        .watch_extra_file(&path)
+        .map_err(|e| e.to_string())?;
+    
+    let added = settings_state.lock()
+        .map_err(|e| format!("Failed to lock settings service: {}", e))
+        .and_then(|mut settings| settings.add_extra_monitored_file(&path).map_err(|e| e.to_string()));
+    if added.is_err() {
+        if let Ok(app) = app_state.lock() {
+            let _ = app.unwatch_extra_file(&path);
+        }
+    }
+    added
+}
+
+#[tauri::command]
+async fn remove_extra_monitored_file(
+    path: String,
+    settings_state: tauri::State<'_, Mutex<settings_service::SettingsService>>,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<bool, String> {
+    log::info!("remove_extra_monitored_file called: {}", path);
+    let path = std::path::PathBuf::from(path);
+    let removed = {
+        let mut settings = settings_state.lock()
+            .map_err(|e| format!("Failed to lock settings service: {}", e))?;
+        settings.remove_extra_monitored_file(&path).map_err(|e| e.to_string())?
+    };
+    
+    let app = app_state.lock().map_err(|e| format!("Failed to lock app state: {}", e))?;
+    app.unwatch_extra_file(&path).map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+#[tauri::command]
+async fn validate_claude_directory(path: String) -> Result<DirectoryValidation, String> {
+    log::info!("validate_claude_directory called: {}", path);
+    Ok(claude_detector::ClaudeDetector::test_directory(std::path::Path::new(&path)))
+}
+
+#[tauri::command]
+async fn import_profile(
+    name: String,
+    overwrite: bool,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<bool, ErrorDto> {
+    use tauri_plugin_dialog::DialogExt;
+    
+    log::info!("import_profile called: {}", name);
+    
+    // The file dialog blocks until closed, keep it off the main thread and the async workers
+    let dialog = app_handle.dialog()
+        .file()
+        .set_title("Import Profile")
+        .add_filter("JSON", &["json"]);
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_pick_file())
+        .await
+        .map_err(|e| AppError::ConfigError(format!("File dialog failed: {}", e)))?;
+    let Some(file_path) = picked else {
+        log::info!("User cancelled profile import");
+        return Ok(false);
+    };
+    
+    let src = file_path.into_path()
+        .map_err(|e| AppError::FileSystemError(format!("Invalid import path: {}", e)))?;
+    let summaries = with_config_service_structured(&app_state, |config| {
+        config.import_profile(&src, &name, overwrite)?;
+        Ok(config.profile_summaries())
+    })?;
+    app::emit_profiles_changed(&app_handle, summaries);
+    Ok(true)
+}
+
+#[tauri::command]
+async fn import_profile_from_clipboard(
+    name: String,
+    overwrite: bool,
+    app_handle: AppHandle,
+    app_state: tauri::State<'_, Arc<Mutex<App>>>,
+) -> Result<bool, ErrorDto> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+    
+    log::info!("import_profile_from_clipboard called: {}", name);
+    
+    let content = app_handle.clipboard().read_text()
+        .map_err(|e| AppError::ConfigError(format!("Failed to read the clipboard: {}", e)))?;
+    let validation = ConfigService::check_profile_json(&content);
+    if let Some(error) = validation.error {
+        return Err(AppError::ConfigError(format!(
+            "The clipboard doesn't contain valid JSON: {} (line {}, column {})", error.message, error.line, error.column
+        )).into());
+    }
+    if !validation.is_object {
+        return Err(AppError::ConfigError("The clipboard JSON must be an object".to_string()).into());
+    }
+    
+    // Pasted configs often carry someone's tokens, make saving them a deliberate choice
+    let secrets = ConfigService::find_secret_keys(&content)?;
+    if !secrets.is_empty() {
+        log::warn!("Clipboard profile '{}' contains secret-looking keys: {:?}", name, secrets);
+        let dialog = app_handle.dialog()
+            .message(format!(
+                "The copied settings contain secret-looking values ({}). Save them as profile '{}' anyway?",
+                secrets.join(", "), name
+            ))
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancel);
+        // The dialog blocks until closed, keep it off the main thread and the async workers
+        let confirmed = tauri::async_runtime::spawn_blocking(move || dialog.blocking_show())
+            .await
+            .map_err(|e| AppError::ConfigError(format!("Confirmation dialog failed: {}", e)))?;
+        if !confirmed {
+            log::info!("User cancelled clipboard import");
+            return Ok(false);
+        }
+    }
+    
+    let summaries = with_config_service_structured(&app_state, |config| {
+        config.import_profile_content(&name, &content, overwrite)?;
+        Ok(config.profile_summaries())
+    })?;
+    app::emit_profiles_changed(&app_handle, summaries);
+    Ok(true)
+}
+
+#[tauri::command]
+fn reload_claude_directory(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<String, String> {
+    log::info!("reload_claude_directory called");
+    
+    let mut app = match app_state.lock() {
+        Ok(guard) => guard,
+        Err(e) => {
+            log::error!("Failed to lock app state: {}", e);
+            return Err("Failed to access application state".to_string());
+        }
+    };
+    
+    let claude_dir = tauri::async_runtime::block_on(app.reload_claude_directory())
+        .map_err(|e| e.to_string())?;
+    
+    Ok(claude_dir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+async fn pick_claude_directory(app_handle: AppHandle) -> Result<Option<std::path::PathBuf>, String> {
+    log::info!("pick_claude_directory called");
+    
+    // The folder dialog blocks until closed, keep it off the main thread and the async workers
+    tauri::async_runtime::spawn_blocking(move || app::pick_and_use_claude_directory(&app_handle))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_claude_dir_info(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<ClaudeDirInfo, String> {
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    Ok(app.claude_dir_info())
+}
+
+#[tauri::command]
+async fn get_external_change(app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<Option<String>, String> {
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    Ok(app.external_change().map(str::to_string))
+}
+
+#[tauri::command]
+async fn subscribe_file(path: String, app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<(), String> {
+    log::info!("subscribe_file called: {}", path);
+    
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    app.subscribe_file(std::path::Path::new(&path)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn unsubscribe_file(path: String, app_state: tauri::State<'_, Arc<Mutex<App>>>) -> Result<bool, String> {
+    let app = app_state.lock().map_err(|e| {
+        log::error!("Failed to lock app state: {}", e);
+        "Failed to access application state".to_string()
+    })?;
+    Ok(app.unsubscribe_file(std::path::Path::new(&path)))
+}
+
 #[tauri::command]
 async fn close_settings_window(app_handle: tauri::AppHandle) -> Result<(), String> {
     if let Some(window) = app_handle.get_webview_window("settings") {
@@ -74,36 +1010,137 @@ async fn close_settings_window(app_handle: tauri::AppHandle) -> Result<(), Strin
     Ok(())
 }
 
+// Launch flag that brings up only the tray, see `App::initialize_safe_mode`
+const SAFE_MODE_FLAG: &str = "--safe-mode";
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_log::Builder::default().build())
+    let safe_mode = std::env::args().skip(1).any(|arg| arg == SAFE_MODE_FLAG);
+    let mut builder = tauri::Builder::default();
+    
+    // Must be registered first: a second launch hands off to the running
+    // instance and exits before any tray or monitor is created
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            log::info!("Another CCCS instance was launched, keeping the existing one");
+            let _ = app.emit("open_settings_window", ());
+        }));
+    }
+    
+    builder
+        // Debug is the ceiling, the verbose_logging setting picks the level actually used
+        .plugin(tauri_plugin_log::Builder::default().level(log::LevelFilter::Debug).build())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
-        .setup(setup_app)
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .setup(move |app| setup_app(app, safe_mode))
         .invoke_handler(tauri::generate_handler![
             settings_service::get_settings,
             settings_service::update_monitor_interval,
-            settings_service::update_auto_start_monitoring,
+            apply_monitor_interval,
+            settings_service::get_monitor_interval_bounds,
+            settings_service::is_settings_persistence_disabled,
+            update_auto_start_monitoring,
+            settings_service::update_monitor_limits,
+            settings_service::update_scan_error_policy,
+            settings_service::update_cache_optimize_interval,
             settings_service::update_language,
             settings_service::update_show_notifications,
+            settings_service::update_verbose_logging,
+            settings_service::update_auto_create_settings,
+            settings_service::update_power_aware_monitoring,
+            settings_service::update_detect_changes_by_mtime_only,
+            settings_service::update_startup_load_check,
+            settings_service::update_rescan_on_hover,
+            settings_service::update_switching_glyph,
             settings_service::reset_settings_to_defaults,
             i18n_service::get_current_locale,
             i18n_service::set_locale,
             i18n_service::get_text,
+            i18n_service::get_texts,
+            i18n_service::format_relative_time,
             i18n_service::get_supported_locales,
             get_profiles_info,
+            is_monitoring,
+            get_profile_names,
+            switch_profile,
+            switch_profile_by_index,
+            lock_profile,
+            unlock_profile,
+            set_profile_alias,
+            #[cfg(debug_assertions)]
+            benchmark_switch,
+            reload_claude_directory,
+            pick_claude_directory,
+            get_claude_dir_info,
+            get_external_change,
+            compare_profiles,
+            get_status_summary,
+            matches_profile,
+            diff_profiles,
+            compare_with_file,
+            get_profile_checksum,
+            get_profile_mtimes,
+            scan_profiles_with_progress,
+            list_profiles_detailed,
+            list_backups,
+            reveal_backup,
+            repair_settings,
+            get_log_path,
+            open_log_file,
+            get_diagnostics,
+            get_error_log,
+            get_current_settings_content,
+            get_profile_content,
+            preview_profile_content,
+            copy_settings_to_clipboard,
+            create_profile_from_template,
+            validate_profile_json,
+            save_profile_content,
+            format_profile,
+            format_all_profiles,
+            snapshot_current_settings,
+            update_compare_keys,
+            update_switch_mode,
+            update_env_merge,
+            update_follow_symlinks,
+            update_allow_empty_profiles,
+            update_preserve_model_on_switch,
+            update_backup_max_age_days,
+            update_backup_dir,
+            update_max_profile_size,
+            update_status_glyphs,
+            add_extra_monitored_file,
+            remove_extra_monitored_file,
+            validate_claude_directory,
+            would_auto_detect,
+            import_profile,
+            import_profile_from_clipboard,
+            subscribe_file,
+            unsubscribe_file,
             close_settings_window,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Only explicit exits carry a code; closing the last window must not stop the tray app
+            if let tauri::RunEvent::ExitRequested { code: Some(_), .. } = event {
+                log::info!("Exit requested, shutting down");
+                app::shutdown_app(app_handle);
+            }
+        });
 }
 
-fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+fn setup_app(app: &mut tauri::App, safe_mode: bool) -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
     if cfg!(debug_assertions) {
         log::info!("CCCS application starting in development mode");
     }
+    if safe_mode {
+        log::warn!("=== CCCS started with {}: no profile scan, no file monitoring ===", SAFE_MODE_FLAG);
+    }
 
     // Hide dock icon on macOS to make this a pure tray application
     #[cfg(target_os = "macos")]
@@ -130,7 +1167,12 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
             // Initialize immediately during setup
             let rt = tokio::runtime::Runtime::new().unwrap();
             rt.block_on(async {
-                match cccs_app.initialize().await {
+                let initialized = if safe_mode {
+                    cccs_app.initialize_safe_mode().await
+                } else {
+                    cccs_app.initialize().await
+                };
+                match initialized {
                     Ok(()) => {
                         log::info!("CCCS application initialized successfully");
                     }
@@ -141,8 +1183,16 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
                 }
             });
             
+            let needs_claude_dir_selection = cccs_app.needs_claude_dir_selection();
+            
             // Store the initialized app instance
             app.manage(Arc::new(Mutex::new(cccs_app)));
+            
+            if needs_claude_dir_selection {
+                app::prompt_for_claude_directory(app_handle.clone());
+            } else if !safe_mode {
+                app::report_profile_load_errors(app_handle.clone());
+            }
         }
         Err(e) => {
             log::error!("Failed to create CCCS app: {}", e);
@@ -159,6 +1209,9 @@ fn initialize_services(app: &mut tauri::App) -> Result<(), Box<dyn std::error::E
     // Initialize settings service
     match settings_service::SettingsService::new() {
         Ok(settings_service) => {
+            settings_service::SettingsService::apply_log_level(
+                settings_service.get_current_settings().verbose_logging
+            );
             app.manage(std::sync::Mutex::new(settings_service));
             log::info!("Settings service initialized");
         }
@@ -175,6 +1228,8 @@ fn initialize_services(app: &mut tauri::App) -> Result<(), Box<dyn std::error::E
     app.manage(std::sync::Mutex::new(i18n_service));
     log::info!("I18n service initialized");
 
+    app.manage(app::ActiveProfileTracker::default());
+
     // Note: Config service will be initialized as part of the App instance
 
     Ok(())
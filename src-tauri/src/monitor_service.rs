@@ -1,25 +1,51 @@
 // File monitoring service for configuration changes
-use crate::{AppError, AppResult, FileMetadata, ConfigFileChange, ChangeType, MonitoringStats};
+use crate::{
+    AppError, AppResult, FileMetadata, ConfigFileChange, ChangeType, MonitoringStats,
+    DEFAULT_MAX_MONITORED_FILES, DEFAULT_MAX_METADATA_CACHE, DEFAULT_MAX_SCAN_ERRORS, DEFAULT_MAX_BACKOFF_SECONDS,
+    DEFAULT_CACHE_OPTIMIZE_INTERVAL_SCANS,
+    settings_service::{MIN_MONITOR_INTERVAL_MINUTES, MAX_MONITOR_INTERVAL_MINUTES},
+};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::time::{interval, Interval};
 
+// How often a subscribed file is checked, much faster than the global scan
+const SUBSCRIPTION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// With power-aware monitoring, only every Nth scheduled scan runs while on battery
+const BATTERY_INTERVAL_MULTIPLIER: u64 = 3;
+
+// Called with the changes found by a scheduled scan
+type ChangeCallback = Arc<dyn Fn(Vec<ConfigFileChange>) + Send + Sync>;
+
 pub struct MonitorService {
     monitored_files: Vec<PathBuf>,
     file_metadata: Arc<Mutex<HashMap<PathBuf, FileMetadata>>>,
     monitor_interval_minutes: u64,
     timer: Option<Interval>,
     is_running: Arc<Mutex<bool>>,
-    // Performance optimization: limit metadata cache size
+    // Performance optimization: limit monitored files and metadata cache size
+    max_monitored_files: usize,
     max_cache_size: usize,
     scan_error_count: Arc<Mutex<u32>>,
     max_scan_errors: u32,
+    max_backoff_secs: u64,
+    // Stale metadata is pruned every this many scans
+    cache_optimize_interval: u64,
+    last_scan_time: Arc<Mutex<Option<u64>>>,
+    power_aware: bool,
+    mtime_only: bool,
+    effective_interval_minutes: Arc<Mutex<u64>>,
+    // Files watched individually, each with the running flag of its poll task
+    subscriptions: HashMap<PathBuf, Arc<Mutex<bool>>>,
+    // Callback of the last start, so monitoring can restart with a new interval
+    callback: Option<ChangeCallback>,
 }
 
 impl MonitorService {
-    /// Create a new monitor service with specified interval
+    /// Create a new monitor service with specified interval and default limits
     pub fn new(interval_minutes: u64) -> Self {
         Self {
             monitored_files: Vec::new(),
@@ -27,22 +53,191 @@ impl MonitorService {
             monitor_interval_minutes: interval_minutes,
             timer: None,
             is_running: Arc::new(Mutex::new(false)),
-            max_cache_size: 100, // Limit cache to 100 files to manage memory
+            max_monitored_files: DEFAULT_MAX_MONITORED_FILES,
+            max_cache_size: DEFAULT_MAX_METADATA_CACHE, // Limit cache size to manage memory
             scan_error_count: Arc::new(Mutex::new(0)),
-            max_scan_errors: 10, // Stop scanning after 10 consecutive errors
+            max_scan_errors: DEFAULT_MAX_SCAN_ERRORS, // Stop scanning after this many consecutive errors
+            max_backoff_secs: DEFAULT_MAX_BACKOFF_SECONDS,
+            cache_optimize_interval: DEFAULT_CACHE_OPTIMIZE_INTERVAL_SCANS,
+            last_scan_time: Arc::new(Mutex::new(None)),
+            power_aware: false,
+            mtime_only: false,
+            effective_interval_minutes: Arc::new(Mutex::new(interval_minutes)),
+            subscriptions: HashMap::new(),
+            callback: None,
         }
     }
     
-    /// Set the monitoring interval (1-60 minutes) with performance optimization
-    pub fn set_monitor_interval(&mut self, minutes: u64) -> AppResult<()> {
-        if !(1..=60).contains(&minutes) {
+    /// Create a new monitor service with custom file and metadata cache limits
+    pub fn with_limits(interval_minutes: u64, max_monitored_files: usize, max_cache_size: usize) -> AppResult<Self> {
+        Self::validate_limits(max_monitored_files, max_cache_size)?;
+        
+        let mut service = Self::new(interval_minutes);
+        service.max_monitored_files = max_monitored_files;
+        service.max_cache_size = max_cache_size;
+        Ok(service)
+    }
+    
+    /// Validate monitoring limits: 1-1000 files and a cache at least that large (max 10000)
+    pub fn validate_limits(max_monitored_files: usize, max_cache_size: usize) -> AppResult<()> {
+        if !(1..=1000).contains(&max_monitored_files) {
             return Err(AppError::MonitorError(
-                format!("Invalid monitor interval: {} minutes. Must be between 1 and 60.", minutes)
+                format!("Invalid max monitored files: {}. Must be between 1 and 1000.", max_monitored_files)
             ));
         }
         
+        if !(max_monitored_files..=10000).contains(&max_cache_size) {
+            return Err(AppError::MonitorError(
+                format!("Invalid max metadata cache: {}. Must be between {} and 10000.", max_cache_size, max_monitored_files)
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    /// Validate scan error handling: 1-100 consecutive errors and a 1-3600 second backoff cap
+    pub fn validate_scan_error_policy(max_scan_errors: u32, max_backoff_secs: u64) -> AppResult<()> {
+        if !(1..=100).contains(&max_scan_errors) {
+            return Err(AppError::MonitorError(
+                format!("Invalid max scan errors: {}. Must be between 1 and 100.", max_scan_errors)
+            ));
+        }
+        
+        if !(1..=3600).contains(&max_backoff_secs) {
+            return Err(AppError::MonitorError(
+                format!("Invalid max backoff: {} seconds. Must be between 1 and 3600.", max_backoff_secs)
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    /// Set how many consecutive scan errors stop monitoring and the backoff cap between them
+    pub fn set_scan_error_policy(&mut self, max_scan_errors: u32, max_backoff_secs: u64) -> AppResult<()> {
+        Self::validate_scan_error_policy(max_scan_errors, max_backoff_secs)?;
+        
+        self.max_scan_errors = max_scan_errors;
+        self.max_backoff_secs = max_backoff_secs;
+        log::info!("Scan error policy set: stop after {} errors, backoff up to {}s", max_scan_errors, max_backoff_secs);
+        Ok(())
+    }
+    
+    /// Validate the cache optimization interval: every 1-1000 scans
+    pub fn validate_cache_optimize_interval(scans: u64) -> AppResult<()> {
+        if !(1..=1000).contains(&scans) {
+            return Err(AppError::MonitorError(
+                format!("Invalid cache optimization interval: {} scans. Must be between 1 and 1000.", scans)
+            ));
+        }
+        Ok(())
+    }
+    
+    /// Set how many scans the monitor runs between pruning stale metadata (applied on next start)
+    pub fn set_cache_optimize_interval(&mut self, scans: u64) -> AppResult<()> {
+        Self::validate_cache_optimize_interval(scans)?;
+        
+        self.cache_optimize_interval = scans;
+        log::info!("Cache optimization interval set to every {} scans", scans);
+        Ok(())
+    }
+    
+    /// Backoff to wait after a failed scan, or None when monitoring should stop
+    fn scan_error_backoff(consecutive_errors: u32, max_scan_errors: u32, max_backoff_secs: u64) -> Option<Duration> {
+        if consecutive_errors >= max_scan_errors {
+            return None;
+        }
+        
+        // Exponential backoff for errors
+        Some(Duration::from_secs(std::cmp::min(max_backoff_secs, 30 * consecutive_errors as u64)))
+    }
+    
+    /// Scan less often while on battery (applied on next start)
+    pub fn set_power_aware(&mut self, enabled: bool) {
+        self.power_aware = enabled;
+        log::info!("Power-aware monitoring set to: {}", enabled);
+    }
+    
+    /// Detect changes by modification time and size only, without hashing file content (applied on next start)
+    ///
+    /// Saves reading every monitored file on each scan, but an edit that keeps both the
+    /// size and the modification time (e.g. restored timestamps) goes unnoticed.
+    pub fn set_mtime_only(&mut self, enabled: bool) {
+        self.mtime_only = enabled;
+        log::info!("Modification-time-only change detection set to: {}", enabled);
+    }
+    
+    /// Interval actually used between scans, falling back to the configured one when the power state is unknown
+    fn effective_interval(interval_minutes: u64, power_aware: bool, on_battery: Option<bool>) -> u64 {
+        if power_aware && on_battery == Some(true) {
+            interval_minutes * BATTERY_INTERVAL_MULTIPLIER
+        } else {
+            interval_minutes
+        }
+    }
+    
+    /// Whether the machine is running on battery, None if it can't be determined
+    #[cfg(target_os = "linux")]
+    fn on_battery() -> Option<bool> {
+        let mut has_mains = false;
+        let mut battery_discharging = false;
+        
+        for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+            let read = |name: &str| std::fs::read_to_string(entry.path().join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default();
+            
+            match read("type").as_str() {
+                "Mains" => {
+                    if read("online") == "1" {
+                        return Some(false);
+                    }
+                    has_mains = true;
+                }
+                "Battery" => battery_discharging |= read("status") == "Discharging",
+                _ => {}
+            }
+        }
+        
+        if has_mains || battery_discharging {
+            Some(true)
+        } else {
+            None
+        }
+    }
+    
+    /// Whether the machine is running on battery, None if it can't be determined
+    #[cfg(target_os = "macos")]
+    fn on_battery() -> Option<bool> {
+        let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+        let output = String::from_utf8_lossy(&output.stdout);
+        
+        if output.contains("'Battery Power'") {
+            Some(true)
+        } else if output.contains("'AC Power'") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+    
+    /// Whether the machine is running on battery, None if it can't be determined
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn on_battery() -> Option<bool> {
+        None
+    }
+    
+    /// Set the monitoring interval (1-60 minutes) with performance optimization
+    pub fn set_monitor_interval(&mut self, minutes: u64) -> AppResult<()> {
+        if !(MIN_MONITOR_INTERVAL_MINUTES..=MAX_MONITOR_INTERVAL_MINUTES).contains(&minutes) {
+            return Err(AppError::MonitorError(format!(
+                "Invalid monitor interval: {} minutes. Must be between {} and {}.",
+                minutes, MIN_MONITOR_INTERVAL_MINUTES, MAX_MONITOR_INTERVAL_MINUTES
+            )));
+        }
+        
         let old_interval = self.monitor_interval_minutes;
         self.monitor_interval_minutes = minutes;
+        *self.effective_interval_minutes.lock().unwrap() = minutes;
         log::info!("Monitor interval changed from {} to {} minutes", old_interval, minutes);
         
         // Only restart if interval changed significantly (more than 30 seconds difference)
@@ -54,16 +249,18 @@ impl MonitorService {
     }
     
     /// Add a file to the monitoring list with validation
-    pub fn add_file_to_monitor(&mut self, path: PathBuf) {
+    ///
+    /// Returns whether the file is monitored afterwards, false when it was refused.
+    pub fn add_file_to_monitor(&mut self, path: PathBuf) -> bool {
         // Validate file path
         if !path.exists() {
             log::warn!("Cannot monitor non-existent file: {:?}", path);
-            return;
+            return false;
         }
         
         if !path.is_file() {
             log::warn!("Cannot monitor non-file path: {:?}", path);
-            return;
+            return false;
         }
         
         // Check file size - don't monitor very large files (>10MB)
@@ -72,46 +269,51 @@ impl MonitorService {
             if metadata.len() > MAX_FILE_SIZE {
                 log::warn!("File too large to monitor ({}MB): {:?}", 
                     metadata.len() / (1024 * 1024), path);
-                return;
+                return false;
             }
         }
         
         if !self.monitored_files.contains(&path) {
+            // Enforce maximum number of monitored files, keeping the ones already monitored
+            if self.monitored_files.len() >= self.max_monitored_files {
+                log::warn!("Monitored file limit ({}) reached, not monitoring {:?}. Increase max_monitored_files in settings to monitor more files.",
+                    self.max_monitored_files, path);
+                return false;
+            }
+            
             log::info!("Adding file to monitor: {:?}", path);
             self.monitored_files.push(path);
-            
-            // Enforce maximum number of monitored files
-            if self.monitored_files.len() > 50 { // Reasonable limit
-                log::warn!("Too many files being monitored ({}), removing oldest", self.monitored_files.len());
-                let removed = self.monitored_files.remove(0);
-                log::info!("Removed from monitoring: {:?}", removed);
-                
-                // Also remove from metadata cache
-                if let Ok(mut metadata_map) = self.file_metadata.lock() {
-                    metadata_map.remove(&removed);
-                }
-            }
         }
+        true
     }
     
     /// Optimize metadata cache by removing old entries
     fn optimize_metadata_cache(&self) {
         if let Ok(mut metadata_map) = self.file_metadata.lock() {
             if metadata_map.len() > self.max_cache_size {
-                // Keep only files that are still being monitored
-                let monitored_set: std::collections::HashSet<_> = self.monitored_files.iter().collect();
-                metadata_map.retain(|path, _| monitored_set.contains(path));
-                
+                Self::prune_metadata(&mut metadata_map, &self.monitored_files);
                 log::debug!("Optimized metadata cache, kept {} entries", metadata_map.len());
             }
         }
     }
     
+    /// Keep only metadata of files that are still being monitored, returns how many entries were removed
+    fn prune_metadata(metadata_map: &mut HashMap<PathBuf, FileMetadata>, monitored_files: &[PathBuf]) -> usize {
+        let monitored_set: std::collections::HashSet<_> = monitored_files.iter().collect();
+        let before = metadata_map.len();
+        metadata_map.retain(|path, _| monitored_set.contains(path));
+        before - metadata_map.len()
+    }
+    
     /// Start monitoring with a callback function and enhanced error handling
     pub fn start_monitoring<F>(&mut self, callback: F) -> AppResult<()>
     where
         F: Fn(Vec<ConfigFileChange>) + Send + Sync + 'static,
     {
+        self.start_with_callback(Arc::new(callback))
+    }
+    
+    fn start_with_callback(&mut self, callback: ChangeCallback) -> AppResult<()> {
         log::info!("Starting file monitoring with {} minute interval", self.monitor_interval_minutes);
         
         // Validate that we have files to monitor
@@ -119,13 +321,19 @@ impl MonitorService {
             log::warn!("No files to monitor, starting monitoring anyway");
         }
         
-        let callback = Arc::new(callback);
+        self.callback = Some(Arc::clone(&callback));
         let monitored_files = self.monitored_files.clone();
         let file_metadata = Arc::clone(&self.file_metadata);
-        let is_running = Arc::clone(&self.is_running);
         let scan_error_count = Arc::clone(&self.scan_error_count);
+        let last_scan_time = Arc::clone(&self.last_scan_time);
         let interval_minutes = self.monitor_interval_minutes;
         let max_scan_errors = self.max_scan_errors;
+        let max_backoff_secs = self.max_backoff_secs;
+        let cache_optimize_interval = self.cache_optimize_interval;
+        let power_aware = self.power_aware;
+        let mtime_only = self.mtime_only;
+        let effective_interval_minutes = Arc::clone(&self.effective_interval_minutes);
+        *effective_interval_minutes.lock().unwrap() = interval_minutes;
         
         // Initialize file metadata with error handling
         if let Err(e) = self.initialize_file_metadata() {
@@ -136,8 +344,10 @@ impl MonitorService {
         // Reset error count
         *scan_error_count.lock().unwrap() = 0;
         
-        // Mark as running
-        *is_running.lock().unwrap() = true;
+        // Mark as running with a fresh flag so a previously stopped task
+        // can't be revived by restarting before its next tick
+        self.is_running = Arc::new(Mutex::new(true));
+        let is_running = Arc::clone(&self.is_running);
         
         // Create timer interval
         let mut timer = interval(Duration::from_secs(interval_minutes * 60));
@@ -145,6 +355,8 @@ impl MonitorService {
         // Spawn monitoring task with error resilience
         tokio::spawn(async move {
             let mut consecutive_errors = 0u32;
+            let mut minutes_since_scan: Option<u64> = None;  // None until the first scan
+            let mut scans_since_optimize = 0u64;
             
             loop {
                 timer.tick().await;
@@ -155,12 +367,28 @@ impl MonitorService {
                     break;
                 }
                 
+                // On battery the timer keeps its pace but only every few ticks scan
+                if power_aware {
+                    let effective = Self::effective_interval(interval_minutes, power_aware, Self::on_battery());
+                    *effective_interval_minutes.lock().unwrap() = effective;
+                    
+                    if let Some(elapsed) = minutes_since_scan.as_mut() {
+                        *elapsed += interval_minutes;
+                        if *elapsed < effective {
+                            log::debug!("On battery, skipping scheduled scan");
+                            continue;
+                        }
+                    }
+                    minutes_since_scan = Some(0);
+                }
+                
                 log::debug!("Performing scheduled file scan");
                 
-                match Self::perform_scan_optimized(&monitored_files, &file_metadata).await {
+                match Self::perform_scan_optimized(&monitored_files, &file_metadata, mtime_only).await {
                     Ok(changes) => {
                         consecutive_errors = 0;
                         *scan_error_count.lock().unwrap() = 0;
+                        Self::record_scan_time(&last_scan_time);
                         
                         if !changes.is_empty() {
                             log::info!("Detected {} file changes", changes.len());
@@ -174,21 +402,24 @@ impl MonitorService {
                         log::error!("Error during file scan (attempt {}): {}", consecutive_errors, e);
                         
                         // Stop monitoring after too many consecutive errors
-                        if consecutive_errors >= max_scan_errors {
+                        let Some(backoff_duration) = Self::scan_error_backoff(consecutive_errors, max_scan_errors, max_backoff_secs) else {
                             log::error!("Too many consecutive scan errors ({}), stopping monitoring", consecutive_errors);
                             *is_running.lock().unwrap() = false;
                             break;
-                        }
+                        };
                         
-                        // Exponential backoff for errors
-                        let backoff_duration = Duration::from_secs(std::cmp::min(300, 30 * consecutive_errors as u64)); // Max 5 minutes
                         log::info!("Backing off for {} seconds due to scan errors", backoff_duration.as_secs());
                         tokio::time::sleep(backoff_duration).await;
                     }
                 }
                 
-                // Periodic cache optimization would need proper cycle counting
-                // This is a placeholder for future implementation
+                // Periodic cache optimization, so files that churn don't grow the metadata map forever
+                scans_since_optimize += 1;
+                if scans_since_optimize >= cache_optimize_interval {
+                    scans_since_optimize = 0;
+                    let removed = Self::prune_metadata(&mut file_metadata.lock().unwrap(), &monitored_files);
+                    log::info!("Optimized metadata cache after {} scans, removed {} stale entries", cache_optimize_interval, removed);
+                }
             }
             
             log::info!("Monitor service task terminated");
@@ -198,11 +429,99 @@ impl MonitorService {
         Ok(())
     }
     
+    /// Watch a single file, calling `callback` within about a second of each change
+    ///
+    /// Independent of the global monitor, so it works while monitoring is stopped.
+    /// Subscribing to a file that is already subscribed does nothing.
+    pub fn subscribe_file<F>(&mut self, path: PathBuf, callback: F)
+    where
+        F: Fn(ConfigFileChange) + Send + Sync + 'static,
+    {
+        if self.subscriptions.contains_key(&path) {
+            log::debug!("Already subscribed to {:?}", path);
+            return;
+        }
+        
+        log::info!("Subscribing to file: {:?}", path);
+        let active = Arc::new(Mutex::new(true));
+        self.subscriptions.insert(path.clone(), Arc::clone(&active));
+        let mtime_only = self.mtime_only;
+        
+        tokio::spawn(async move {
+            let mut previous = Self::get_file_metadata_optimized(&path, mtime_only).ok();
+            let mut timer = interval(SUBSCRIPTION_POLL_INTERVAL);
+            
+            loop {
+                timer.tick().await;
+                if !*active.lock().unwrap() {
+                    break;
+                }
+                
+                let current = Self::get_file_metadata_optimized(&path, mtime_only).ok();
+                if let Some(change_type) = Self::detect_change(previous.as_ref(), current.as_ref()) {
+                    log::debug!("Subscribed file changed: {:?} ({:?})", path, change_type);
+                    callback(ConfigFileChange { file_path: path.clone(), change_type });
+                }
+                previous = current;
+            }
+            
+            log::debug!("Subscription task for {:?} terminated", path);
+        });
+    }
+    
+    /// Stop watching a subscribed file, returns false if it wasn't subscribed
+    pub fn unsubscribe_file(&mut self, path: &Path) -> bool {
+        match self.subscriptions.remove(path) {
+            Some(active) => {
+                log::info!("Unsubscribed from file: {:?}", path);
+                *active.lock().unwrap() = false;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Stop watching all subscribed files
+    pub fn unsubscribe_all(&mut self) {
+        for (_, active) in self.subscriptions.drain() {
+            *active.lock().unwrap() = false;
+        }
+    }
+    
+    /// Files currently subscribed to
+    #[allow(dead_code)]
+    pub fn get_subscribed_files(&self) -> Vec<PathBuf> {
+        self.subscriptions.keys().cloned().collect()
+    }
+    
+    /// Classify the change between two polls of a file, `None` meaning missing
+    fn detect_change(previous: Option<&FileMetadata>, current: Option<&FileMetadata>) -> Option<ChangeType> {
+        match (previous, current) {
+            (None, Some(_)) => Some(ChangeType::Created),
+            (Some(_), None) => Some(ChangeType::Deleted),
+            (Some(previous), Some(current)) if Self::compare_metadata_optimized(previous, current) => {
+                Some(ChangeType::Modified)
+            }
+            _ => None,
+        }
+    }
+    
+    /// Record the current time as the last successful scan
+    fn record_scan_time(last_scan_time: &Arc<Mutex<Option<u64>>>) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        *last_scan_time.lock().unwrap() = Some(now);
+    }
+    
     /// Optimized scan with better resource management
     async fn perform_scan_optimized(
         monitored_files: &[PathBuf],
         file_metadata: &Arc<Mutex<HashMap<PathBuf, FileMetadata>>>,
+        mtime_only: bool,
     ) -> AppResult<Vec<ConfigFileChange>> {
+        let started = Instant::now();
         let mut changes = Vec::new();
         let mut scan_errors = Vec::new();
         
@@ -213,7 +532,7 @@ impl MonitorService {
         };
         
         for file_path in monitored_files {
-            match Self::scan_single_file(file_path, &cached_metadata).await {
+            match Self::scan_single_file(file_path, &cached_metadata, mtime_only).await {
                 Ok(file_changes) => {
                     changes.extend(file_changes);
                 }
@@ -230,7 +549,7 @@ impl MonitorService {
             for change in &changes {
                 match change.change_type {
                     ChangeType::Created | ChangeType::Modified => {
-                        if let Ok(new_metadata) = Self::get_file_metadata_optimized(&change.file_path) {
+                        if let Ok(new_metadata) = Self::get_file_metadata_optimized(&change.file_path, mtime_only) {
                             metadata_map.insert(change.file_path.clone(), new_metadata);
                         }
                     }
@@ -252,6 +571,10 @@ impl MonitorService {
             }
         }
         
+        log::debug!(
+            "perform_scan_optimized took {:?} ({} files, {} changes, {} errors)",
+            started.elapsed(), monitored_files.len(), changes.len(), scan_errors.len()
+        );
         Ok(changes)
     }
     
@@ -259,6 +582,7 @@ impl MonitorService {
     async fn scan_single_file(
         file_path: &PathBuf,
         cached_metadata: &HashMap<PathBuf, FileMetadata>,
+        mtime_only: bool,
     ) -> AppResult<Vec<ConfigFileChange>> {
         let mut changes = Vec::new();
         
@@ -276,7 +600,7 @@ impl MonitorService {
         }
         
         // Check if file was modified
-        match Self::get_file_metadata_optimized(file_path) {
+        match Self::get_file_metadata_optimized(file_path, mtime_only) {
             Ok(current_metadata) => {
                 let was_changed = if let Some(cached) = cached_metadata.get(file_path) {
                     // Check for modifications using optimized comparison
@@ -310,13 +634,17 @@ impl MonitorService {
     }
     
     /// Optimized metadata retrieval with lazy content reading
-    fn get_file_metadata_optimized(path: &Path) -> AppResult<FileMetadata> {
+    ///
+    /// With `mtime_only` the content isn't read and the checksum is 0, like for large files.
+    fn get_file_metadata_optimized(path: &Path, mtime_only: bool) -> AppResult<FileMetadata> {
         let metadata = std::fs::metadata(path)
             .map_err(|e| AppError::FileSystemError(format!("Failed to get file metadata: {}", e)))?;
         
         // For very large files, skip content reading and use a dummy checksum
         const MAX_CONTENT_SIZE: u64 = 1024 * 1024; // 1MB
-        let checksum = if metadata.len() > MAX_CONTENT_SIZE {
+        let checksum = if mtime_only {
+            0 // No checksum, rely on modification time and size
+        } else if metadata.len() > MAX_CONTENT_SIZE {
             log::debug!("File too large for content checksum: {:?} ({}MB)", path, metadata.len() / (1024 * 1024));
             0 // Use dummy checksum for large files, rely on modification time and size
         } else {
@@ -379,7 +707,11 @@ impl MonitorService {
             is_running: self.is_monitoring(),
             interval_minutes: self.monitor_interval_minutes,
             cache_size_limit: self.max_cache_size,
+            max_monitored_files: self.max_monitored_files,
             max_scan_errors: self.max_scan_errors,
+            max_backoff_seconds: self.max_backoff_secs,
+            last_scan_time: *self.last_scan_time.lock().unwrap(),
+            effective_interval_minutes: *self.effective_interval_minutes.lock().unwrap(),
         }
     }
     
@@ -396,13 +728,14 @@ impl MonitorService {
         *self.scan_error_count.lock().unwrap() = 0;
     }
     
-    /// Restart monitoring (used when interval changes)
-    fn restart_monitoring(&mut self) -> AppResult<()> {
-        log::info!("Restarting monitoring with new interval");
+    /// Restart monitoring (used when the interval or the monitored files change)
+    pub fn restart_monitoring(&mut self) -> AppResult<()> {
+        log::info!("Restarting monitoring");
         self.stop_monitoring();
-        // Note: The callback would need to be stored to restart properly
-        // For now, this is a placeholder that requires manual restart
-        Ok(())
+        match self.callback.clone() {
+            Some(callback) => self.start_with_callback(callback),
+            None => Ok(()),
+        }
     }
     
     /// Initialize metadata for all monitored files
@@ -411,7 +744,12 @@ impl MonitorService {
         
         for file_path in &self.monitored_files {
             if file_path.exists() {
-                match Self::get_file_metadata(file_path) {
+                let metadata = if self.mtime_only {
+                    Self::get_file_metadata_optimized(file_path, true)
+                } else {
+                    Self::get_file_metadata(file_path)
+                };
+                match metadata {
                     Ok(metadata) => {
                         metadata_map.insert(file_path.clone(), metadata);
                         log::debug!("Initialized metadata for: {:?}", file_path);
@@ -540,6 +878,14 @@ impl MonitorService {
         &self.monitored_files
     }
     
+    /// Stop monitoring a file, returns false if it wasn't monitored
+    pub fn remove_file_from_monitor(&mut self, path: &Path) -> bool {
+        let before = self.monitored_files.len();
+        self.monitored_files.retain(|file| file != path);
+        self.file_metadata.lock().unwrap().remove(path);
+        self.monitored_files.len() != before
+    }
+    
     /// Clear all monitored files
     pub fn clear_monitored_files(&mut self) {
         self.monitored_files.clear();
@@ -582,6 +928,93 @@ mod tests {
         assert!(service.set_monitor_interval(61).is_err());
     }
     
+    #[test]
+    fn test_cache_optimization() {
+        let mut service = MonitorService::new(5);
+        assert!(service.set_cache_optimize_interval(0).is_err());
+        assert!(service.set_cache_optimize_interval(1001).is_err());
+        assert!(service.set_cache_optimize_interval(20).is_ok());
+        
+        let metadata = FileMetadata { modified_time: UNIX_EPOCH, checksum: 0, size: 0 };
+        let mut metadata_map: HashMap<PathBuf, FileMetadata> = ["a.json", "b.json", "c.json"].iter()
+            .map(|name| (PathBuf::from(name), metadata.clone()))
+            .collect();
+        
+        let monitored = vec![PathBuf::from("a.json"), PathBuf::from("c.json")];
+        assert_eq!(MonitorService::prune_metadata(&mut metadata_map, &monitored), 1);
+        assert!(!metadata_map.contains_key(Path::new("b.json")));
+        assert_eq!(MonitorService::prune_metadata(&mut metadata_map, &monitored), 0);
+    }
+    
+    #[test]
+    fn test_last_scan_time_in_stats() {
+        let service = MonitorService::new(5);
+        assert_eq!(service.get_monitoring_stats().last_scan_time, None);
+        
+        MonitorService::record_scan_time(&service.last_scan_time);
+        let recorded = service.get_monitoring_stats().last_scan_time.unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert!(recorded <= now && now - recorded < 5);
+    }
+    
+    #[test]
+    fn test_effective_interval() {
+        assert_eq!(MonitorService::effective_interval(5, true, Some(true)), 5 * BATTERY_INTERVAL_MULTIPLIER);
+        assert_eq!(MonitorService::effective_interval(5, true, Some(false)), 5);
+        // Unknown power state keeps the configured interval
+        assert_eq!(MonitorService::effective_interval(5, true, None), 5);
+        assert_eq!(MonitorService::effective_interval(5, false, Some(true)), 5);
+        
+        let mut service = MonitorService::new(5);
+        service.set_power_aware(true);
+        assert_eq!(service.get_monitoring_stats().effective_interval_minutes, 5);
+    }
+    
+    #[test]
+    fn test_monitored_file_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = MonitorService::with_limits(5, 2, 10).unwrap();
+        
+        let files: Vec<_> = (0..3)
+            .map(|i| create_test_file(temp_dir.path(), &format!("file{}.json", i), "{}"))
+            .collect();
+        let added: Vec<bool> = files.iter().map(|file| service.add_file_to_monitor(file.clone())).collect();
+        
+        // Existing files are kept, the one over the limit is refused
+        assert_eq!(added, [true, true, false]);
+        assert_eq!(service.get_monitored_files(), &files[..2]);
+        
+        let stats = service.get_monitoring_stats();
+        assert_eq!(stats.max_monitored_files, 2);
+        assert_eq!(stats.cache_size_limit, 10);
+        
+        assert!(MonitorService::with_limits(5, 0, 10).is_err());
+        assert!(MonitorService::with_limits(5, 20, 10).is_err());
+        assert!(MonitorService::with_limits(5, 10, 20000).is_err());
+    }
+    
+    #[test]
+    fn test_scan_error_policy() {
+        // A lower threshold stops monitoring after fewer consecutive errors
+        assert!(MonitorService::scan_error_backoff(3, 3, 300).is_none());
+        assert!(MonitorService::scan_error_backoff(3, 10, 300).is_some());
+        
+        // Backoff grows per error up to the configured cap
+        assert_eq!(MonitorService::scan_error_backoff(2, 10, 300), Some(Duration::from_secs(60)));
+        assert_eq!(MonitorService::scan_error_backoff(9, 10, 300), Some(Duration::from_secs(270)));
+        assert_eq!(MonitorService::scan_error_backoff(9, 10, 120), Some(Duration::from_secs(120)));
+        
+        let mut service = MonitorService::new(5);
+        service.set_scan_error_policy(3, 120).unwrap();
+        let stats = service.get_monitoring_stats();
+        assert_eq!(stats.max_scan_errors, 3);
+        assert_eq!(stats.max_backoff_seconds, 120);
+        
+        assert!(service.set_scan_error_policy(0, 120).is_err());
+        assert!(service.set_scan_error_policy(101, 120).is_err());
+        assert!(service.set_scan_error_policy(3, 0).is_err());
+    }
+    
     #[test]
     fn test_add_file_to_monitor() {
         let mut service = MonitorService::new(5);
@@ -596,6 +1029,21 @@ mod tests {
         assert_eq!(service.get_monitored_files().len(), 1);
     }
     
+    #[test]
+    fn test_remove_file_from_monitor() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(temp_dir.path(), "notes.md", "notes");
+        let mut service = MonitorService::new(5);
+        
+        service.add_file_to_monitor(file_path.clone());
+        service.initialize_file_metadata().unwrap();
+        assert!(service.remove_file_from_monitor(&file_path));
+        assert!(service.get_monitored_files().is_empty());
+        assert!(service.file_metadata.lock().unwrap().is_empty());
+        
+        assert!(!service.remove_file_from_monitor(&file_path));
+    }
+    
     #[test]
     fn test_get_file_metadata() {
         let temp_dir = TempDir::new().unwrap();
@@ -630,6 +1078,81 @@ mod tests {
         assert!(MonitorService::compare_metadata(&metadata1, &metadata3));
     }
     
+    #[test]
+    fn test_mtime_only_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(temp_dir.path(), "test.json", "{}");
+        
+        let hashed = MonitorService::get_file_metadata_optimized(&file_path, false).unwrap();
+        assert_eq!(hashed.checksum, crc32fast::hash(b"{}"));
+        
+        // Without a checksum, only size and modification time tell changes apart
+        let unhashed = MonitorService::get_file_metadata_optimized(&file_path, true).unwrap();
+        assert_eq!(unhashed.checksum, 0);
+        assert_eq!(unhashed.size, 2);
+        assert!(!MonitorService::compare_metadata_optimized(&unhashed, &unhashed));
+        assert!(MonitorService::compare_metadata_optimized(&unhashed, &FileMetadata { size: 3, ..unhashed.clone() }));
+    }
+    
+    #[test]
+    fn test_detect_change() {
+        let metadata = FileMetadata {
+            modified_time: SystemTime::now(),
+            checksum: 1,
+            size: 10,
+        };
+        let modified = FileMetadata { checksum: 2, ..metadata.clone() };
+        
+        assert_eq!(MonitorService::detect_change(None, Some(&metadata)), Some(ChangeType::Created));
+        assert_eq!(MonitorService::detect_change(Some(&metadata), None), Some(ChangeType::Deleted));
+        assert_eq!(MonitorService::detect_change(Some(&metadata), Some(&modified)), Some(ChangeType::Modified));
+        assert_eq!(MonitorService::detect_change(Some(&metadata), Some(&metadata)), None);
+        assert_eq!(MonitorService::detect_change(None, None), None);
+    }
+    
+    #[tokio::test]
+    async fn test_subscribe_and_unsubscribe_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = create_test_file(temp_dir.path(), "settings.json", "{}");
+        let mut service = MonitorService::new(5);
+        
+        service.subscribe_file(file.clone(), |_| {});
+        service.subscribe_file(file.clone(), |_| {});
+        assert_eq!(service.get_subscribed_files(), vec![file.clone()]);
+        
+        assert!(service.unsubscribe_file(&file));
+        assert!(!service.unsubscribe_file(&file));
+        assert!(service.get_subscribed_files().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_start_and_stop_monitoring_updates_stats() {
+        let mut service = MonitorService::new(5);
+        assert!(!service.get_monitoring_stats().is_running);
+        
+        service.start_monitoring(|_| {}).unwrap();
+        assert!(service.get_monitoring_stats().is_running);
+        
+        service.stop_monitoring();
+        assert!(!service.get_monitoring_stats().is_running);
+    }
+    
+    #[tokio::test]
+    async fn test_set_monitor_interval_keeps_monitoring() {
+        let mut service = MonitorService::new(5);
+        service.start_monitoring(|_| {}).unwrap();
+        
+        service.set_monitor_interval(10).unwrap();
+        assert!(service.is_monitoring());
+        assert_eq!(service.get_monitoring_stats().interval_minutes, 10);
+        
+        // A stopped monitor stays stopped
+        service.stop_monitoring();
+        service.set_monitor_interval(15).unwrap();
+        assert!(!service.is_monitoring());
+        assert_eq!(service.get_monitoring_stats().effective_interval_minutes, 15);
+    }
+    
     #[tokio::test]
     async fn test_force_scan_empty() {
         let service = MonitorService::new(5);
@@ -1,12 +1,87 @@
 // Performance tests for CCCS components
-// This module is only available in test builds
+// This module is only available in debug builds, the tests themselves only in test builds
+
+use crate::{AppError, AppResult};
+use crate::config_service::ConfigService;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceTestResult {
+    pub test_name: String,
+    pub total_duration: Duration,
+    pub operations_count: u64,
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+impl PerformanceTestResult {
+    pub fn print_summary(&self) {
+        println!("=== {} ===", self.test_name);
+        println!("Success: {}", self.success);
+        if let Some(ref error) = self.error_message {
+            println!("Error: {}", error);
+        }
+        println!("Total Duration: {:?}", self.total_duration);
+        println!("Operations: {}", self.operations_count);
+        println!();
+    }
+}
+
+/// Time backup, write, verify and rollback cycles in a scratch directory under `parent`
+///
+/// The scratch directory sits next to the real settings so the numbers reflect the same drive,
+/// but settings.json itself is never touched. It's removed again afterwards.
+pub fn benchmark_switch(parent: &Path, iterations: u64) -> AppResult<PerformanceTestResult> {
+    let stamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let scratch_dir = parent.join(format!(".cccs-benchmark-{}", stamp));
+    fs::create_dir_all(&scratch_dir)
+        .map_err(|e| AppError::FileSystemError(format!("Failed to create benchmark directory: {}", e)))?;
+    
+    let result = run_switch_cycles(&scratch_dir, iterations);
+    if let Err(e) = fs::remove_dir_all(&scratch_dir) {
+        log::warn!("Failed to remove benchmark directory {:?}: {}", scratch_dir, e);
+    }
+    result
+}
+
+fn run_switch_cycles(scratch_dir: &Path, iterations: u64) -> AppResult<PerformanceTestResult> {
+    fs::write(scratch_dir.join("settings.json"), r#"{"benchmark": "original"}"#)?;
+    let service = ConfigService::new(scratch_dir.to_path_buf());
+    let content = serde_json::json!({ "benchmark": "switched", "padding": "x".repeat(4096) }).to_string();
+    
+    let started = Instant::now();
+    let mut completed = 0;
+    let mut error_message = None;
+    while completed < iterations {
+        if let Err(e) = service.switch_and_roll_back(&content) {
+            error_message = Some(e.to_string());
+            break;
+        }
+        completed += 1;
+    }
+    
+    let result = PerformanceTestResult {
+        test_name: "switch cycle".to_string(),
+        total_duration: started.elapsed(),
+        operations_count: completed,
+        success: error_message.is_none(),
+        error_message,
+    };
+    log::info!("Switch benchmark: {} cycles in {:?}", result.operations_count, result.total_duration);
+    Ok(result)
+}
 
 #[cfg(test)]
 pub mod tests {
-    use crate::{AppResult, PerformanceTestConfig};
-    use std::time::{Duration, Instant};
+    use super::*;
+    use crate::PerformanceTestConfig;
     use std::path::PathBuf;
-    use std::fs;
     use tempfile::TempDir;
     
     pub struct PerformanceTestSuite {
@@ -50,25 +125,59 @@ pub mod tests {
         }
     }
 
-    #[derive(Debug, Clone)]
-    pub struct PerformanceTestResult {
-        pub test_name: String,
-        pub total_duration: Duration,
-        pub operations_count: u64,
-        pub success: bool,
-        pub error_message: Option<String>,
+    fn timed<T>(test_name: &str, operations_count: u64, operation: impl FnOnce() -> T) -> (T, PerformanceTestResult) {
+        let started = Instant::now();
+        let output = operation();
+        let result = PerformanceTestResult {
+            test_name: test_name.to_string(),
+            total_duration: started.elapsed(),
+            operations_count,
+            success: true,
+            error_message: None,
+        };
+        result.print_summary();
+        (output, result)
     }
     
-    impl PerformanceTestResult {
-        pub fn print_summary(&self) {
-            println!("=== {} ===", self.test_name);
-            println!("Success: {}", self.success);
-            if let Some(ref error) = self.error_message {
-                println!("Error: {}", error);
-            }
-            println!("Total Duration: {:?}", self.total_duration);
-            println!("Operations: {}", self.operations_count);
-            println!();
-        }
+    #[test]
+    fn test_compare_profiles_reads_settings_once() {
+        let config = PerformanceTestConfig { file_count: 100, ..PerformanceTestConfig::default() };
+        let mut suite = PerformanceTestSuite::new().unwrap();
+        suite.setup_test_files(&config).unwrap();
+        
+        // A large settings.json makes every extra read and parse show up
+        let settings: serde_json::Map<String, serde_json::Value> = (0..1000)
+            .map(|i| (format!("key_{}", i), serde_json::json!("x".repeat(50))))
+            .collect();
+        fs::write(suite.get_test_dir().join("settings.json"), serde_json::to_string(&settings).unwrap()).unwrap();
+        
+        let mut service = ConfigService::new(suite.get_test_dir().to_path_buf());
+        let names: Vec<String> = service.scan_profiles().unwrap().into_iter().map(|p| p.name).collect();
+        let operations = names.len() as u64;
+        
+        // One profile at a time reads settings.json once per profile
+        let (individual, individual_result) = timed("status per profile", operations, || {
+            names.iter().map(|name| service.get_profile_status(name)).collect::<Vec<_>>()
+        });
+        let (batch, batch_result) = timed("compare_profiles batch", operations, || service.compare_profiles());
+        
+        assert_eq!(batch, individual);
+        assert!(batch_result.total_duration < individual_result.total_duration);
+    }
+    
+    #[test]
+    fn test_benchmark_switch_leaves_no_trace() {
+        let temp_dir = TempDir::new().unwrap();
+        let settings_path = temp_dir.path().join("settings.json");
+        fs::write(&settings_path, r#"{"theme": "dark"}"#).unwrap();
+        
+        let result = benchmark_switch(temp_dir.path(), 10).unwrap();
+        result.print_summary();
+        assert!(result.success, "{:?}", result.error_message);
+        assert_eq!(result.operations_count, 10);
+        
+        // Only the real settings.json is left, untouched
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), r#"{"theme": "dark"}"#);
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
     }
-}
\ No newline at end of file
+}
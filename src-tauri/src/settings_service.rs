@@ -1,29 +1,45 @@
 // Settings service for user configuration management
-use crate::{AppError, AppResult, UserSettings};
+use crate::{AppError, AppResult, MonitorIntervalBounds, StatusGlyphs, SwitchMode, UserSettings, WindowGeometry};
+use crate::config_service::ConfigService;
+use crate::monitor_service::MonitorService;
 use std::path::{Path, PathBuf};
 use std::fs;
 
+// Allowed monitor interval in minutes, shared by validation and the settings UI
+pub const MIN_MONITOR_INTERVAL_MINUTES: u64 = 1;
+pub const MAX_MONITOR_INTERVAL_MINUTES: u64 = 60;
+
+// Longest switching glyph in characters, enough for emoji built from several code points
+const MAX_SWITCHING_GLYPH_CHARS: usize = 8;
+
 pub struct SettingsService {
     settings_file_path: PathBuf,
     current_settings: UserSettings,
+    // Set when the settings directory isn't writable, changes then only last until exit
+    persistence_disabled: bool,
 }
 
 impl SettingsService {
     /// Create a new settings service
     pub fn new() -> AppResult<Self> {
         let settings_dir = Self::get_settings_directory()?;
-        
-        // Ensure settings directory exists
-        if !settings_dir.exists() {
-            fs::create_dir_all(&settings_dir)
-                .map_err(|e| AppError::SettingsError(format!("Failed to create settings directory: {}", e)))?;
-        }
-        
-        let settings_file_path = settings_dir.join("cccs_settings.json");
+        Self::in_directory(&settings_dir)
+    }
+    
+    fn in_directory(settings_dir: &Path) -> AppResult<Self> {
+        // Checked up front so a read-only location is reported once instead of every save failing
+        let persistence_disabled = match Self::ensure_writable(settings_dir) {
+            Ok(()) => false,
+            Err(e) => {
+                log::warn!("Settings won't be saved, {}", e);
+                true
+            }
+        };
         
         let mut service = Self {
-            settings_file_path,
+            settings_file_path: settings_dir.join("cccs_settings.json"),
             current_settings: UserSettings::default(),
+            persistence_disabled,
         };
         
         // Load existing settings or create default
@@ -31,6 +47,23 @@ impl SettingsService {
         Ok(service)
     }
     
+    /// Create the settings directory if needed and check a file can be written to it
+    fn ensure_writable(settings_dir: &Path) -> AppResult<()> {
+        fs::create_dir_all(settings_dir)
+            .map_err(|e| AppError::SettingsError(format!("Failed to create settings directory {:?}: {}", settings_dir, e)))?;
+        
+        let test_write_path = settings_dir.join("cccs_settings.json.write_test");
+        fs::write(&test_write_path, "test")
+            .map_err(|e| AppError::SettingsError(format!("Cannot write to settings directory {:?}: {}", settings_dir, e)))?;
+        let _ = fs::remove_file(&test_write_path);
+        Ok(())
+    }
+    
+    /// Whether changes are kept in memory only, because the settings directory isn't writable
+    pub fn is_persistence_disabled(&self) -> bool {
+        self.persistence_disabled
+    }
+    
     /// Create a settings service with default values (fallback)
     pub fn with_defaults() -> Self {
         let settings_file_path = dirs::config_dir()
@@ -41,6 +74,7 @@ impl SettingsService {
         Self {
             settings_file_path,
             current_settings: UserSettings::default(),
+            persistence_disabled: false,
         }
     }
     
@@ -55,7 +89,7 @@ impl SettingsService {
     fn load_or_create_settings(&mut self) -> AppResult<()> {
         if self.settings_file_path.exists() {
             self.load_settings()?;
-        } else {
+        } else if !self.persistence_disabled {
             self.save_settings(&UserSettings::default())?;
         }
         Ok(())
@@ -84,6 +118,12 @@ impl SettingsService {
     pub fn save_settings(&self, settings: &UserSettings) -> AppResult<()> {
         log::info!("Saving settings to: {:?}", self.settings_file_path);
         
+        if self.persistence_disabled {
+            return Err(AppError::SettingsNotPersisted(format!(
+                "{:?} is not writable", self.settings_file_path.parent().unwrap_or(&self.settings_file_path)
+            )));
+        }
+        
         // Validate settings before saving
         Self::validate_settings(settings)?;
         
@@ -106,6 +146,17 @@ impl SettingsService {
     /// Validate settings values
     fn validate_settings(settings: &UserSettings) -> AppResult<()> {
         Self::validate_monitor_interval(settings.monitor_interval_minutes)?;
+        Self::validate_switching_glyph(&settings.switching_glyph)?;
+        Self::validate_backup_max_age_days(settings.backup_max_age_days)?;
+        Self::validate_backup_dir(settings.backup_dir.as_deref())?;
+        MonitorService::validate_limits(settings.max_monitored_files, settings.max_metadata_cache)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
+        MonitorService::validate_scan_error_policy(settings.max_scan_errors, settings.max_backoff_seconds)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
+        MonitorService::validate_cache_optimize_interval(settings.cache_optimize_interval_scans)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
+        ConfigService::validate_max_profile_size(settings.max_profile_size_bytes)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
         
         // Validate language if specified
         if let Some(ref language) = settings.language {
@@ -121,14 +172,51 @@ impl SettingsService {
     
     /// Validate monitor interval (1-60 minutes)
     pub fn validate_monitor_interval(minutes: u64) -> AppResult<()> {
-        if !(1..=60).contains(&minutes) {
-            return Err(AppError::SettingsError(
-                format!("Invalid monitor interval: {} minutes. Must be between 1 and 60.", minutes)
-            ));
+        if !(MIN_MONITOR_INTERVAL_MINUTES..=MAX_MONITOR_INTERVAL_MINUTES).contains(&minutes) {
+            return Err(AppError::SettingsError(format!(
+                "Invalid monitor interval: {} minutes. Must be between {} and {}.",
+                minutes, MIN_MONITOR_INTERVAL_MINUTES, MAX_MONITOR_INTERVAL_MINUTES
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Validate the tray's switching glyph, a short non-empty symbol
+    pub fn validate_switching_glyph(glyph: &str) -> AppResult<()> {
+        let length = glyph.trim().chars().count();
+        if !(1..=MAX_SWITCHING_GLYPH_CHARS).contains(&length) {
+            return Err(AppError::SettingsError(format!(
+                "Invalid switching glyph: {:?}. Must be 1 to {} characters.",
+                glyph, MAX_SWITCHING_GLYPH_CHARS
+            )));
         }
         Ok(())
     }
     
+    /// Validate a user-specified file to monitor, an absolute path to an existing file
+    pub fn validate_extra_monitored_file(path: &Path) -> AppResult<()> {
+        if !path.is_absolute() {
+            return Err(AppError::SettingsError(format!(
+                "Invalid monitored file: {:?}. Must be an absolute path.", path
+            )));
+        }
+        if !path.is_file() {
+            return Err(AppError::SettingsError(format!(
+                "Invalid monitored file: {:?}. Must be an existing file.", path
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Allowed monitor interval range along with the current interval
+    pub fn monitor_interval_bounds(&self) -> MonitorIntervalBounds {
+        MonitorIntervalBounds {
+            min: MIN_MONITOR_INTERVAL_MINUTES,
+            max: MAX_MONITOR_INTERVAL_MINUTES,
+            current: self.current_settings.monitor_interval_minutes,
+        }
+    }
+    
     /// Check if a language is supported
     fn is_supported_language(language: &str) -> bool {
         matches!(language, "en" | "zh" | "zh-CN" | "zh-TW")
@@ -155,6 +243,56 @@ impl SettingsService {
         Ok(())
     }
     
+    /// Update monitored file and metadata cache limits (applied on next start)
+    pub fn update_monitor_limits(&mut self, max_monitored_files: usize, max_metadata_cache: usize) -> AppResult<()> {
+        MonitorService::validate_limits(max_monitored_files, max_metadata_cache)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
+        
+        self.current_settings.max_monitored_files = max_monitored_files;
+        self.current_settings.max_metadata_cache = max_metadata_cache;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Monitor limits updated: {} files, {} cache entries", max_monitored_files, max_metadata_cache);
+        Ok(())
+    }
+    
+    /// Update the size in bytes above which profile files are skipped during scanning
+    pub fn update_max_profile_size(&mut self, max_bytes: u64) -> AppResult<()> {
+        ConfigService::validate_max_profile_size(max_bytes)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
+        
+        self.current_settings.max_profile_size_bytes = max_bytes;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Max profile size updated to {} bytes", max_bytes);
+        Ok(())
+    }
+    
+    /// Update how many consecutive scan errors stop monitoring and the backoff cap (applied on next start)
+    pub fn update_scan_error_policy(&mut self, max_scan_errors: u32, max_backoff_seconds: u64) -> AppResult<()> {
+        MonitorService::validate_scan_error_policy(max_scan_errors, max_backoff_seconds)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
+        
+        self.current_settings.max_scan_errors = max_scan_errors;
+        self.current_settings.max_backoff_seconds = max_backoff_seconds;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Scan error policy updated: {} errors, {}s max backoff", max_scan_errors, max_backoff_seconds);
+        Ok(())
+    }
+    
+    /// Update how many scans the monitor runs between pruning stale metadata (applied on next start)
+    pub fn update_cache_optimize_interval(&mut self, scans: u64) -> AppResult<()> {
+        MonitorService::validate_cache_optimize_interval(scans)
+            .map_err(|e| AppError::SettingsError(e.to_string()))?;
+        
+        self.current_settings.cache_optimize_interval_scans = scans;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Cache optimization interval updated to every {} scans", scans);
+        Ok(())
+    }
+    
     /// Update auto start monitoring setting
     pub fn update_auto_start_monitoring(&mut self, enabled: bool) -> AppResult<()> {
         self.current_settings.auto_start_monitoring = enabled;
@@ -190,6 +328,263 @@ impl SettingsService {
         Ok(())
     }
     
+    /// Turn verbose (Debug level) logging on or off, effective immediately
+    pub fn update_verbose_logging(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.verbose_logging = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        Self::apply_log_level(enabled);
+        log::info!("Verbose logging set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Log level used for the verbose logging setting
+    pub fn log_level_filter(verbose: bool) -> log::LevelFilter {
+        if verbose {
+            log::LevelFilter::Debug
+        } else {
+            log::LevelFilter::Info
+        }
+    }
+    
+    /// Apply the verbose logging setting to the running logger
+    pub fn apply_log_level(verbose: bool) {
+        log::set_max_level(Self::log_level_filter(verbose));
+    }
+    
+    /// Update the whitelist of keys used for profile comparison (None compares everything)
+    pub fn update_compare_keys(&mut self, keys: Option<Vec<String>>) -> AppResult<()> {
+        if let Some(ref keys) = keys {
+            if keys.is_empty() || keys.iter().any(|k| k.trim().is_empty()) {
+                return Err(AppError::SettingsError(
+                    "Compare keys must be a non-empty list of non-empty keys".to_string()
+                ));
+            }
+        }
+        
+        self.current_settings.compare_keys = keys;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Compare keys set to: {:?}", self.current_settings.compare_keys);
+        Ok(())
+    }
+    
+    /// Choose whether switching replaces settings.json or merges the profile onto it
+    pub fn update_switch_mode(&mut self, mode: SwitchMode) -> AppResult<()> {
+        self.current_settings.switch_mode = mode;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Switch mode set to: {:?}", mode);
+        Ok(())
+    }
+    
+    /// Merge profiles' env blocks into settings.json's env instead of replacing the file
+    pub fn update_env_merge(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.env_merge = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Env merge set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Update whether switches write through a symlinked settings.json
+    pub fn update_follow_symlinks(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.follow_symlinks = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Follow symlinks set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Update whether switching to a profile with no keys is allowed
+    pub fn update_allow_empty_profiles(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.allow_empty_profiles = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Allow empty profiles set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Update whether switches keep the model currently in settings.json
+    pub fn update_preserve_model_on_switch(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.preserve_model_on_switch = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Preserve model on switch set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Update the glyph set used for profile statuses in the tray menu
+    pub fn update_status_glyphs(&mut self, glyphs: StatusGlyphs) -> AppResult<()> {
+        self.current_settings.status_glyphs = glyphs;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Status glyphs set to: {:?}", glyphs);
+        Ok(())
+    }
+    
+    /// Update how many days backups are kept before being pruned, None keeps them by count only
+    pub fn update_backup_max_age_days(&mut self, days: Option<u64>) -> AppResult<()> {
+        Self::validate_backup_max_age_days(days)?;
+        self.current_settings.backup_max_age_days = days;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Backup max age set to: {:?} days", days);
+        Ok(())
+    }
+    
+    /// Add a file to watch alongside settings and profiles, returns false if it was already listed
+    pub fn add_extra_monitored_file(&mut self, path: &Path) -> AppResult<bool> {
+        Self::validate_extra_monitored_file(path)?;
+        if self.current_settings.extra_monitored_files.iter().any(|file| file == path) {
+            return Ok(false);
+        }
+        
+        self.current_settings.extra_monitored_files.push(path.to_path_buf());
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Added extra monitored file: {:?}", path);
+        Ok(true)
+    }
+    
+    /// Stop watching a user-specified file, returns false if it wasn't listed
+    pub fn remove_extra_monitored_file(&mut self, path: &Path) -> AppResult<bool> {
+        let before = self.current_settings.extra_monitored_files.len();
+        self.current_settings.extra_monitored_files.retain(|file| file != path);
+        if self.current_settings.extra_monitored_files.len() == before {
+            return Ok(false);
+        }
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Removed extra monitored file: {:?}", path);
+        Ok(true)
+    }
+    
+    /// Remember the active profile for the next launch, only writing the file when it changed
+    pub fn update_last_active_profile(&mut self, profile: Option<&str>) -> AppResult<()> {
+        if self.current_settings.last_active_profile.as_deref() == profile {
+            return Ok(());
+        }
+        
+        self.current_settings.last_active_profile = profile.map(str::to_string);
+        self.save_settings(&self.current_settings)?;
+        
+        log::debug!("Last active profile set to: {:?}", profile);
+        Ok(())
+    }
+    
+    /// Update where settings.json backups are kept, None uses the Claude directory
+    pub fn update_backup_dir(&mut self, backup_dir: Option<PathBuf>) -> AppResult<()> {
+        Self::validate_backup_dir(backup_dir.as_deref())?;
+        self.current_settings.backup_dir = backup_dir;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Backup directory set to: {:?}", self.current_settings.backup_dir);
+        Ok(())
+    }
+    
+    fn validate_backup_dir(backup_dir: Option<&Path>) -> AppResult<()> {
+        match backup_dir {
+            Some(dir) if !dir.is_absolute() => Err(AppError::SettingsError(format!(
+                "Invalid backup directory: {:?}. Must be an absolute path.", dir
+            ))),
+            Some(dir) if dir.exists() && !dir.is_dir() => Err(AppError::SettingsError(format!(
+                "Invalid backup directory: {:?}. Not a directory.", dir
+            ))),
+            _ => Ok(()),
+        }
+    }
+    
+    fn validate_backup_max_age_days(days: Option<u64>) -> AppResult<()> {
+        if days == Some(0) {
+            return Err(AppError::SettingsError(
+                "Backup max age must be at least 1 day".to_string()
+            ));
+        }
+        Ok(())
+    }
+    
+    /// Remember a user-selected Claude directory (None goes back to auto-detection)
+    pub fn update_claude_dir_override(&mut self, claude_dir: Option<PathBuf>) -> AppResult<()> {
+        self.current_settings.claude_dir_override = claude_dir;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Claude directory override set to: {:?}", self.current_settings.claude_dir_override);
+        Ok(())
+    }
+    
+    /// Scan less often while on battery (applied on next start)
+    pub fn update_power_aware_monitoring(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.power_aware_monitoring = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Power-aware monitoring set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Allow creating an empty settings.json without asking when no Claude directory is found
+    pub fn update_auto_create_settings(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.auto_create_settings = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Auto-create settings.json set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Update whether the monitor detects changes by modification time and size only (applied on next start)
+    pub fn update_detect_changes_by_mtime_only(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.detect_changes_by_mtime_only = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Modification-time-only change detection set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Update the glyph shown next to a profile in the tray while switching to it
+    pub fn update_switching_glyph(&mut self, glyph: &str) -> AppResult<()> {
+        Self::validate_switching_glyph(glyph)?;
+        
+        self.current_settings.switching_glyph = glyph.trim().to_string();
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Switching glyph set to: {}", self.current_settings.switching_glyph);
+        Ok(())
+    }
+    
+    /// Update whether hovering the tray icon rescans profiles
+    pub fn update_rescan_on_hover(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.rescan_on_hover = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Rescan on hover set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Update whether profiles that failed to load are reported at startup
+    pub fn update_startup_load_check(&mut self, enabled: bool) -> AppResult<()> {
+        self.current_settings.startup_load_check = enabled;
+        self.save_settings(&self.current_settings)?;
+        
+        log::info!("Startup load check set to: {}", enabled);
+        Ok(())
+    }
+    
+    /// Remember the settings window geometry for the next time it opens
+    pub fn update_settings_window_geometry(&mut self, geometry: WindowGeometry) -> AppResult<()> {
+        let values = [geometry.x, geometry.y, geometry.width, geometry.height];
+        if values.iter().any(|v| !v.is_finite()) || geometry.width <= 0.0 || geometry.height <= 0.0 {
+            return Err(AppError::SettingsError(
+                format!("Invalid settings window geometry: {:?}", geometry)
+            ));
+        }
+        
+        self.current_settings.settings_window = Some(geometry);
+        self.save_settings(&self.current_settings)?;
+        
+        log::debug!("Settings window geometry saved: {:?}", geometry);
+        Ok(())
+    }
+    
     /// Get current settings
     pub fn get_current_settings(&self) -> &UserSettings {
         &self.current_settings
@@ -206,6 +601,7 @@ impl SettingsService {
         
         let default_settings = UserSettings::default();
         self.save_settings(&default_settings)?;
+        Self::apply_log_level(default_settings.verbose_logging);
         self.current_settings = default_settings;
         
         Ok(())
@@ -271,6 +667,25 @@ pub async fn get_settings(state: tauri::State<'_, std::sync::Mutex<SettingsServi
     Ok(service.get_current_settings().clone())
 }
 
+/// Whether settings changes are only kept in memory, for the UI to warn that they won't be saved
+///
+/// This is decided before any window exists, so the UI asks when it loads instead of waiting for an event.
+#[tauri::command]
+pub async fn is_settings_persistence_disabled(
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<bool, String> {
+    let service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    Ok(service.is_persistence_disabled())
+}
+
+#[tauri::command]
+pub async fn get_monitor_interval_bounds(
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<MonitorIntervalBounds, String> {
+    let service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    Ok(service.monitor_interval_bounds())
+}
+
 #[tauri::command]
 pub async fn update_monitor_interval(
     minutes: u64,
@@ -282,12 +697,34 @@ pub async fn update_monitor_interval(
 }
 
 #[tauri::command]
-pub async fn update_auto_start_monitoring(
-    enabled: bool,
+pub async fn update_monitor_limits(
+    max_monitored_files: usize,
+    max_metadata_cache: usize,
     state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
 ) -> Result<(), String> {
     let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
-    service.update_auto_start_monitoring(enabled)
+    service.update_monitor_limits(max_monitored_files, max_metadata_cache)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_scan_error_policy(
+    max_scan_errors: u32,
+    max_backoff_seconds: u64,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_scan_error_policy(max_scan_errors, max_backoff_seconds)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_cache_optimize_interval(
+    scans: u64,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_cache_optimize_interval(scans)
         .map_err(|e| e.to_string())
 }
 
@@ -311,6 +748,76 @@ pub async fn update_show_notifications(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn update_verbose_logging(
+    enabled: bool,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_verbose_logging(enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_power_aware_monitoring(
+    enabled: bool,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_power_aware_monitoring(enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_auto_create_settings(
+    enabled: bool,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_auto_create_settings(enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_detect_changes_by_mtime_only(
+    enabled: bool,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_detect_changes_by_mtime_only(enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_switching_glyph(
+    glyph: String,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_switching_glyph(&glyph)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_rescan_on_hover(
+    enabled: bool,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_rescan_on_hover(enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_startup_load_check(
+    enabled: bool,
+    state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
+) -> Result<(), String> {
+    let mut service = state.lock().map_err(|e| format!("Failed to lock settings service: {}", e))?;
+    service.update_startup_load_check(enabled)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn reset_settings_to_defaults(
     state: tauri::State<'_, std::sync::Mutex<SettingsService>>,
@@ -342,6 +849,32 @@ mod tests {
         // If we get here without panic, the service was created successfully
     }
     
+    #[test]
+    fn test_unwritable_settings_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        // A file where the directory should be can't be created or written into
+        let settings_dir = temp_dir.path().join("cccs");
+        fs::write(&settings_dir, "").unwrap();
+        
+        let mut service = SettingsService::in_directory(&settings_dir).unwrap();
+        assert!(service.is_persistence_disabled());
+        assert_eq!(service.get_current_settings().monitor_interval_minutes, 5);
+        
+        let result = service.update_show_notifications(false);
+        assert!(matches!(result, Err(AppError::SettingsNotPersisted(_))));
+    }
+    
+    #[test]
+    fn test_log_level_filter() {
+        assert_eq!(SettingsService::log_level_filter(true), log::LevelFilter::Debug);
+        assert_eq!(SettingsService::log_level_filter(false), log::LevelFilter::Info);
+        
+        let settings: UserSettings = serde_json::from_str(
+            r#"{"monitor_interval_minutes": 5, "auto_start_monitoring": true, "language": null, "show_notifications": true}"#
+        ).unwrap();
+        assert!(!settings.verbose_logging);
+    }
+    
     #[test]
     fn test_validate_monitor_interval() {
         assert!(SettingsService::validate_monitor_interval(1).is_ok());
@@ -352,6 +885,18 @@ mod tests {
         assert!(SettingsService::validate_monitor_interval(61).is_err());
     }
     
+    #[test]
+    fn test_monitor_interval_bounds() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        service.update_monitor_interval(15).unwrap();
+        
+        let bounds = service.monitor_interval_bounds();
+        assert_eq!(bounds, MonitorIntervalBounds { min: 1, max: 60, current: 15 });
+        assert!(SettingsService::validate_monitor_interval(bounds.min).is_ok());
+        assert!(SettingsService::validate_monitor_interval(bounds.max).is_ok());
+        assert!(SettingsService::validate_monitor_interval(bounds.max + 1).is_err());
+    }
+    
     #[test]
     fn test_is_supported_language() {
         assert!(SettingsService::is_supported_language("en"));
@@ -388,6 +933,274 @@ mod tests {
         assert!(service.update_language(Some("invalid".to_string())).is_err());
     }
     
+    #[test]
+    fn test_update_monitor_limits() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        
+        service.update_monitor_limits(200, 400).unwrap();
+        let settings = service.get_current_settings();
+        assert_eq!(settings.max_monitored_files, 200);
+        assert_eq!(settings.max_metadata_cache, 400);
+        
+        assert!(service.update_monitor_limits(0, 100).is_err());
+        assert!(service.update_scan_error_policy(0, 300).is_err());
+        service.update_scan_error_policy(25, 600).unwrap();
+        assert_eq!(service.get_current_settings().max_scan_errors, 25);
+        assert!(service.update_cache_optimize_interval(0).is_err());
+        service.update_cache_optimize_interval(30).unwrap();
+        assert_eq!(service.get_current_settings().cache_optimize_interval_scans, 30);
+        assert!(service.update_monitor_limits(100, 50).is_err());
+        assert_eq!(service.get_current_settings().max_monitored_files, 200);
+    }
+    
+    #[test]
+    fn test_update_max_profile_size() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().max_profile_size_bytes, crate::DEFAULT_MAX_PROFILE_SIZE_BYTES);
+        
+        service.update_max_profile_size(64 * 1024).unwrap();
+        assert_eq!(service.get_current_settings().max_profile_size_bytes, 64 * 1024);
+        
+        assert!(service.update_max_profile_size(0).is_err());
+        assert!(service.update_max_profile_size(1024 * 1024 * 1024).is_err());
+        assert_eq!(service.get_current_settings().max_profile_size_bytes, 64 * 1024);
+    }
+    
+    #[test]
+    fn test_settings_without_monitor_limits_use_defaults() {
+        let settings: UserSettings = serde_json::from_str(
+            r#"{"monitor_interval_minutes": 5, "auto_start_monitoring": true, "language": null, "show_notifications": true}"#
+        ).unwrap();
+        assert_eq!(settings.max_monitored_files, crate::DEFAULT_MAX_MONITORED_FILES);
+        assert_eq!(settings.max_metadata_cache, crate::DEFAULT_MAX_METADATA_CACHE);
+        assert_eq!(settings.max_scan_errors, crate::DEFAULT_MAX_SCAN_ERRORS);
+        assert_eq!(settings.max_backoff_seconds, crate::DEFAULT_MAX_BACKOFF_SECONDS);
+        assert_eq!(settings.cache_optimize_interval_scans, crate::DEFAULT_CACHE_OPTIMIZE_INTERVAL_SCANS);
+        assert_eq!(settings.max_profile_size_bytes, crate::DEFAULT_MAX_PROFILE_SIZE_BYTES);
+    }
+    
+    #[test]
+    fn test_update_compare_keys() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        
+        let keys = vec!["model".to_string(), "env.ANTHROPIC_BASE_URL".to_string()];
+        service.update_compare_keys(Some(keys.clone())).unwrap();
+        assert_eq!(service.get_current_settings().compare_keys, Some(keys));
+        
+        assert!(service.update_compare_keys(Some(vec![])).is_err());
+        assert!(service.update_compare_keys(Some(vec![" ".to_string()])).is_err());
+        
+        service.update_compare_keys(None).unwrap();
+        assert_eq!(service.get_current_settings().compare_keys, None);
+    }
+    
+    #[test]
+    fn test_update_claude_dir_override() {
+        let (mut service, temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().claude_dir_override, None);
+        
+        let claude_dir = temp_dir.path().join("claude");
+        service.update_claude_dir_override(Some(claude_dir.clone())).unwrap();
+        assert_eq!(service.get_current_settings().claude_dir_override, Some(claude_dir));
+        
+        service.update_claude_dir_override(None).unwrap();
+        assert_eq!(service.get_current_settings().claude_dir_override, None);
+    }
+    
+    #[test]
+    fn test_update_auto_create_settings() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(!service.get_current_settings().auto_create_settings);
+        
+        service.update_auto_create_settings(true).unwrap();
+        assert!(service.get_current_settings().auto_create_settings);
+    }
+    
+    #[test]
+    fn test_update_switch_mode() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().switch_mode, SwitchMode::Replace);
+        
+        service.update_switch_mode(SwitchMode::Merge).unwrap();
+        assert_eq!(service.get_current_settings().switch_mode, SwitchMode::Merge);
+        
+        let json = serde_json::to_value(service.get_current_settings()).unwrap();
+        assert_eq!(json["switch_mode"], "merge");
+    }
+    
+    #[test]
+    fn test_update_env_merge() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(!service.get_current_settings().env_merge);
+        
+        service.update_env_merge(true).unwrap();
+        assert!(service.get_current_settings().env_merge);
+    }
+    
+    #[test]
+    fn test_update_detect_changes_by_mtime_only() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(!service.get_current_settings().detect_changes_by_mtime_only);
+        
+        service.update_detect_changes_by_mtime_only(true).unwrap();
+        assert!(service.get_current_settings().detect_changes_by_mtime_only);
+    }
+    
+    #[test]
+    fn test_update_allow_empty_profiles() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(!service.get_current_settings().allow_empty_profiles);
+        
+        service.update_allow_empty_profiles(true).unwrap();
+        assert!(service.get_current_settings().allow_empty_profiles);
+    }
+    
+    #[test]
+    fn test_update_preserve_model_on_switch() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(!service.get_current_settings().preserve_model_on_switch);
+        
+        service.update_preserve_model_on_switch(true).unwrap();
+        assert!(service.get_current_settings().preserve_model_on_switch);
+    }
+    
+    #[test]
+    fn test_update_status_glyphs() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().status_glyphs, StatusGlyphs::Emoji);
+        
+        service.update_status_glyphs(StatusGlyphs::Ascii).unwrap();
+        assert_eq!(service.get_current_settings().status_glyphs, StatusGlyphs::Ascii);
+        
+        // Stored in snake_case like the other enums
+        let json = serde_json::to_string(service.get_current_settings()).unwrap();
+        assert!(json.contains(r#""status_glyphs":"ascii""#));
+    }
+    
+    #[test]
+    fn test_extra_monitored_files() {
+        let (mut service, temp_dir) = create_test_settings_service();
+        let file = temp_dir.path().join("CLAUDE.md");
+        fs::write(&file, "notes").unwrap();
+        
+        assert!(service.add_extra_monitored_file(&file).unwrap());
+        assert!(!service.add_extra_monitored_file(&file).unwrap());
+        assert_eq!(service.get_current_settings().extra_monitored_files, vec![file.clone()]);
+        
+        // Relative paths, directories and missing files are rejected
+        assert!(service.add_extra_monitored_file(Path::new("CLAUDE.md")).is_err());
+        assert!(service.add_extra_monitored_file(temp_dir.path()).is_err());
+        assert!(service.add_extra_monitored_file(&temp_dir.path().join("missing.md")).is_err());
+        
+        assert!(service.remove_extra_monitored_file(&file).unwrap());
+        assert!(!service.remove_extra_monitored_file(&file).unwrap());
+        assert!(service.get_current_settings().extra_monitored_files.is_empty());
+    }
+    
+    #[test]
+    fn test_update_backup_dir() {
+        let (mut service, temp_dir) = create_test_settings_service();
+        let backup_dir = temp_dir.path().join("backups");
+        
+        service.update_backup_dir(Some(backup_dir.clone())).unwrap();
+        assert_eq!(service.get_current_settings().backup_dir, Some(backup_dir.clone()));
+        
+        // Relative paths and files are rejected
+        assert!(service.update_backup_dir(Some(PathBuf::from("backups"))).is_err());
+        let file = temp_dir.path().join("file.txt");
+        fs::write(&file, "x").unwrap();
+        assert!(service.update_backup_dir(Some(file)).is_err());
+        assert_eq!(service.get_current_settings().backup_dir, Some(backup_dir));
+        
+        service.update_backup_dir(None).unwrap();
+        assert_eq!(service.get_current_settings().backup_dir, None);
+    }
+    
+    #[test]
+    fn test_update_backup_max_age_days() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().backup_max_age_days, None);
+        
+        service.update_backup_max_age_days(Some(30)).unwrap();
+        assert_eq!(service.get_current_settings().backup_max_age_days, Some(30));
+        
+        assert!(service.update_backup_max_age_days(Some(0)).is_err());
+        assert_eq!(service.get_current_settings().backup_max_age_days, Some(30));
+        
+        service.update_backup_max_age_days(None).unwrap();
+        assert_eq!(service.get_current_settings().backup_max_age_days, None);
+    }
+    
+    #[test]
+    fn test_update_last_active_profile() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().last_active_profile, None);
+        
+        service.update_last_active_profile(Some("work")).unwrap();
+        service.load_settings().unwrap();
+        assert_eq!(service.get_current_settings().last_active_profile.as_deref(), Some("work"));
+        
+        service.update_last_active_profile(None).unwrap();
+        assert_eq!(service.get_current_settings().last_active_profile, None);
+    }
+    
+    #[test]
+    fn test_update_follow_symlinks() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(!service.get_current_settings().follow_symlinks);
+        
+        service.update_follow_symlinks(true).unwrap();
+        assert!(service.get_current_settings().follow_symlinks);
+    }
+    
+    #[test]
+    fn test_update_switching_glyph() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().switching_glyph, crate::DEFAULT_SWITCHING_GLYPH);
+        
+        service.update_switching_glyph(" ⏳ ").unwrap();
+        assert_eq!(service.get_current_settings().switching_glyph, "⏳");
+        
+        assert!(service.update_switching_glyph("  ").is_err());
+        assert!(service.update_switching_glyph("switching").is_err());
+        assert_eq!(service.get_current_settings().switching_glyph, "⏳");
+    }
+    
+    #[test]
+    fn test_update_rescan_on_hover() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(service.get_current_settings().rescan_on_hover);
+        
+        service.update_rescan_on_hover(false).unwrap();
+        assert!(!service.get_current_settings().rescan_on_hover);
+    }
+    
+    #[test]
+    fn test_update_startup_load_check() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert!(service.get_current_settings().startup_load_check);
+        
+        service.update_startup_load_check(false).unwrap();
+        assert!(!service.get_current_settings().startup_load_check);
+    }
+    
+    #[test]
+    fn test_update_settings_window_geometry() {
+        let (mut service, _temp_dir) = create_test_settings_service();
+        assert_eq!(service.get_current_settings().settings_window, None);
+        
+        let geometry = WindowGeometry { x: 120.0, y: 80.0, width: 640.0, height: 720.0 };
+        service.update_settings_window_geometry(geometry).unwrap();
+        assert_eq!(service.get_current_settings().settings_window, Some(geometry));
+        
+        let invalid = WindowGeometry { x: 0.0, y: 0.0, width: 0.0, height: 720.0 };
+        assert!(service.update_settings_window_geometry(invalid).is_err());
+        
+        let invalid = WindowGeometry { x: f64::NAN, y: 0.0, width: 600.0, height: 720.0 };
+        assert!(service.update_settings_window_geometry(invalid).is_err());
+        assert_eq!(service.get_current_settings().settings_window, Some(geometry));
+    }
+    
     #[test]
     fn test_settings_persistence() {
         let temp_dir = TempDir::new().unwrap();
@@ -1,15 +1,36 @@
 // System tray service for CCCS
-use crate::{AppError, AppResult, Profile, ProfileStatus};
+use crate::{AppError, AppResult, Profile, ProfileStatus, StatusGlyphs};
+use crate::config_service::ConfigService;
+use std::collections::HashMap;
 use tauri::{
-    menu::{Menu, MenuBuilder, MenuItemBuilder},
+    menu::{Menu, MenuBuilder, MenuItemBuilder, Submenu, SubmenuBuilder},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter, Manager,
 };
 
+// Size of the generated fallback tray icon, in pixels
+const FALLBACK_ICON_SIZE: u32 = 32;
+// "C" glyph for the generated fallback icon, 5x7 cells, '#' is drawn
+const FALLBACK_ICON_GLYPH: [&str; 7] = [
+    ".###.",
+    "#...#",
+    "#....",
+    "#....",
+    "#....",
+    "#...#",
+    ".###.",
+];
+
 pub struct TrayService {
     app_handle: AppHandle,
     current_menu: Option<Menu<tauri::Wry>>,
     tray_id: String,
+    // Profiles and statuses the detailed menu was last built from, to rebuild it for temporary statuses
+    shown_profiles: Vec<Profile>,
+    shown_statuses: Vec<ProfileStatus>,
+    // Temporary status glyphs by profile name, e.g. ❕ while switching
+    temp_statuses: HashMap<String, String>,
+    status_glyphs: StatusGlyphs,
 }
 
 impl TrayService {
@@ -18,9 +39,18 @@ impl TrayService {
             app_handle,
             current_menu: None,
             tray_id: "cccs_tray".to_string(),
+            shown_profiles: Vec::new(),
+            shown_statuses: Vec::new(),
+            temp_statuses: HashMap::new(),
+            status_glyphs: StatusGlyphs::default(),
         }
     }
     
+    /// Choose the glyph set for profile statuses, shown from the next menu update on
+    pub fn set_status_glyphs(&mut self, glyphs: StatusGlyphs) {
+        self.status_glyphs = glyphs;
+    }
+    
     /// Create and initialize the system tray icon with enhanced error handling
     pub fn create_tray(&mut self) -> AppResult<()> {
         log::info!("Creating system tray icon");
@@ -50,6 +80,16 @@ impl TrayService {
         }
     }
     
+    /// Show only Settings and Exit, for safe mode where profiles aren't loaded
+    pub fn show_safe_mode_menu(&mut self) -> AppResult<()> {
+        let menu = self.build_fallback_menu()?;
+        if let Some(tray) = self.app_handle.tray_by_id(&self.tray_id) {
+            tray.set_menu(Some(menu.clone()))?;
+        }
+        self.current_menu = Some(menu);
+        self.set_tooltip("CCCS (safe mode)")
+    }
+    
     /// Create tray icon with safety checks
     fn create_tray_icon_safe(&self, menu: &Menu<tauri::Wry>) -> AppResult<()> {
         // Try to load tray-specific icon first, fallback to app icon
@@ -57,19 +97,24 @@ impl TrayService {
         
         match icon_result {
             Ok(icon) => {
-                log::info!("Using custom tray icon");
+                log::info!("Using custom tray icon (fallback level 0)");
                 self.create_tray_with_icon(menu, icon)
             }
             Err(_) => {
                 // Fallback to default window icon
                 let icon = match self.app_handle.default_window_icon() {
                     Some(icon) => {
-                        log::info!("Using default window icon for tray");
+                        log::info!("Using default window icon for tray (fallback level 1)");
                         icon.clone()
                     }
                     None => {
-                        log::warn!("No default window icon found, creating tray without icon");
-                        return self.create_tray_without_icon(menu);
+                        // A generated icon keeps the tray visible on desktops that hide iconless trays
+                        log::warn!("No default window icon found, using generated tray icon (fallback level 2)");
+                        let icon = Self::generate_fallback_icon(self.should_use_template_mode());
+                        return self.create_tray_with_icon(menu, icon).or_else(|e| {
+                            log::warn!("Generated tray icon failed ({}), creating tray without icon (fallback level 3)", e);
+                            self.create_tray_without_icon(menu)
+                        });
                     }
                 };
                 self.create_tray_with_icon(menu, icon)
@@ -77,6 +122,38 @@ impl TrayService {
         }
     }
     
+    /// Draw a simple "C" tray icon in memory
+    ///
+    /// Template icons are black on transparent so the OS can recolor them,
+    /// otherwise a white letter on a solid colored square.
+    fn generate_fallback_icon(template: bool) -> tauri::image::Image<'static> {
+        let (background, foreground) = if template {
+            (image::Rgba([0, 0, 0, 255]), image::Rgba([0, 0, 0, 0]))
+        } else {
+            (image::Rgba([217, 119, 87, 255]), image::Rgba([255, 255, 255, 255]))
+        };
+        
+        // Center the glyph, each glyph cell is a `scale` x `scale` block
+        let scale = 4;
+        let offset_x = (FALLBACK_ICON_SIZE - 5 * scale) / 2;
+        let offset_y = (FALLBACK_ICON_SIZE - 7 * scale) / 2;
+        let is_glyph = |x: u32, y: u32| {
+            if x < offset_x || y < offset_y {
+                return false;
+            }
+            let (col, row) = ((x - offset_x) / scale, (y - offset_y) / scale);
+            FALLBACK_ICON_GLYPH.get(row as usize)
+                .and_then(|line| line.as_bytes().get(col as usize))
+                .is_some_and(|cell| *cell == b'#')
+        };
+        
+        let img = image::RgbaImage::from_fn(FALLBACK_ICON_SIZE, FALLBACK_ICON_SIZE, |x, y| {
+            if is_glyph(x, y) { foreground } else { background }
+        });
+        
+        tauri::image::Image::new_owned(img.into_raw(), FALLBACK_ICON_SIZE, FALLBACK_ICON_SIZE)
+    }
+    
     /// Load tray-specific icon
     fn load_tray_icon(&self) -> AppResult<tauri::image::Image<'_>> {
         use std::fs;
@@ -235,6 +312,7 @@ impl TrayService {
     fn build_basic_menu(&self) -> AppResult<Menu<tauri::Wry>> {
         let menu = MenuBuilder::new(&self.app_handle)
             .separator()
+            .item(&self.build_template_submenu()?)
             .item(&MenuItemBuilder::with_id("settings", "Settings").build(&self.app_handle)?)
             .item(&MenuItemBuilder::with_id("exit", "Exit").build(&self.app_handle)?)
             .build()?;
@@ -242,6 +320,21 @@ impl TrayService {
         Ok(menu)
     }
     
+    /// Build the "New Profile from Template" submenu
+    fn build_template_submenu(&self) -> AppResult<Submenu<tauri::Wry>> {
+        let mut submenu_builder = SubmenuBuilder::with_id(
+            &self.app_handle,
+            "new_from_template",
+            "New Profile from Template…"
+        );
+        
+        for template in ConfigService::template_names() {
+            submenu_builder = submenu_builder.text(format!("template_{}", template), template);
+        }
+        
+        Ok(submenu_builder.build()?)
+    }
+    
     /// Update menu with current profiles
     pub fn update_menu(&mut self, profiles: &[Profile]) -> AppResult<()> {
         log::info!("Updating tray menu with {} profiles", profiles.len());
@@ -250,11 +343,7 @@ impl TrayService {
         
         // Add profile menu items
         for profile in profiles {
-            let menu_text = if profile.is_active {
-                format!("✅ {}", profile.name)
-            } else {
-                format!("　  {}", profile.name)  // 全角空格 + 两个普通空格
-            };
+            let menu_text = Self::status_menu_text(profile, None, self.status_glyphs);
             
             let menu_item = MenuItemBuilder::with_id(
                 format!("profile_{}", profile.name),
@@ -262,11 +351,15 @@ impl TrayService {
             ).build(&self.app_handle)?;
             
             menu_builder = menu_builder.item(&menu_item);
+            if profile.is_live {
+                menu_builder = menu_builder.separator();
+            }
         }
         
         // Add separator and system menu items
         let menu = menu_builder
             .separator()
+            .item(&self.build_template_submenu()?)
             .item(&MenuItemBuilder::with_id("settings", "Settings").build(&self.app_handle)?)
             .item(&MenuItemBuilder::with_id("exit", "Exit").build(&self.app_handle)?)
             .build()?;
@@ -283,19 +376,24 @@ impl TrayService {
     }
     
     /// Update menu with detailed profile status indicators
+    ///
+    /// Fresh statuses replace any temporary ones, so a finished switch drops its glyph here.
     pub fn update_menu_with_detailed_status(&mut self, profiles: &[Profile], statuses: &[ProfileStatus]) -> AppResult<()> {
         log::info!("Updating tray menu with {} profiles and detailed status", profiles.len());
         
+        self.shown_profiles = profiles.to_vec();
+        self.shown_statuses = statuses.to_vec();
+        self.temp_statuses.clear();
+        self.rebuild_detailed_menu()
+    }
+    
+    /// Build the detailed menu from the last shown profiles, with temporary statuses on top
+    fn rebuild_detailed_menu(&mut self) -> AppResult<()> {
         let mut menu_builder = MenuBuilder::new(&self.app_handle);
         
         // Add profile menu items with detailed status
-        for (profile, status) in profiles.iter().zip(statuses.iter()) {
-            let menu_text = match status {
-                ProfileStatus::FullMatch => format!("✅ {}", profile.name),      // 完全匹配 - 图标前置
-                ProfileStatus::PartialMatch => format!("🔄 {}", profile.name),  // 仅model字段不同 - 图标前置
-                ProfileStatus::NoMatch => format!("　  {}", profile.name),       // 配置不同，全角空格 + 两个普通空格
-                ProfileStatus::Error(_) => format!("❌ {}", profile.name),       // 错误状态 - 图标前置
-            };
+        for (profile, status) in self.shown_profiles.iter().zip(self.shown_statuses.iter()) {
+            let menu_text = Self::detailed_menu_text(profile, status, self.temp_statuses.get(&profile.name), self.status_glyphs);
             
             let menu_item = MenuItemBuilder::with_id(
                 format!("profile_{}", profile.name),
@@ -303,11 +401,15 @@ impl TrayService {
             ).build(&self.app_handle)?;
             
             menu_builder = menu_builder.item(&menu_item);
+            if profile.is_live {
+                menu_builder = menu_builder.separator();
+            }
         }
         
         // Add separator and system menu items
         let menu = menu_builder
             .separator()
+            .item(&self.build_template_submenu()?)
             .item(&MenuItemBuilder::with_id("settings", "Settings").build(&self.app_handle)?)
             .item(&MenuItemBuilder::with_id("exit", "Exit").build(&self.app_handle)?)
             .build()?;
@@ -323,6 +425,51 @@ impl TrayService {
         Ok(())
     }
     
+    /// Menu label for the detailed menu, a temporary status replaces the status icon
+    fn detailed_menu_text(profile: &Profile, status: &ProfileStatus, temp_status: Option<&String>, glyphs: StatusGlyphs) -> String {
+        match temp_status {
+            Some(temp_status) if !profile.is_live => Self::status_menu_text(profile, Some(temp_status), glyphs),
+            _ => Self::profile_menu_text(profile, status, glyphs),
+        }
+    }
+    
+    /// Menu label for a profile: status icon, then the name
+    fn profile_menu_text(profile: &Profile, status: &ProfileStatus, glyphs: StatusGlyphs) -> String {
+        let name = Self::profile_display_name(profile);
+        if profile.is_live {
+            return name;
+        }
+        format!("{}{}", glyphs.prefix(status), name)
+    }
+    
+    /// Menu item text for a profile with an optional temporary status, such as the switching glyph
+    fn status_menu_text(profile: &Profile, temp_status: Option<&str>, glyphs: StatusGlyphs) -> String {
+        let name = Self::profile_display_name(profile);
+        if profile.is_live {
+            name
+        } else if let Some(temp_status) = temp_status.filter(|s| !s.is_empty()) {
+            // Show temporary status (e.g., "❕ Profile")
+            format!("{} {}", temp_status, name)
+        } else if profile.is_active {
+            format!("{}{}", glyphs.prefix(&ProfileStatus::FullMatch), name)
+        } else {
+            format!("{}{}", glyphs.prefix(&ProfileStatus::NoMatch), name)
+        }
+    }
+    
+    /// Profile name as shown in the menu, locked profiles get a 🔒 prefix
+    ///
+    /// The live profile is shown as 📍 with no status icon, and followed by a separator.
+    fn profile_display_name(profile: &Profile) -> String {
+        if profile.is_live {
+            format!("📍 {}", profile.name)
+        } else if profile.locked {
+            format!("🔒 {}", profile.label())
+        } else {
+            profile.label().to_string()
+        }
+    }
+    
     /// Handle menu item click events with error handling
     fn handle_menu_event_safe(app: &AppHandle, event: tauri::menu::MenuEvent) -> AppResult<()> {
         let event_id = event.id().as_ref();
@@ -339,6 +486,10 @@ impl TrayService {
                 let profile_name = id.strip_prefix("profile_").unwrap_or("");
                 Self::handle_profile_click(app, profile_name)
             }
+            id if id.starts_with("template_") => {
+                let template = id.strip_prefix("template_").unwrap_or("");
+                Self::handle_template_click(app, template)
+            }
             _ => {
                 log::warn!("Unhandled menu event: {}", event_id);
                 Ok(())
@@ -406,8 +557,8 @@ impl TrayService {
     fn handle_exit_click(app: &AppHandle) -> AppResult<()> {
         log::info!("Exit menu clicked");
         
-        // Emit event for cleanup before exit
-        let _ = app.emit("app_exit_requested", ());
+        // Clean up before exiting, the ExitRequested run event then finds it already done
+        crate::app::shutdown_app(app);
         
         // Exit the application
         app.exit(0);
@@ -425,39 +576,46 @@ impl TrayService {
         Ok(())
     }
     
+    /// Handle template menu item click
+    fn handle_template_click(app: &AppHandle, template: &str) -> AppResult<()> {
+        log::info!("Template menu clicked: {}", template);
+        
+        app.emit("profile_template_requested", template)
+            .map_err(|e| AppError::TrayError(format!("Failed to emit template event: {}", e)))?;
+        
+        Ok(())
+    }
+    
     /// Show temporary status in menu item (e.g., ❕ during switch)
     pub fn update_profile_status(&mut self, profile_name: &str, status: &str) -> AppResult<()> {
         log::debug!("Updating profile status: {} -> {}", profile_name, status);
         
-        // For temporary status updates (like showing ❕ during switch)
-        // we need to rebuild the menu with updated text
-        if let Some(_current_menu) = &self.current_menu {
-            // Emit event to trigger menu refresh with temporary status
-            self.app_handle.emit("profile_status_update", (profile_name, status))
-                .map_err(|e| AppError::TrayError(format!("Failed to emit status update: {}", e)))?;
+        // An empty status clears the temporary one
+        if status.is_empty() {
+            self.temp_statuses.remove(profile_name);
+        } else {
+            self.temp_statuses.insert(profile_name.to_string(), status.to_string());
         }
         
-        Ok(())
+        // Still let the frontend know, then rebuild the menu so the tray shows it too
+        self.app_handle.emit("profile_status_update", (profile_name, status))
+            .map_err(|e| AppError::TrayError(format!("Failed to emit status update: {}", e)))?;
+        
+        if self.shown_profiles.is_empty() {
+            return Ok(());
+        }
+        self.rebuild_detailed_menu()
     }
     
     /// Update menu with profiles and temporary status indicators
-    pub fn update_menu_with_status(&mut self, profiles: &[Profile], status_updates: &std::collections::HashMap<String, String>) -> AppResult<()> {
+    pub fn update_menu_with_status(&mut self, profiles: &[Profile], status_updates: &HashMap<String, String>) -> AppResult<()> {
         log::info!("Updating tray menu with {} profiles and status updates", profiles.len());
         
         let mut menu_builder = MenuBuilder::new(&self.app_handle);
         
         // Add profile menu items with status
         for profile in profiles {
-            let menu_text = if let Some(temp_status) = status_updates.get(&profile.name) {
-                // Show temporary status (e.g., "❕ Profile")
-                format!("{} {}", temp_status, profile.name)
-            } else if profile.is_active {
-                // Show active status
-                format!("✅ {}", profile.name)
-            } else {
-                // No status - use full-width space + two normal spaces
-                format!("　  {}", profile.name)
-            };
+            let menu_text = Self::status_menu_text(profile, status_updates.get(&profile.name).map(String::as_str), self.status_glyphs);
             
             let menu_item = MenuItemBuilder::with_id(
                 format!("profile_{}", profile.name),
@@ -465,11 +623,15 @@ impl TrayService {
             ).build(&self.app_handle)?;
             
             menu_builder = menu_builder.item(&menu_item);
+            if profile.is_live {
+                menu_builder = menu_builder.separator();
+            }
         }
         
         // Add separator and system menu items
         let menu = menu_builder
             .separator()
+            .item(&self.build_template_submenu()?)
             .item(&MenuItemBuilder::with_id("settings", "Settings").build(&self.app_handle)?)
             .item(&MenuItemBuilder::with_id("exit", "Exit").build(&self.app_handle)?)
             .build()?;
@@ -516,6 +678,10 @@ mod tests {
             path: PathBuf::from(format!("{}.settings.json", name)),
             content: "{}".to_string(),
             is_active,
+            locked: false,
+            companions: Vec::new(),
+            is_live: false,
+            alias: None,
         }
     }
     
@@ -551,4 +717,81 @@ mod tests {
         assert_eq!(active_text, "✅ active");
         assert_eq!(inactive_text, "　  inactive");
     }
+    
+    #[test]
+    fn test_locked_profile_menu_text() {
+        let mut profile = create_test_profile("prod", true);
+        assert_eq!(TrayService::profile_menu_text(&profile, &ProfileStatus::FullMatch, StatusGlyphs::Emoji), "✅ prod");
+        
+        profile.locked = true;
+        assert_eq!(TrayService::profile_menu_text(&profile, &ProfileStatus::FullMatch, StatusGlyphs::Emoji), "✅ 🔒 prod");
+        
+        let mut live = create_test_profile("(current)", true);
+        live.is_live = true;
+        assert_eq!(TrayService::profile_menu_text(&live, &ProfileStatus::FullMatch, StatusGlyphs::Emoji), "📍 (current)");
+        assert_eq!(TrayService::profile_menu_text(&profile, &ProfileStatus::NoMatch, StatusGlyphs::Emoji), "　  🔒 prod");
+        
+        // An alias replaces the name in the label, the menu id keeps the real name
+        profile.alias = Some("p".to_string());
+        assert_eq!(TrayService::profile_menu_text(&profile, &ProfileStatus::FullMatch, StatusGlyphs::Emoji), "✅ 🔒 p");
+    }
+    
+    #[test]
+    fn test_switching_glyph_menu_text() {
+        let profile = create_test_profile("work", false);
+        
+        // Mid-switch the configured glyph replaces the status icon
+        assert_eq!(TrayService::status_menu_text(&profile, Some("⏳"), StatusGlyphs::Emoji), "⏳ work");
+        assert_eq!(TrayService::status_menu_text(&profile, Some(crate::DEFAULT_SWITCHING_GLYPH), StatusGlyphs::Emoji), "❕ work");
+        
+        // Once the switch is done the temporary status is cleared
+        let switched = create_test_profile("work", true);
+        assert_eq!(TrayService::status_menu_text(&switched, None, StatusGlyphs::Emoji), "✅ work");
+        assert_eq!(TrayService::status_menu_text(&profile, Some(""), StatusGlyphs::Emoji), "　  work");
+    }
+    
+    #[test]
+    fn test_detailed_menu_text_with_temporary_status() {
+        let profile = create_test_profile("work", false);
+        let glyph = crate::DEFAULT_SWITCHING_GLYPH.to_string();
+        assert_eq!(TrayService::detailed_menu_text(&profile, &ProfileStatus::NoMatch, Some(&glyph), StatusGlyphs::Emoji), "❕ work");
+        assert_eq!(TrayService::detailed_menu_text(&profile, &ProfileStatus::FullMatch, None, StatusGlyphs::Emoji), "✅ work");
+        
+        // The live profile never shows a status
+        let mut live = create_test_profile("(current)", true);
+        live.is_live = true;
+        assert_eq!(TrayService::detailed_menu_text(&live, &ProfileStatus::FullMatch, Some(&glyph), StatusGlyphs::Emoji), "📍 (current)");
+    }
+    
+    #[test]
+    fn test_ascii_and_no_status_glyphs() {
+        let profile = create_test_profile("work", false);
+        let partial = ProfileStatus::PartialMatch { fields: vec!["model".to_string()] };
+        let error = ProfileStatus::Error { message: "bad".to_string() };
+        assert_eq!(TrayService::profile_menu_text(&profile, &ProfileStatus::FullMatch, StatusGlyphs::Ascii), "[*] work");
+        assert_eq!(TrayService::profile_menu_text(&profile, &partial, StatusGlyphs::Ascii), "[~] work");
+        assert_eq!(TrayService::profile_menu_text(&profile, &error, StatusGlyphs::Ascii), "[x] work");
+        assert_eq!(TrayService::profile_menu_text(&profile, &ProfileStatus::NoMatch, StatusGlyphs::Ascii), "[ ] work");
+        assert_eq!(TrayService::status_menu_text(&profile, None, StatusGlyphs::Ascii), "[ ] work");
+        
+        assert_eq!(TrayService::profile_menu_text(&profile, &ProfileStatus::FullMatch, StatusGlyphs::None), "work");
+        assert_eq!(TrayService::profile_menu_text(&profile, &error, StatusGlyphs::None), "work");
+    }
+    
+    #[test]
+    fn test_generate_fallback_icon() {
+        let icon = TrayService::generate_fallback_icon(false);
+        assert_eq!((icon.width(), icon.height()), (FALLBACK_ICON_SIZE, FALLBACK_ICON_SIZE));
+        assert_eq!(icon.rgba().len() as u32, FALLBACK_ICON_SIZE * FALLBACK_ICON_SIZE * 4);
+        
+        // Corner is background, top of the "C" is the letter
+        assert_eq!(&icon.rgba()[0..4], &[217, 119, 87, 255]);
+        let top_of_c = ((2 * FALLBACK_ICON_SIZE + 6 + 4) * 4) as usize;
+        assert_eq!(&icon.rgba()[top_of_c..top_of_c + 4], &[255, 255, 255, 255]);
+        
+        // Template icons only use black with full or no alpha
+        let template = TrayService::generate_fallback_icon(true);
+        assert!(template.rgba().chunks(4).all(|px| px == [0, 0, 0, 255] || px == [0, 0, 0, 0]));
+        assert_eq!(&template.rgba()[top_of_c..top_of_c + 4], &[0, 0, 0, 0]);
+    }
 }
\ No newline at end of file
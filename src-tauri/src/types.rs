@@ -9,6 +9,38 @@ pub struct Profile {
     pub path: PathBuf,
     pub content: String,
     pub is_active: bool,
+    #[serde(default)]
+    pub locked: bool,       // from the profile's meta sidecar
+    #[serde(default)]
+    pub companions: Vec<CompanionFile>,  // files switched together with settings.json
+    #[serde(default)]
+    pub is_live: bool,      // the read-only "(current)" profile standing for settings.json itself
+    #[serde(default)]
+    pub alias: Option<String>,  // from the meta sidecar, shown instead of the name
+}
+
+impl Profile {
+    /// What the tray shows for the profile, its alias when it has one
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+// A file swapped in alongside settings.json, both names relative to the Claude directory.
+// Declared in a profile as `"companions": [{"target": "settings.local.json", "source": "work.local.json"}]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompanionFile {
+    pub target: String,
+    pub source: String,
+}
+
+// Per-profile CCCS metadata, stored next to the profile as `<name>.meta.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileMeta {
+    #[serde(default)]
+    pub locked: bool,       // refuse switching away from this profile while it's active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,  // shorter label for the tray, switching accepts it too
 }
 
 #[derive(Debug, Clone)]
@@ -18,12 +50,168 @@ pub struct FileMetadata {
     pub size: u64,
 }
 
+// Default monitoring limits, also used for settings files written before they existed
+pub const DEFAULT_MAX_MONITORED_FILES: usize = 50;
+pub const DEFAULT_MAX_METADATA_CACHE: usize = 100;
+pub const DEFAULT_MAX_SCAN_ERRORS: u32 = 10;
+pub const DEFAULT_MAX_BACKOFF_SECONDS: u64 = 300;
+pub const DEFAULT_CACHE_OPTIMIZE_INTERVAL_SCANS: u64 = 10;
+pub const DEFAULT_MAX_PROFILE_SIZE_BYTES: u64 = 5 * 1024 * 1024; // 5MB
+pub const DEFAULT_SWITCHING_GLYPH: &str = "❕";
+
+fn default_max_monitored_files() -> usize {
+    DEFAULT_MAX_MONITORED_FILES
+}
+
+fn default_max_metadata_cache() -> usize {
+    DEFAULT_MAX_METADATA_CACHE
+}
+
+fn default_max_scan_errors() -> u32 {
+    DEFAULT_MAX_SCAN_ERRORS
+}
+
+fn default_max_backoff_seconds() -> u64 {
+    DEFAULT_MAX_BACKOFF_SECONDS
+}
+
+fn default_cache_optimize_interval_scans() -> u64 {
+    DEFAULT_CACHE_OPTIMIZE_INTERVAL_SCANS
+}
+
+fn default_max_profile_size_bytes() -> u64 {
+    DEFAULT_MAX_PROFILE_SIZE_BYTES
+}
+
+fn default_startup_load_check() -> bool {
+    true
+}
+
+fn default_rescan_on_hover() -> bool {
+    true
+}
+
+fn default_switching_glyph() -> String {
+    DEFAULT_SWITCHING_GLYPH.to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSettings {
     pub monitor_interval_minutes: u64,
     pub auto_start_monitoring: bool,
     pub language: Option<String>,
     pub show_notifications: bool,
+    pub settings_window: Option<WindowGeometry>,
+    pub compare_keys: Option<Vec<String>>,  // only compare these keys when set
+    #[serde(default = "default_max_monitored_files")]
+    pub max_monitored_files: usize,
+    #[serde(default = "default_max_metadata_cache")]
+    pub max_metadata_cache: usize,
+    #[serde(default = "default_max_scan_errors")]
+    pub max_scan_errors: u32,
+    #[serde(default = "default_max_backoff_seconds")]
+    pub max_backoff_seconds: u64,
+    #[serde(default = "default_cache_optimize_interval_scans")]
+    pub cache_optimize_interval_scans: u64,  // prune metadata of files no longer monitored every N scans
+    #[serde(default = "default_max_profile_size_bytes")]
+    pub max_profile_size_bytes: u64,  // larger profile files are skipped during scanning
+    pub claude_dir_override: Option<PathBuf>,  // Claude directory picked by the user, tried before auto-detection
+    #[serde(default)]
+    pub verbose_logging: bool,  // log at Debug instead of Info
+    #[serde(default)]
+    pub auto_create_settings: bool,  // create an empty settings.json without asking when no Claude directory is found
+    #[serde(default)]
+    pub power_aware_monitoring: bool,  // scan less often while running on battery
+    #[serde(default)]
+    pub switch_mode: SwitchMode,
+    #[serde(default)]
+    pub env_merge: bool,  // merge a profile's env block into settings.json's env when switching
+    #[serde(default = "default_startup_load_check")]
+    pub startup_load_check: bool,  // tell the user at startup when profiles failed to load
+    #[serde(default)]
+    pub detect_changes_by_mtime_only: bool,  // skip content checksums, cheaper but misses edits that keep size and mtime
+    #[serde(default)]
+    pub follow_symlinks: bool,  // write through a symlinked settings.json instead of refusing to switch
+    #[serde(default = "default_rescan_on_hover")]
+    pub rescan_on_hover: bool,  // rescan profiles when the pointer enters the tray icon
+    #[serde(default = "default_switching_glyph")]
+    pub switching_glyph: String,  // shown next to a profile in the tray while switching to it
+    #[serde(default)]
+    pub allow_empty_profiles: bool,  // allow switching to a `{}` profile, which empties settings.json
+    #[serde(default)]
+    pub preserve_model_on_switch: bool,  // keep settings.json's model (and other partial match fields) when switching
+    #[serde(default)]
+    pub backup_max_age_days: Option<u64>,  // also prune backups older than this, the newest is always kept
+    #[serde(default)]
+    pub backup_dir: Option<PathBuf>,  // where settings.json backups go, the Claude directory's .cccs_backups when unset
+    #[serde(default)]
+    pub last_active_profile: Option<String>,  // active profile when last seen, to spot edits made while CCCS wasn't running
+    #[serde(default)]
+    pub status_glyphs: StatusGlyphs,  // glyphs in front of profiles in the tray menu
+    #[serde(default)]
+    pub extra_monitored_files: Vec<PathBuf>,  // other files to watch, changes are reported without touching profile status
+}
+
+// How a switch writes a profile to settings.json
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchMode {
+    #[default]
+    Replace,    // settings.json becomes exactly the profile
+    Merge,      // the profile is deep-merged onto settings.json, keeping keys it doesn't mention
+}
+
+// Glyph set for profile statuses in the tray menu
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusGlyphs {
+    #[default]
+    Emoji,      // ✅ 🔄 ❌
+    Ascii,      // [*] [~] [x], for fonts without emoji
+    None,       // no status glyphs at all
+}
+
+impl StatusGlyphs {
+    /// Prefix for a profile's menu label in this glyph set, including the space before the name
+    pub fn prefix(self, status: &ProfileStatus) -> &'static str {
+        self.kind_prefix(ProfileStatusKind::from(status))
+    }
+    
+    /// Bare glyph for a status in this glyph set, empty when it shows none
+    pub fn glyph(self, status: ProfileStatusKind) -> &'static str {
+        self.kind_prefix(status).trim()
+    }
+    
+    fn kind_prefix(self, status: ProfileStatusKind) -> &'static str {
+        match (self, status) {
+            (Self::Emoji, ProfileStatusKind::FullMatch) => "✅ ",
+            (Self::Emoji, ProfileStatusKind::PartialMatch) => "🔄 ",
+            (Self::Emoji, ProfileStatusKind::NoMatch) => "　  ",  // 全角空格 + 两个普通空格, as wide as an emoji
+            (Self::Emoji, ProfileStatusKind::Error) => "❌ ",
+            (Self::Ascii, ProfileStatusKind::FullMatch) => "[*] ",
+            (Self::Ascii, ProfileStatusKind::PartialMatch) => "[~] ",
+            (Self::Ascii, ProfileStatusKind::NoMatch) => "[ ] ",
+            (Self::Ascii, ProfileStatusKind::Error) => "[x] ",
+            (Self::None, _) => "",
+        }
+    }
+}
+
+// Allowed monitor interval range in minutes, for the settings slider
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MonitorIntervalBounds {
+    pub min: u64,
+    pub max: u64,
+    pub current: u64,
+}
+
+// Saved position and size of a window, in logical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
 }
 
 impl Default for UserSettings {
@@ -33,16 +221,220 @@ impl Default for UserSettings {
             auto_start_monitoring: true,
             language: None,
             show_notifications: true,
+            settings_window: None,
+            compare_keys: None,
+            max_monitored_files: DEFAULT_MAX_MONITORED_FILES,
+            max_metadata_cache: DEFAULT_MAX_METADATA_CACHE,
+            max_scan_errors: DEFAULT_MAX_SCAN_ERRORS,
+            max_backoff_seconds: DEFAULT_MAX_BACKOFF_SECONDS,
+            cache_optimize_interval_scans: DEFAULT_CACHE_OPTIMIZE_INTERVAL_SCANS,
+            max_profile_size_bytes: DEFAULT_MAX_PROFILE_SIZE_BYTES,
+            claude_dir_override: None,
+            verbose_logging: false,
+            auto_create_settings: false,
+            power_aware_monitoring: false,
+            switch_mode: SwitchMode::Replace,
+            env_merge: false,
+            startup_load_check: true,
+            detect_changes_by_mtime_only: false,
+            follow_symlinks: false,
+            rescan_on_hover: true,
+            switching_glyph: DEFAULT_SWITCHING_GLYPH.to_string(),
+            allow_empty_profiles: false,
+            preserve_model_on_switch: false,
+            backup_max_age_days: None,
+            backup_dir: None,
+            last_active_profile: None,
+            status_glyphs: StatusGlyphs::Emoji,
+            extra_monitored_files: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+// Serialized with a "status" tag, e.g. { "status": "partial_match", "fields": ["model"] }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
 pub enum ProfileStatus {
-    FullMatch,      // 完全匹配 ✅
-    PartialMatch,   // 部分匹配（忽略model字段后匹配）🔄
-    NoMatch,        // 不匹配 ❌
-    Error(String),  // 错误状态
+    FullMatch,                              // 完全匹配 ✅
+    PartialMatch { fields: Vec<String> },   // 部分匹配（忽略model字段后匹配）🔄，fields 为被忽略的不同字段
+    NoMatch,                                // 不匹配 ❌
+    Error { message: String },              // 错误状态
+}
+
+// Profile status flattened to a plain string for event payloads
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileStatusKind {
+    FullMatch,
+    PartialMatch,
+    NoMatch,
+    Error,
+}
+
+impl From<&ProfileStatus> for ProfileStatusKind {
+    fn from(status: &ProfileStatus) -> Self {
+        match status {
+            ProfileStatus::FullMatch => ProfileStatusKind::FullMatch,
+            ProfileStatus::PartialMatch { .. } => ProfileStatusKind::PartialMatch,
+            ProfileStatus::NoMatch => ProfileStatusKind::NoMatch,
+            ProfileStatus::Error { .. } => ProfileStatusKind::Error,
+        }
+    }
+}
+
+// Profile name and status sent with the profiles_updated event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub status: ProfileStatusKind,
+    pub glyph: String,
+}
+
+// Number of profiles in each status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusSummary {
+    pub full_match: usize,
+    pub partial_match: usize,
+    pub no_match: usize,
+    pub error: usize,
+}
+
+// A settings.json backup offered for restore
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub path: String,
+    pub timestamp: u64,  // Unix seconds the backup was taken
+    pub size: String,    // human readable, e.g. "1.2 KB"
+}
+
+// What repairing settings.json did, e.g. { "action": "restored_backup", "backup": "..." }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SettingsRepair {
+    NotNeeded,                          // settings.json already parses
+    RestoredBackup { backup: String },  // the most recent valid backup was restored
+    ResetToEmpty,                       // settings.json was replaced with `{}`
+}
+
+// A profile as stored on disk next to what a switch would write to settings.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileContentPreview {
+    pub raw: String,
+    pub normalized: String,
+}
+
+// Outcome of pretty-printing all profile files
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileFormatReport {
+    pub formatted: Vec<String>,  // profiles whose file was rewritten
+    pub errors: Vec<String>,     // "<profile>: <error>" for profiles left as they were
+}
+
+// A profile with everything the settings window's table shows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileDetails {
+    pub name: String,
+    pub is_active: bool,
+    pub status: ProfileStatus,
+    pub path: String,
+    pub size: u64,  // bytes of the loaded content
+}
+
+// CRC32 checksum and size of a profile file, for comparing against copies elsewhere
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileChecksum {
+    pub name: String,
+    pub checksum: u32,
+    pub size: u64,  // bytes
+}
+
+// How the Claude directory in use was found
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaudeDirSource {
+    Auto,      // ~/.claude
+    Env,       // CLAUDE_CONFIG_DIR
+    Override,  // picked by the user
+    Fallback,  // another well-known location
+    Process,   // taken from a running Claude Code process
+    Stub,      // nothing found, default ~/.claude used (created with a stub settings.json if the user declined picking)
+}
+
+impl ClaudeDirSource {
+    /// Name as serialized, for log lines
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ClaudeDirSource::Auto => "auto",
+            ClaudeDirSource::Env => "env",
+            ClaudeDirSource::Override => "override",
+            ClaudeDirSource::Fallback => "fallback",
+            ClaudeDirSource::Process => "process",
+            ClaudeDirSource::Stub => "stub",
+        }
+    }
+}
+
+// The Claude directory CCCS is reading and how it was chosen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaudeDirInfo {
+    pub path: String,
+    pub source: ClaudeDirSource,
+}
+
+impl ClaudeDirInfo {
+    /// `key=value` form for logs, e.g. `claude_dir_source=env path="/home/me/.claude"`
+    pub fn log_fields(&self) -> String {
+        format!("claude_dir_source={} path={:?}", self.source.as_str(), self.path)
+    }
+}
+
+// Which Claude product a config directory belongs to, only Claude Code has settings.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaudeProduct {
+    Code,
+    Desktop,
+    Unknown,
+}
+
+// Result of checking editor content as profile JSON, without touching disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonValidation {
+    pub valid: bool,        // parses and is an object, so it can be saved
+    pub is_object: bool,
+    pub error: Option<JsonSyntaxError>,
+}
+
+// JSON syntax error with its 1-based position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSyntaxError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+// Result of trial-loading a directory as a Claude config directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryValidation {
+    pub is_valid: bool,
+    pub product: ClaudeProduct,
+    pub profile_count: usize,
+    pub errors: Vec<String>,
+}
+
+// A single differing field between two JSON configurations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDiff {
+    pub path: String,                       // dotted path, e.g. "env.API_KEY"
+    pub left: Option<serde_json::Value>,    // None when the field is missing on the left
+    pub right: Option<serde_json::Value>,   // None when the field is missing on the right
+}
+
+// Result of comparing an arbitrary file with settings.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileComparison {
+    pub status: ProfileStatus,   // as if the file were a profile
+    pub diffs: Vec<FieldDiff>,   // left is the file, right is settings.json
 }
 
 #[derive(Debug)]
@@ -51,13 +443,28 @@ pub struct ConfigFileChange {
     pub change_type: ChangeType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ChangeType {
     Modified,
     Created,
     Deleted,
 }
 
+// Payload of the file_status_changed event sent for subscribed files
+#[derive(Debug, Clone, Serialize)]
+pub struct FileStatusChanged {
+    pub path: String,
+    pub change_type: ChangeType,
+}
+
+// Payload of the scan_progress event, counting directory entries rather than profiles
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ScanProgress {
+    pub current: usize,
+    pub total: usize,
+}
+
 // Performance monitoring statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringStats {
@@ -68,6 +475,23 @@ pub struct MonitoringStats {
     pub interval_minutes: u64,
     pub cache_size_limit: usize,
     pub max_scan_errors: u32,
+    pub max_backoff_seconds: u64,
+    pub max_monitored_files: usize,
+    pub last_scan_time: Option<u64>,  // Unix seconds of the last successful scheduled scan
+    pub effective_interval_minutes: u64,  // longer than interval_minutes while power-aware monitoring is on battery
+}
+
+// Environment details for bug reports, gathered from the services in one call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub os: String,
+    pub arch: String,
+    pub app_version: String,
+    pub claude_dir: ClaudeDirInfo,
+    pub settings_file: String,
+    pub log_file: Option<String>,  // None when logging to a file isn't enabled
+    pub locale: String,
+    pub monitoring: MonitoringStats,
 }
 
 // Performance test configuration